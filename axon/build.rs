@@ -0,0 +1,26 @@
+fn main() {
+    #[cfg(feature = "ffi")]
+    ffi_header();
+}
+
+/// 由 src/ffi.rs、src/mirror.rs 与 src/protocol.rs 中的常量生成 $OUT_DIR/bevy_axon.h，
+/// 提交的 include/bevy_axon.h 由 gen-header 刷新
+#[cfg(feature = "ffi")]
+fn ffi_header() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=src/protocol.rs");
+    println!("cargo:rerun-if-changed=src/mirror.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out = std::env::var("OUT_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", dir))
+        .expect("Failed to read cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{}/src/protocol.rs", dir))
+        .with_src(format!("{}/src/mirror.rs", dir))
+        .with_src(format!("{}/src/ffi.rs", dir))
+        .generate()
+        .expect("Failed to generate bevy_axon.h")
+        .write_to_file(format!("{}/bevy_axon.h", out));
+}
@@ -0,0 +1,635 @@
+#ifndef BEVY_AXON_H
+#define BEVY_AXON_H
+
+/* 由 cbindgen 生成，请勿手动修改；更新: cargo run --features ffi --bin gen-header */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * netcode 连接时携带的 user data 长度
+ */
+#define AXON_USER_DATA_BYTES 256
+
+/**
+ * user data 中标志位所在的字节
+ */
+#define AXON_USER_DATA_FLAGS 0
+
+/**
+ * 观战连接：只接收同步流，所有上行消息都会被拒绝
+ */
+#define AXON_FLAG_SPECTATOR (1 << 0)
+
+/**
+ * 支持压缩：服务器发来的每条消息都带 1 字节消息头。
+ * 服务器不确认该标志，客户端按首字节区分（见 axon_decode_message），不支持压缩的服务器照常发送原始动作流
+ */
+#define AXON_FLAG_COMPRESSION (1 << 1)
+
+/**
+ * 不可靠通道，编号与 renet DefaultChannel 一致
+ */
+#define AXON_CHANNEL_UNRELIABLE 0
+
+/**
+ * 可靠无序通道
+ */
+#define AXON_CHANNEL_RELIABLE_UNORDERED 1
+
+/**
+ * 可靠有序通道，同步流默认使用该通道
+ */
+#define AXON_CHANNEL_RELIABLE_ORDERED 2
+
+#define ACTION_TYPE_SPAWN 1
+
+#define ACTION_TYPE_DESPAWN 2
+
+#define ACTION_TYPE_CHANGE 3
+
+#define ACTION_TYPE_INVOKE 4
+
+/**
+ * 资源变更，id 固定为 0，t 为资源类型
+ */
+#define ACTION_TYPE_RESOURCE 5
+
+/**
+ * 对象类型变更（如 Player 换成 Corpse），t 为新类型，已同步的变体保留
+ */
+#define ACTION_TYPE_RETYPE 6
+
+/**
+ * 入场快照发送完毕，id 与 t 均为 0
+ */
+#define ACTION_TYPE_SYNC 7
+
+/**
+ * 消息头：未压缩。消息头取值不与 sbin 类型标记（0..=16）冲突，
+ * 原始动作流总是以 SBIN_U8 开头，不会被误认为消息头
+ */
+#define AXON_MESSAGE_RAW 160
+
+/**
+ * 消息头：lz4 压缩，头后为 4 字节小端原始长度与压缩数据
+ */
+#define AXON_MESSAGE_LZ4 161
+
+/**
+ * 默认压缩阈值，小于该长度的消息不压缩
+ */
+#define AXON_COMPRESSION_THRESHOLD 256
+
+/**
+ * 解压后的最大长度，防止恶意长度字段
+ */
+#define AXON_MAX_MESSAGE_BYTES ((64 * 1024) * 1024)
+
+/**
+ * 通道发送方式，对应 renet 的 SendType
+ */
+#define AXON_SEND_UNRELIABLE 0
+
+#define AXON_SEND_RELIABLE_UNORDERED 1
+
+#define AXON_SEND_RELIABLE_ORDERED 2
+
+/**
+ * AxonNetStats 最多记录的通道数，超出的通道不计数
+ */
+#define AXON_STATS_MAX_CHANNELS 8
+
+/**
+ * FFI 函数的返回码
+ */
+typedef enum AxonFfiError {
+  AXON_FFI_ERROR_OK = 0,
+  /**
+   * 必需的指针参数为 null
+   */
+  AXON_FFI_ERROR_NULL_POINTER = 1,
+  /**
+   * 参数不合法，如地址无法解析、dt 为负数
+   */
+  AXON_FFI_ERROR_INVALID_ARGUMENT = 2,
+  /**
+   * 字符串不是合法的 UTF-8
+   */
+  AXON_FFI_ERROR_INVALID_UTF8 = 3,
+  /**
+   * 传输层错误，如 socket 绑定失败、发送失败
+   */
+  AXON_FFI_ERROR_TRANSPORT = 4,
+  /**
+   * 尚未连接到服务器
+   */
+  AXON_FFI_ERROR_NOT_CONNECTED = 5,
+  /**
+   * 收到无法解码的消息
+   */
+  AXON_FFI_ERROR_DECODE = 6,
+  /**
+   * bevy_axon_ffi_next_action 已取完本帧的动作
+   */
+  AXON_FFI_ERROR_END = 7,
+  /**
+   * 世界镜像中没有要查询的对象、变体或资源
+   */
+  AXON_FFI_ERROR_NOT_FOUND = 8,
+  /**
+   * 函数内部发生 panic，game 已中毒，之后的调用都返回 Panic，只能 exit
+   */
+  AXON_FFI_ERROR_PANIC = 9,
+} AxonFfiError;
+
+/**
+ * 连接状态
+ */
+typedef enum AxonFfiState {
+  AXON_FFI_STATE_CONNECTING = 0,
+  AXON_FFI_STATE_CONNECTED = 1,
+  AXON_FFI_STATE_DISCONNECTED = 2,
+} AxonFfiState;
+
+/**
+ * 断开原因，由 renet 与 netcode 的断开原因映射而来
+ */
+typedef enum AxonDisconnectReason {
+  /**
+   * 尚未断开
+   */
+  AXON_DISCONNECT_REASON_NONE = 0,
+  /**
+   * 本地调用了 bevy_axon_ffi_disconnect
+   */
+  AXON_DISCONNECT_REASON_DISCONNECTED_BY_CLIENT = 1,
+  /**
+   * 服务器主动断开
+   */
+  AXON_DISCONNECT_REASON_DISCONNECTED_BY_SERVER = 2,
+  /**
+   * 服务器拒绝连接，通常是服务器已满
+   */
+  AXON_DISCONNECT_REASON_CONNECTION_DENIED = 3,
+  /**
+   * 连接过程中超时：服务器不可达、协议 id 不一致或超过 connect_timeout_ms
+   */
+  AXON_DISCONNECT_REASON_CONNECT_TIMED_OUT = 4,
+  /**
+   * 连接后长时间没有收到服务器的包
+   */
+  AXON_DISCONNECT_REASON_CONNECTION_TIMED_OUT = 5,
+  /**
+   * 连接令牌过期
+   */
+  AXON_DISCONNECT_REASON_CONNECT_TOKEN_EXPIRED = 6,
+  /**
+   * socket 收发失败
+   */
+  AXON_DISCONNECT_REASON_TRANSPORT = 7,
+  /**
+   * 收到无法解析的包或未知通道
+   */
+  AXON_DISCONNECT_REASON_PROTOCOL = 8,
+  /**
+   * 通道出错，如可靠通道未确认的消息超过内存上限
+   */
+  AXON_DISCONNECT_REASON_CHANNEL = 9,
+  /**
+   * 函数内部发生 panic，game 已中毒
+   */
+  AXON_DISCONNECT_REASON_PANICKED = 10,
+} AxonDisconnectReason;
+
+/**
+ * 镜像变化的种类
+ */
+typedef enum AxonChangeKind {
+  /**
+   * 对象生成，t 为对象类型
+   */
+  AXON_CHANGE_KIND_SPAWN = 0,
+  /**
+   * 对象移除（包括断开时清空），t 为移除前的对象类型
+   */
+  AXON_CHANGE_KIND_DESPAWN = 1,
+  /**
+   * 变体更新，t 为变体类型
+   */
+  AXON_CHANGE_KIND_CHANGE = 2,
+  /**
+   * 对象类型变更，t 为新类型
+   */
+  AXON_CHANGE_KIND_RETYPE = 3,
+  /**
+   * 资源更新，id 为 0，t 为资源类型
+   */
+  AXON_CHANGE_KIND_RESOURCE = 4,
+} AxonChangeKind;
+
+typedef struct Game Game;
+
+/**
+ * 单个通道的配置，客户端与服务器两个方向使用同一组通道
+ */
+typedef struct AxonChannelOptions {
+  uint8_t channel_id;
+  /**
+   * AXON_SEND_* 之一
+   */
+  uint32_t send_type;
+  /**
+   * 未确认消息最多占用的字节数
+   */
+  size_t max_memory_usage_bytes;
+  /**
+   * 可靠通道的重发间隔（毫秒），0 为默认 300
+   */
+  uint64_t resend_time_ms;
+} AxonChannelOptions;
+
+/**
+ * bevy_axon_ffi_create_ex 的参数，全部置零（除 server_addr）即为默认值
+ */
+typedef struct AxonClientOptions {
+  /**
+   * 服务器地址，如 "192.168.1.2:5000" 或 "[::1]:5000"
+   */
+  const char *server_addr;
+  /**
+   * 本地绑定地址，null 时按服务器地址族绑定 0.0.0.0:0 或 [::]:0
+   */
+  const char *bind_addr;
+  uint64_t client_id;
+  /**
+   * 需与服务器一致
+   */
+  uint64_t protocol_id;
+  /**
+   * 连接时携带的数据（见 protocol 模块），null 时只声明支持压缩
+   */
+  const uint8_t *user_data;
+  /**
+   * 不超过 256
+   */
+  size_t user_data_len;
+  /**
+   * 每次更新可发送的字节数，0 为默认 60000
+   */
+  uint64_t available_bytes_per_tick;
+  /**
+   * 通道配置（需与服务器一致），null 时使用默认的三个通道
+   */
+  const struct AxonChannelOptions *channels;
+  size_t channel_count;
+  /**
+   * 超过该时间（毫秒）仍未连接则断开，0 时只依赖 netcode 自身的超时
+   */
+  uint64_t connect_timeout_ms;
+} AxonClientOptions;
+
+/**
+ * 动作回调: user_data, id, type, payload, len；payload 只在回调内有效
+ * 回调中的 panic 会被 update 捕获，之后 game 被标记为中毒
+ */
+typedef void (*AxonActionCallback)(void*, uint64_t, uint32_t, const uint8_t*, size_t);
+
+/**
+ * 其他动作（RESOURCE、RETYPE、SYNC 等）的回调: user_data, act, id, type, payload, len
+ */
+typedef void (*AxonOtherActionCallback)(void*, uint8_t, uint64_t, uint32_t, const uint8_t*, size_t);
+
+/**
+ * 连接状态变化回调: user_data, state, reason
+ */
+typedef void (*AxonStateCallback)(void*, enum AxonFfiState, enum AxonDisconnectReason);
+
+/**
+ * bevy_axon_ffi_update 中同步调用的回调，为 null 的回调被跳过
+ * 回调内可调用 bevy_axon_ffi_invoke（本帧发出）与查询函数，不可调用 update 与 exit
+ */
+typedef struct AxonCallbacks {
+  /**
+   * 原样传给每个回调
+   */
+  void *user_data;
+  AxonActionCallback on_spawn;
+  AxonActionCallback on_despawn;
+  AxonActionCallback on_change;
+  AxonActionCallback on_invoke;
+  AxonOtherActionCallback on_other;
+  AxonStateCallback on_state;
+} AxonCallbacks;
+
+/**
+ * 单个通道的消息计数，从创建起累计
+ */
+typedef struct AxonChannelStats {
+  uint8_t channel_id;
+  /**
+   * 经 invoke 发出的消息数
+   */
+  uint64_t messages_sent;
+  /**
+   * update 收到的消息数
+   */
+  uint64_t messages_received;
+} AxonChannelStats;
+
+/**
+ * 网络统计，rtt、丢包率与速率在每次 update 时从 renet 刷新
+ */
+typedef struct AxonNetStats {
+  /**
+   * 往返时间（毫秒）
+   */
+  double rtt_ms;
+  /**
+   * 丢包率，0 到 1
+   */
+  double packet_loss;
+  double bytes_sent_per_second;
+  double bytes_received_per_second;
+  /**
+   * channels 中有效的项数，顺序与 update 读取通道的顺序一致
+   */
+  size_t channel_count;
+  struct AxonChannelStats channels[AXON_STATS_MAX_CHANNELS];
+} AxonNetStats;
+
+/**
+ * 一次镜像变化
+ */
+typedef struct AxonChange {
+  enum AxonChangeKind kind;
+  uint64_t id;
+  uint32_t t;
+} AxonChange;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * 获取最后一次错误消息
+ * game: Game 指针；为 null 时返回当前线程上不属于任何实例的错误（如创建失败）
+ * 返回: 以 null 结尾的 C 字符串，没有错误时返回 null
+ * 注意: 指针在该 game 下一次出错或被释放前有效，调用者不应释放
+ *
+ * # Safety
+ * game 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+ */
+const char *bevy_axon_ffi_errmsg(const struct Game *game);
+
+/**
+ * 创建游戏实例
+ * addr: 服务器地址字符串（以 null 结尾的 C 字符串）
+ * out_game: 成功时写入 Game 指针，失败时写入 null
+ * 其余选项使用默认值，见 bevy_axon_ffi_create_ex
+ *
+ * # Safety
+ * addr 为 null 或以 null 结尾的字符串；out_game 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_create(const char *addr,
+                                       uint64_t client_id,
+                                       struct Game **out_game);
+
+/**
+ * 按选项创建游戏实例
+ * options: 连接选项，调用返回后即可释放
+ * out_game: 成功时写入 Game 指针，失败时写入 null
+ *
+ * # Safety
+ * options 为 null 或指向有效的 AxonClientOptions，其中的指针满足各字段说明；out_game 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_create_ex(const struct AxonClientOptions *options,
+                                          struct Game **out_game);
+
+/**
+ * 退出游戏实例，尚未断开时先发送断开包
+ * ptr: Game 指针（从 create 返回）
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+ */
+void bevy_axon_ffi_exit(struct Game *ptr);
+
+/**
+ * 立即向服务器发送断开包，之后状态为 Disconnected，句柄仍需 exit 释放
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+ */
+enum AxonFfiError bevy_axon_ffi_disconnect(struct Game *ptr);
+
+/**
+ * 获取连接状态，ptr 为 null 时返回 Disconnected
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+ */
+enum AxonFfiState bevy_axon_ffi_state(const struct Game *ptr);
+
+/**
+ * 获取断开原因，尚未断开时返回 None，game 中毒后返回 Panicked
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+ */
+enum AxonDisconnectReason bevy_axon_ffi_disconnect_reason(const struct Game *ptr);
+
+/**
+ * 检查游戏实例是否已连接
+ * 返回: 1 表示已连接，0 表示未连接
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+ */
+int bevy_axon_ffi_is_connected(const struct Game *ptr);
+
+/**
+ * 更新游戏实例
+ * dt: 时间增量（秒）
+ * out_data: 写入输出数据指针（字节数组，在下一次 update 前有效，C# 需立即复制）
+ * out_len: 写入输出数据长度
+ * 出错时仍会写入本帧已收到的数据；断开不算错误，用 state 与 disconnect_reason 查询
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_data、out_len 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_update(struct Game *ptr,
+                                       float dt,
+                                       const uint8_t **out_data,
+                                       size_t *out_len);
+
+/**
+ * 设置 update 中调用的回调，callbacks 为 null 时清除；设置后 update 的输出与 next_action 仍可用
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；callbacks 为 null 或指向有效的 AxonCallbacks
+ */
+enum AxonFfiError bevy_axon_ffi_set_callbacks(struct Game *ptr,
+                                              const struct AxonCallbacks *callbacks);
+
+/**
+ * 依次取出本帧 update 收到的动作，不复制数据
+ * out_payload: 写入动作数据指针，在下一次 update 前有效
+ * 返回: Ok 表示写入了一条动作，End 表示已取完，Decode 表示数据损坏（剩余动作被丢弃）
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；各输出指针为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_next_action(struct Game *ptr,
+                                            uint8_t *out_act,
+                                            uint64_t *out_id,
+                                            uint32_t *out_type,
+                                            const uint8_t **out_payload,
+                                            size_t *out_len);
+
+/**
+ * 获取 next_action 最后取出的动作（回调中为正在分发的动作）所在的通道 id
+ * ptr 为 null 或还没有取出动作时返回可靠有序通道
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+ */
+uint8_t bevy_axon_ffi_action_channel(const struct Game *ptr);
+
+/**
+ * 在可靠有序通道上调用游戏实例的方法，见 bevy_axon_ffi_invoke_on
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；raw 为 null 或指向至少 raw_len 字节
+ */
+enum AxonFfiError bevy_axon_ffi_invoke(struct Game *ptr,
+                                       const unsigned char *raw,
+                                       size_t raw_len);
+
+/**
+ * 在指定通道上调用游戏实例的方法，高频输入可走不可靠通道
+ * channel: 已配置的通道 id，默认通道见 AXON_CHANNEL_*
+ * raw: 输入数据指针
+ * raw_len: 输入数据长度
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；raw 为 null 或指向至少 raw_len 字节
+ */
+enum AxonFfiError bevy_axon_ffi_invoke_on(struct Game *ptr,
+                                          uint8_t channel,
+                                          const unsigned char *raw,
+                                          size_t raw_len);
+
+/**
+ * 获取网络统计
+ * out_stats: 写入统计，未连接时 rtt 等为 renet 的初始值
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_stats 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_stats(const struct Game *ptr, struct AxonNetStats *out_stats);
+
+/**
+ * 把 JSON 文本编码为 sbin，规则见 json 模块：数字默认为 i64/f64，其他类型用 {"$u32": 1} 等包装
+ * out_data: 写入 sbin 数据指针，在当前线程下一次转换前有效，调用者不应释放
+ * 返回: JSON 无法解析或包装不合法时为 InvalidArgument，消息见 bevy_axon_ffi_errmsg(NULL)
+ *
+ * # Safety
+ * json 为 null 或以 null 结尾的字符串；out_data、out_len 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_json_to_sbin(const char *json,
+                                             const uint8_t **out_data,
+                                             size_t *out_len);
+
+/**
+ * 把一个完整的 sbin 值（如动作的 payload）转换为 JSON 文本
+ * typed: 非 0 时数字、bytes 与非字符串键的映射输出为 {"$u32": 1} 等包装，可无损转回 sbin
+ * out_json: 写入以 null 结尾的 JSON 文本，在当前线程下一次转换前有效，调用者不应释放
+ * 返回: 数据损坏时为 Decode
+ *
+ * # Safety
+ * data 为 null 或指向至少 len 字节；out_json 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_sbin_to_json(const uint8_t *data,
+                                             size_t len,
+                                             int typed,
+                                             const char **out_json);
+
+/**
+ * 把 JSON 编码为 sbin 后作为事件发给服务器（可靠有序通道）
+ * id: 目标对象的网络 id，0 表示无目标
+ * event_type: 事件类型 id
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；json 为 null 或以 null 结尾的字符串
+ */
+enum AxonFfiError bevy_axon_ffi_invoke_json(struct Game *ptr,
+                                            uint64_t id,
+                                            uint32_t event_type,
+                                            const char *json);
+
+/**
+ * 列出世界镜像中的对象 id（升序）
+ * out_ids: 最多写入 capacity 个 id，为 null 时只返回数量
+ * 返回: 对象总数，大于 capacity 时需用更大的缓冲重试
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_ids 为 null 或可写 capacity 个 u64
+ */
+size_t bevy_axon_ffi_objects(const struct Game *ptr,
+                             uint64_t *out_ids,
+                             size_t capacity);
+
+/**
+ * 获取对象的类型
+ * 返回: 对象不存在时为 NotFound（不记录错误消息）
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_type 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_object_type(const struct Game *ptr,
+                                            uint64_t id,
+                                            uint32_t *out_type);
+
+/**
+ * 获取对象某个变体最新的数据
+ * out_payload: 写入数据指针，在下一次 update 前有效
+ * 返回: 对象不存在或尚未收到该变体时为 NotFound（不记录错误消息）
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_payload、out_len 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_variant(const struct Game *ptr,
+                                        uint64_t id,
+                                        uint32_t variant_type,
+                                        const uint8_t **out_payload,
+                                        size_t *out_len);
+
+/**
+ * 获取资源最新的数据
+ * out_payload: 写入数据指针，在下一次 update 前有效
+ * 返回: 尚未收到该资源时为 NotFound（不记录错误消息）
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_payload、out_len 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_resource(const struct Game *ptr,
+                                         uint32_t resource_type,
+                                         const uint8_t **out_payload,
+                                         size_t *out_len);
+
+/**
+ * 依次取出最近一次 update 中世界镜像的变化，按动作顺序，断开时每个对象记为 Despawn
+ * 返回: Ok 表示写入了一条变化，End 表示已取完
+ *
+ * # Safety
+ * ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_change 为 null 或可写
+ */
+enum AxonFfiError bevy_axon_ffi_next_change(struct Game *ptr,
+                                            struct AxonChange *out_change);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BEVY_AXON_H */
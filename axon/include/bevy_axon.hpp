@@ -0,0 +1,155 @@
+// bevy_axon.h 的 C++ 封装：Client 持有 Game 句柄，析构时断开并释放
+// 不使用异常，错误以 AxonFfiError 返回，可直接用于关闭了异常的引擎（如 Unreal）
+#ifndef BEVY_AXON_HPP
+#define BEVY_AXON_HPP
+
+#include <cstddef>
+#include <cstdint>
+#include <utility>
+
+#include "bevy_axon.h"
+
+namespace bevy_axon {
+
+// next_action 取出的一条动作，payload 在下一次 update 前有效
+struct Action {
+    uint8_t act = 0;
+    uint64_t id = 0;
+    uint32_t type = 0;
+    const uint8_t *payload = nullptr;
+    size_t len = 0;
+    // 动作所在的通道 id
+    uint8_t channel = AXON_CHANNEL_RELIABLE_ORDERED;
+};
+
+class Client {
+public:
+    Client() = default;
+    ~Client() { reset(); }
+
+    Client(const Client &) = delete;
+    Client &operator=(const Client &) = delete;
+
+    Client(Client &&other) noexcept : game_(std::exchange(other.game_, nullptr)) {}
+    Client &operator=(Client &&other) noexcept {
+        if (this != &other) {
+            reset();
+            game_ = std::exchange(other.game_, nullptr);
+        }
+        return *this;
+    }
+
+    // 使用默认选项连接，已有连接会先释放
+    AxonFfiError connect(const char *addr, uint64_t client_id) {
+        reset();
+        return bevy_axon_ffi_create(addr, client_id, &game_);
+    }
+
+    AxonFfiError connect(const AxonClientOptions &options) {
+        reset();
+        return bevy_axon_ffi_create_ex(&options, &game_);
+    }
+
+    // 发送断开包并释放句柄
+    void reset() {
+        if (game_ != nullptr) {
+            bevy_axon_ffi_exit(game_);
+            game_ = nullptr;
+        }
+    }
+
+    // 发送断开包，句柄保留以便查询断开原因
+    AxonFfiError disconnect() { return bevy_axon_ffi_disconnect(game_); }
+
+    AxonFfiError update(float dt) { return bevy_axon_ffi_update(game_, dt, nullptr, nullptr); }
+
+    // 取出下一条动作：OK 表示取到，END 表示已取完，DECODE 表示数据损坏（剩余动作被丢弃）
+    AxonFfiError next_action(Action &action) {
+        AxonFfiError err = bevy_axon_ffi_next_action(game_, &action.act, &action.id,
+                                                     &action.type, &action.payload, &action.len);
+        if (err == AXON_FFI_ERROR_OK) {
+            action.channel = bevy_axon_ffi_action_channel(game_);
+        }
+        return err;
+    }
+
+    // 对本帧的每条动作调用 f(const Action &)，取完时返回 OK，否则返回中断时的错误
+    template <typename F> AxonFfiError for_each_action(F &&f) {
+        Action action;
+        AxonFfiError err;
+        while ((err = next_action(action)) == AXON_FFI_ERROR_OK) {
+            f(static_cast<const Action &>(action));
+        }
+        return err == AXON_FFI_ERROR_END ? AXON_FFI_ERROR_OK : err;
+    }
+
+    AxonFfiError invoke(const uint8_t *data, size_t len) {
+        return bevy_axon_ffi_invoke(game_, data, len);
+    }
+
+    // 在指定通道上发送，如 AXON_CHANNEL_UNRELIABLE
+    AxonFfiError invoke_on(uint8_t channel, const uint8_t *data, size_t len) {
+        return bevy_axon_ffi_invoke_on(game_, channel, data, len);
+    }
+
+    // 世界镜像：写入最多 capacity 个对象 id，返回对象总数
+    size_t objects(uint64_t *ids, size_t capacity) const {
+        return bevy_axon_ffi_objects(game_, ids, capacity);
+    }
+
+    // 对象不存在时返回 AXON_FFI_ERROR_NOT_FOUND
+    AxonFfiError object_type(uint64_t id, uint32_t &type) const {
+        return bevy_axon_ffi_object_type(game_, id, &type);
+    }
+
+    // 变体最新的数据，在下一次 update 前有效
+    AxonFfiError variant(uint64_t id, uint32_t variant_type, const uint8_t *&payload,
+                         size_t &len) const {
+        return bevy_axon_ffi_variant(game_, id, variant_type, &payload, &len);
+    }
+
+    AxonFfiError resource(uint32_t resource_type, const uint8_t *&payload, size_t &len) const {
+        return bevy_axon_ffi_resource(game_, resource_type, &payload, &len);
+    }
+
+    // 对最近一次 update 中的每个镜像变化调用 f(const AxonChange &)
+    template <typename F> void for_each_change(F &&f) {
+        AxonChange change;
+        while (bevy_axon_ffi_next_change(game_, &change) == AXON_FFI_ERROR_OK) {
+            f(static_cast<const AxonChange &>(change));
+        }
+    }
+
+    // json 编码为 sbin 后作为事件发送，规则见 bevy_axon_ffi_json_to_sbin
+    AxonFfiError invoke_json(uint64_t id, uint32_t event_type, const char *json) {
+        return bevy_axon_ffi_invoke_json(game_, id, event_type, json);
+    }
+
+    // next_action 最后取出或回调中正在分发的动作所在通道
+    uint8_t action_channel() const { return bevy_axon_ffi_action_channel(game_); }
+
+    // callbacks 中的函数在 update 中调用，user_data 需在 Client 存活期间有效
+    AxonFfiError set_callbacks(const AxonCallbacks &callbacks) {
+        return bevy_axon_ffi_set_callbacks(game_, &callbacks);
+    }
+
+    AxonFfiState state() const { return bevy_axon_ffi_state(game_); }
+    AxonDisconnectReason disconnect_reason() const { return bevy_axon_ffi_disconnect_reason(game_); }
+    bool connected() const { return state() == AXON_FFI_STATE_CONNECTED; }
+
+    // rtt、丢包率与速率在每次 update 时刷新
+    AxonFfiError stats(AxonNetStats &out) const { return bevy_axon_ffi_stats(game_, &out); }
+
+    // 最后一次错误消息，没有句柄时返回当前线程的错误
+    const char *error() const { return bevy_axon_ffi_errmsg(game_); }
+
+    Game *get() const { return game_; }
+    explicit operator bool() const { return game_ != nullptr; }
+
+private:
+    Game *game_ = nullptr;
+};
+
+} // namespace bevy_axon
+
+#endif // BEVY_AXON_HPP
@@ -0,0 +1,8 @@
+//! 刷新提交的 include/bevy_axon.h：cargo run --features ffi --bin gen-header
+
+fn main() {
+    let dst = concat!(env!("CARGO_MANIFEST_DIR"), "/include/bevy_axon.h");
+    std::fs::copy(concat!(env!("OUT_DIR"), "/bevy_axon.h"), dst)
+        .expect("Failed to copy bevy_axon.h");
+    println!("wrote {}", dst);
+}
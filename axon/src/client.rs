@@ -0,0 +1,346 @@
+use crate::core::*;
+use crate::netcode::AxonNetcodeClientTransport;
+use crate::protocol::*;
+use crate::transport::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_sbin::SbinError;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// 服务器对象在客户端的镜像
+#[derive(Component, Debug)]
+pub struct AxonReplica {
+    pub id: u64,
+    pub t: u32,
+}
+
+/// 入场快照已全部收到
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AxonSyncEvent;
+
+/// 服务器对象 id 到本地实体的映射
+#[derive(Resource, Default)]
+pub struct AxonClientEntities {
+    map: HashMap<u64, Entity>,
+    types: HashMap<u64, u32>,
+}
+
+impl AxonClientEntities {
+    pub fn get(&self, id: u64) -> Option<Entity> {
+        self.map.get(&id).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u64, Entity)> + '_ {
+        self.map.iter().map(|(id, entity)| (*id, *entity))
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+type AxonObjectInsert = fn(&mut EntityCommands<'_>);
+type AxonObjectRemove = fn(&mut EntityCommands<'_>);
+type AxonVariantInsert = fn(&[u8], &mut EntityCommands<'_>) -> Result<(), SbinError>;
+type AxonResourceInsert = fn(&[u8], &mut Commands<'_, '_>) -> Result<(), SbinError>;
+
+#[derive(Resource, Default)]
+struct AxonClientTypeSet {
+    objects: HashMap<u32, (AxonObjectInsert, AxonObjectRemove)>,
+    variants: HashMap<u32, AxonVariantInsert>,
+    resources: HashMap<u32, AxonResourceInsert>,
+}
+
+/// 到服务器的连接
+#[derive(Resource)]
+pub struct AxonClientConnection {
+    transport: Box<dyn AxonClientTransport>,
+    compressed: bool,
+}
+
+impl AxonClientConnection {
+    pub fn new(transport: impl AxonClientTransport) -> Self {
+        Self {
+            transport: Box::new(transport),
+            compressed: false,
+        }
+    }
+
+    /// 连接时声明了 AXON_FLAG_COMPRESSION，收到的消息带消息头
+    pub fn with_compression(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    /// 取出一条消息，按需解码消息头
+    fn receive(&mut self, channel: u8) -> Option<Vec<u8>> {
+        loop {
+            let message = self.transport.receive(channel)?;
+            if !self.compressed {
+                return Some(message);
+            }
+            match axon_decode_message(&message) {
+                Some(message) => return Some(message),
+                None => warn!("axon message decode error"),
+            }
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.transport.is_connected()
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.transport.is_disconnected()
+    }
+
+    pub fn send(&mut self, channel: u8, data: Vec<u8>) {
+        self.transport.send(channel, data);
+    }
+
+    pub fn disconnect(&mut self) {
+        self.transport.disconnect();
+    }
+}
+
+#[derive(Default)]
+pub struct AxonClientPlugin;
+
+impl Plugin for AxonClientPlugin {
+    fn build(&self, app: &mut App) {
+        // 不依赖 AxonPlugin，单独使用时 add_axon_event 与事件派发同样可用
+        app.init_resource::<AxonEventInvokeSet>();
+        app.init_resource::<AxonTypeRegistry>();
+        app.init_resource::<AxonClientEntities>();
+        app.init_resource::<AxonClientTypeSet>();
+        app.add_systems(
+            PreUpdate,
+            client_axon_system.run_if(resource_exists::<AxonClientConnection>),
+        );
+        app.add_systems(
+            PostUpdate,
+            client_flush_system.run_if(resource_exists::<AxonClientConnection>),
+        );
+        app.add_systems(
+            Last,
+            client_exit_system.run_if(resource_exists::<AxonClientConnection>),
+        );
+    }
+}
+
+fn client_axon_system(
+    time: Res<Time>,
+    mut client: ResMut<AxonClientConnection>,
+    mut commands: Commands,
+    mut entities: ResMut<AxonClientEntities>,
+    type_set: Res<AxonClientTypeSet>,
+    event_set: Res<AxonEventInvokeSet>,
+) {
+    if !client.is_disconnected() {
+        if let Err(e) = client.transport.update(time.delta()) {
+            warn!("axon transport error: {}", e);
+        }
+    }
+
+    if client.is_disconnected() {
+        for (_, entity) in entities.map.drain() {
+            commands.entity(entity).despawn();
+        }
+        entities.types.clear();
+        return;
+    }
+
+    for channel in client.transport.channels() {
+        while let Some(message) = client.receive(channel) {
+            for action in read_axon_actions(&message) {
+                apply_action(&action, &mut commands, &mut entities, &type_set, &event_set);
+            }
+        }
+    }
+}
+
+fn client_flush_system(mut client: ResMut<AxonClientConnection>) {
+    if let Err(e) = client.transport.flush() {
+        warn!("axon transport error: {}", e);
+    }
+}
+
+fn client_exit_system(exit: MessageReader<AppExit>, mut client: ResMut<AxonClientConnection>) {
+    if !exit.is_empty() {
+        client.disconnect();
+    }
+}
+
+fn apply_action(
+    action: &AxonAction,
+    commands: &mut Commands,
+    entities: &mut AxonClientEntities,
+    type_set: &AxonClientTypeSet,
+    event_set: &AxonEventInvokeSet,
+) {
+    match action.act {
+        ACTION_TYPE_SPAWN => {
+            if entities.map.contains_key(&action.id) {
+                return;
+            }
+            let mut e = commands.spawn(AxonReplica {
+                id: action.id,
+                t: action.t,
+            });
+            if let Some((insert, _)) = type_set.objects.get(&action.t) {
+                insert(&mut e);
+            }
+            entities.map.insert(action.id, e.id());
+            entities.types.insert(action.id, action.t);
+        }
+        ACTION_TYPE_DESPAWN => {
+            entities.types.remove(&action.id);
+            if let Some(entity) = entities.map.remove(&action.id) {
+                commands.entity(entity).despawn();
+            }
+        }
+        ACTION_TYPE_RETYPE => {
+            let Some(&entity) = entities.map.get(&action.id) else {
+                return;
+            };
+            let old = entities.types.insert(action.id, action.t);
+            let mut e = commands.entity(entity);
+            if let Some((_, remove)) = old.and_then(|t| type_set.objects.get(&t)) {
+                remove(&mut e);
+            }
+            if let Some((insert, _)) = type_set.objects.get(&action.t) {
+                insert(&mut e);
+            }
+            e.insert(AxonReplica {
+                id: action.id,
+                t: action.t,
+            });
+        }
+        ACTION_TYPE_CHANGE => {
+            let Some(&entity) = entities.map.get(&action.id) else {
+                return;
+            };
+            let Some(insert) = type_set.variants.get(&action.t) else {
+                return;
+            };
+            if let Err(e) = insert(&action.v, &mut commands.entity(entity)) {
+                warn!("axon variant {} decode error: {}", action.t, e);
+            }
+        }
+        ACTION_TYPE_INVOKE => {
+            let target = entities.map.get(&action.id).copied();
+            event_set.invoke_action(action, 0, target, commands);
+        }
+        ACTION_TYPE_SYNC => {
+            commands.trigger(AxonSyncEvent);
+        }
+        ACTION_TYPE_RESOURCE => {
+            let Some(insert) = type_set.resources.get(&action.t) else {
+                return;
+            };
+            if let Err(e) = insert(&action.v, commands) {
+                warn!("axon resource {} decode error: {}", action.t, e);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 向服务器发送事件
+#[derive(SystemParam)]
+pub struct AxonClientSender<'w> {
+    client: Option<ResMut<'w, AxonClientConnection>>,
+}
+
+impl AxonClientSender<'_> {
+    pub fn is_connected(&self) -> bool {
+        self.client.as_ref().is_some_and(|v| v.is_connected())
+    }
+
+    /// id 为目标对象（0 表示无目标）
+    pub fn send<T: AxonEvent + Serialize>(&mut self, id: u64, event: &T) -> Result<(), SbinError> {
+        let v = serde_sbin::to_bytes(event)?;
+        if let Some(client) = self.client.as_mut() {
+            client.send(
+                AXON_CHANNEL_RELIABLE_ORDERED,
+                encode_axon_action(ACTION_TYPE_INVOKE, id, T::axon_event_type(), &v),
+            );
+        }
+        Ok(())
+    }
+}
+
+pub trait AppAxonClient {
+    /// 收到对象时同时插入 T
+    fn add_axon_client_object<T: AxonObject + Component + Default>(&mut self);
+    /// 收到变更时解码并插入 T
+    fn add_axon_client_variant<T: AxonVariant + Component + DeserializeOwned>(&mut self);
+    /// 收到资源变更时解码并插入资源 T
+    fn add_axon_client_resource<T: AxonResource + Resource + DeserializeOwned>(&mut self);
+    /// 通过 UDP（renet/netcode）连接服务器
+    fn connect_axon_server(&mut self, addr: &str, client_id: u64);
+}
+
+impl AppAxonClient for App {
+    fn add_axon_client_object<T: AxonObject + Component + Default>(&mut self) {
+        fn insert<T: Component + Default>(e: &mut EntityCommands<'_>) {
+            e.insert(T::default());
+        }
+        fn remove<T: Component>(e: &mut EntityCommands<'_>) {
+            e.remove::<T>();
+        }
+        self.world_mut()
+            .resource_mut::<AxonClientTypeSet>()
+            .objects
+            .insert(T::axon_object_type(), (insert::<T>, remove::<T>));
+    }
+
+    fn add_axon_client_variant<T: AxonVariant + Component + DeserializeOwned>(&mut self) {
+        fn insert<T: Component + DeserializeOwned>(
+            bytes: &[u8],
+            e: &mut EntityCommands<'_>,
+        ) -> Result<(), SbinError> {
+            e.insert(serde_sbin::from_bytes::<T>(bytes)?);
+            Ok(())
+        }
+        self.world_mut()
+            .resource_mut::<AxonClientTypeSet>()
+            .variants
+            .insert(T::axon_variant_type(), insert::<T>);
+    }
+
+    fn add_axon_client_resource<T: AxonResource + Resource + DeserializeOwned>(&mut self) {
+        fn insert<T: Resource + DeserializeOwned>(
+            bytes: &[u8],
+            commands: &mut Commands<'_, '_>,
+        ) -> Result<(), SbinError> {
+            commands.insert_resource(serde_sbin::from_bytes::<T>(bytes)?);
+            Ok(())
+        }
+        self.world_mut()
+            .resource_mut::<AxonClientTypeSet>()
+            .resources
+            .insert(T::axon_resource_type(), insert::<T>);
+    }
+
+    fn connect_axon_server(&mut self, addr: &str, client_id: u64) {
+        let flags = axon_compression_flag();
+        let user_data = axon_user_data(flags);
+        // 本地地址需与服务器地址同属 IPv4 或 IPv6
+        let bind_addr = match addr.parse::<SocketAddr>() {
+            Ok(v) if v.is_ipv6() => "[::]:0",
+            _ => "0.0.0.0:0",
+        };
+        let transport =
+            AxonNetcodeClientTransport::connect(bind_addr, addr, client_id, Some(user_data))
+                .expect("Failed to create transport");
+        self.insert_resource(AxonClientConnection::new(transport).with_compression(flags != 0));
+    }
+}
@@ -1,25 +1,65 @@
+use crate::protocol::axon_write_action;
+use bevy::ecs::entity::Entities;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use serde::Serialize;
 use serde_bytes::ByteBuf;
-use std::collections::HashMap;
+use serde_sbin::SbinError;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 pub trait AxonObject {
     fn axon_object_type() -> u32;
+    fn axon_object_schema() -> &'static [AxonField] {
+        &[]
+    }
 }
 
 pub trait AxonVariant {
     fn axon_variant_type() -> u32;
+    fn axon_variant_schema() -> &'static [AxonField] {
+        &[]
+    }
 }
 
 pub trait AxonEvent {
     fn axon_event_type() -> u32;
     fn axon_event_invoke(bytes: &[u8], commands: &mut Commands<'_, '_>);
+    /// 带目标对象的事件，derive 生成的实现触发 AxonEntityEvent<Self>；
+    /// 默认退回全局触发
+    fn axon_event_invoke_entity(
+        bytes: &[u8],
+        entity: Entity,
+        client_id: u64,
+        commands: &mut Commands<'_, '_>,
+    ) {
+        let _ = (entity, client_id);
+        Self::axon_event_invoke(bytes, commands);
+    }
+    fn axon_event_schema() -> &'static [AxonField] {
+        &[]
+    }
 }
 
-pub const ACTION_TYPE_SPAWN: u8 = 1;
-pub const ACTION_TYPE_DESPAWN: u8 = 2;
-pub const ACTION_TYPE_CHANGE: u8 = 3;
-pub const ACTION_TYPE_INVOKE: u8 = 4;
+/// 复制到客户端的全局资源，如比分、回合计时
+pub trait AxonResource {
+    fn axon_resource_type() -> u32;
+    fn axon_resource_schema() -> &'static [AxonField] {
+        &[]
+    }
+}
+
+/// 字段描述，由 derive 宏生成
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxonField {
+    pub name: &'static str,
+    pub ty: &'static str,
+}
+
+pub use crate::protocol::{
+    ACTION_TYPE_CHANGE, ACTION_TYPE_DESPAWN, ACTION_TYPE_INVOKE, ACTION_TYPE_RESOURCE,
+    ACTION_TYPE_RETYPE, ACTION_TYPE_SPAWN, ACTION_TYPE_SYNC,
+};
 
 #[derive(Event)]
 pub struct AxonActionEvent {
@@ -33,49 +73,244 @@ pub struct AxonActionEvent {
 #[derive(Event)]
 pub struct AxonExitEvent;
 
+/// 发往某个同步对象的事件，在对象映射到的实体上触发，可用实体观察者处理
+#[derive(EntityEvent, Debug, Clone)]
+pub struct AxonEntityEvent<T: Send + Sync + 'static> {
+    pub entity: Entity,
+    /// 发送者，来自服务器时为 0
+    pub client_id: u64,
+    pub event: T,
+}
+
+/// 事件目标不存在或已销毁
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct AxonUnknownTargetEvent {
+    pub client_id: u64,
+    pub id: u64,
+    pub t: u32,
+}
+
 #[derive(Component)]
 pub struct AxonClient {
     pub id: u64,
 }
 
+/// 客户端所在的房间，放在 AxonClient 实体上
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AxonRoom(pub u64);
+
+/// 实体的所属客户端
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxonOwner {
+    pub client_id: u64,
+}
+
+/// 观战客户端，不参与游戏逻辑
+#[derive(Component)]
+pub struct AxonSpectator {
+    pub id: u64,
+}
+
 pub type AxonEventInvoke = fn(&[u8], &mut Commands<'_, '_>);
+pub type AxonEntityEventInvoke = fn(&[u8], Entity, u64, &mut Commands<'_, '_>);
 
 #[derive(Resource, Default)]
 pub struct AxonEventInvokeSet {
     map: HashMap<u32, AxonEventInvoke>,
+    entity_map: HashMap<u32, AxonEntityEventInvoke>,
 }
 
 impl AxonEventInvokeSet {
-    pub fn invoke(&self, raw: &[u8], commands: &mut Commands<'_, '_>) {
-        let mut dec = serde_sbin::SbinDeserializer::from_bytes(raw);
-        loop {
-            let act: Result<u8, _> = serde::Deserialize::deserialize(&mut dec);
-            if let Ok(act) = act {
-                let id: Result<u64, _> = serde::Deserialize::deserialize(&mut dec);
-                if let Ok(_) = id {
-                    let t: Result<u32, _> = serde::Deserialize::deserialize(&mut dec);
-                    if let Ok(t) = t {
-                        let v: Result<ByteBuf, _> = serde::Deserialize::deserialize(&mut dec);
-                        if let Ok(vv) = v {
-                            if act == ACTION_TYPE_INVOKE {
-                                if let Some(invoke) = self.map.get(&t) {
-                                    invoke(&vv.into_vec(), commands);
-                                }
-                            }
-                        } else {
-                            break;
-                        }
-                    } else {
-                        break;
-                    }
-                } else {
-                    break;
-                }
-            } else {
-                break;
+    /// 派发动作流中的事件，resolve 将目标 id 映射为实体
+    pub fn invoke(
+        &self,
+        raw: &[u8],
+        client_id: u64,
+        resolve: impl Fn(u64) -> Option<Entity>,
+        commands: &mut Commands<'_, '_>,
+    ) {
+        for action in read_axon_actions(raw) {
+            if action.act == ACTION_TYPE_INVOKE {
+                let target = resolve(action.id);
+                self.invoke_action(&action, client_id, target, commands);
             }
         }
     }
+
+    /// 派发一条 INVOKE 动作：id 为 0 时全局触发，否则在 target 上触发；
+    /// target 为 None 时触发 AxonUnknownTargetEvent
+    pub fn invoke_action(
+        &self,
+        action: &AxonAction,
+        client_id: u64,
+        target: Option<Entity>,
+        commands: &mut Commands<'_, '_>,
+    ) -> bool {
+        if action.id == 0 {
+            return self.invoke_event(action.t, &action.v, commands);
+        }
+        let Some(invoke) = self.entity_map.get(&action.t) else {
+            return false;
+        };
+        match target {
+            Some(entity) => invoke(&action.v, entity, client_id, commands),
+            None => {
+                debug!(
+                    "axon event {} from {} targets unknown object {}",
+                    action.t, client_id, action.id
+                );
+                commands.trigger(AxonUnknownTargetEvent {
+                    client_id,
+                    id: action.id,
+                    t: action.t,
+                });
+            }
+        }
+        true
+    }
+
+    /// 派发单个事件，未注册的类型返回 false
+    pub fn invoke_event(&self, t: u32, v: &[u8], commands: &mut Commands<'_, '_>) -> bool {
+        match self.map.get(&t) {
+            Some(invoke) => {
+                invoke(v, commands);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 一条同步动作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AxonAction {
+    pub act: u8,
+    pub id: u64,
+    pub t: u32,
+    pub v: Vec<u8>,
+}
+
+/// 编码一条动作，见 protocol::axon_write_action
+pub fn encode_axon_action(act: u8, id: u64, t: u32, v: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    axon_write_action(&mut out, act, id, t, v);
+    out
+}
+
+/// 解析动作流，遇到不完整的动作时停止
+pub fn read_axon_actions(raw: &[u8]) -> Vec<AxonAction> {
+    let mut dec = serde_sbin::SbinDeserializer::from_bytes(raw);
+    let mut actions = Vec::new();
+    loop {
+        let act: Result<u8, _> = serde::Deserialize::deserialize(&mut dec);
+        let Ok(act) = act else { break };
+        let id: Result<u64, _> = serde::Deserialize::deserialize(&mut dec);
+        let Ok(id) = id else { break };
+        let t: Result<u32, _> = serde::Deserialize::deserialize(&mut dec);
+        let Ok(t) = t else { break };
+        let v: Result<ByteBuf, _> = serde::Deserialize::deserialize(&mut dec);
+        let Ok(v) = v else { break };
+        actions.push(AxonAction {
+            act,
+            id,
+            t,
+            v: v.into_vec(),
+        });
+    }
+    actions
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AxonTypeKind {
+    Object,
+    Variant,
+    Event,
+    Resource,
+}
+
+/// 从存档重建该类型的函数，由 add_axon_persistent_* 记录
+#[derive(Debug, Clone, Copy)]
+pub enum AxonRestore {
+    Object(fn(&mut EntityWorldMut<'_>)),
+    Variant(fn(&[u8], &mut EntityWorldMut<'_>) -> Result<(), SbinError>),
+    Resource(fn(&[u8], &mut World) -> Result<(), SbinError>),
+}
+
+#[derive(Debug, Clone)]
+pub struct AxonTypeInfo {
+    pub kind: AxonTypeKind,
+    pub type_id: u32,
+    pub type_name: &'static str,
+    pub schema: &'static [AxonField],
+    /// 未注册为可持久化时为 None
+    pub restore: Option<AxonRestore>,
+}
+
+/// 运行时类型注册表，记录所有 `add_axon_*` 注册的类型
+#[derive(Resource, Default)]
+pub struct AxonTypeRegistry {
+    types: Vec<AxonTypeInfo>,
+    index: HashMap<(AxonTypeKind, u32), usize>,
+}
+
+impl AxonTypeRegistry {
+    /// 注册类型，已注册时返回 false；type_id 与另一个类型冲突时 panic
+    pub fn register(&mut self, info: AxonTypeInfo) -> bool {
+        let key = (info.kind, info.type_id);
+        if let Some(&i) = self.index.get(&key) {
+            let exists = &self.types[i];
+            if exists.type_name != info.type_name {
+                panic!(
+                    "axon {:?} type id {} collision: {} vs {}",
+                    info.kind, info.type_id, exists.type_name, info.type_name
+                );
+            }
+            return false;
+        }
+        self.index.insert(key, self.types.len());
+        self.types.push(info);
+        true
+    }
+
+    /// 记录已注册类型的重建函数，类型未注册时返回 false
+    pub fn set_restore(&mut self, kind: AxonTypeKind, type_id: u32, restore: AxonRestore) -> bool {
+        match self.index.get(&(kind, type_id)) {
+            Some(&i) => {
+                self.types[i].restore = Some(restore);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get(&self, kind: AxonTypeKind, type_id: u32) -> Option<&AxonTypeInfo> {
+        self.index.get(&(kind, type_id)).map(|&i| &self.types[i])
+    }
+
+    pub fn contains(&self, kind: AxonTypeKind, type_id: u32) -> bool {
+        self.index.contains_key(&(kind, type_id))
+    }
+
+    pub fn find_by_name(&self, type_name: &str) -> Option<&AxonTypeInfo> {
+        self.types.iter().find(|v| v.type_name == type_name)
+    }
+
+    /// 按注册顺序遍历
+    pub fn iter(&self) -> impl Iterator<Item = &AxonTypeInfo> {
+        self.types.iter()
+    }
+
+    pub fn iter_kind(&self, kind: AxonTypeKind) -> impl Iterator<Item = &AxonTypeInfo> {
+        self.types.iter().filter(move |v| v.kind == kind)
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
 }
 
 #[derive(Default)]
@@ -84,15 +319,25 @@ pub struct AxonPlugin;
 impl Plugin for AxonPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<AxonEventInvokeSet>();
+        app.init_resource::<AxonTypeRegistry>();
+        app.init_resource::<AxonObjectSet>();
         app.configure_sets(
             PostUpdate,
             (
+                AxonSystemSet::Detect,
                 AxonSystemSet::Spawn,
                 AxonSystemSet::Change,
                 AxonSystemSet::Despawn,
             )
                 .chain(), // 关键
         );
+        app.add_systems(
+            PostUpdate,
+            (
+                reg_object_spawn.in_set(AxonSystemSet::Spawn),
+                reg_object_despawn.in_set(AxonSystemSet::Despawn),
+            ),
+        );
     }
 }
 
@@ -100,69 +345,233 @@ pub trait AppAxon {
     fn add_axon_event<T: AxonEvent + Event>(&mut self);
     fn add_axon_object<T: AxonObject + Component>(&mut self);
     fn add_axon_variant<T: AxonVariant + Component + Serialize>(&mut self);
+    fn add_axon_resource<T: AxonResource + Resource + Serialize>(&mut self);
     fn send_axon_client_event<T: AxonEvent + Serialize>(
         &mut self,
         id: u64,
         event: &T,
         client_id: u64,
-    );
-    fn broadcast_axon_client_event<T: AxonEvent + Serialize>(&mut self, id: u64, event: &T);
+    ) -> Result<(), SbinError>;
+    fn broadcast_axon_client_event<T: AxonEvent + Serialize>(
+        &mut self,
+        id: u64,
+        event: &T,
+    ) -> Result<(), SbinError>;
 }
 
 impl AppAxon for App {
     fn add_axon_event<T: AxonEvent + Event>(&mut self) {
         let type_id = T::axon_event_type();
-        // println!("add_axon_event: {:?}", type_id);
-        let invoke = T::axon_event_invoke as AxonEventInvoke;
-        self.world_mut()
-            .resource_mut::<AxonEventInvokeSet>()
+        if !register_axon_type(
+            self,
+            AxonTypeKind::Event,
+            type_id,
+            T::axon_event_schema(),
+            std::any::type_name::<T>(),
+        ) {
+            return;
+        }
+        let mut event_set = self.world_mut().resource_mut::<AxonEventInvokeSet>();
+        event_set
             .map
-            .insert(type_id, invoke);
+            .insert(type_id, T::axon_event_invoke as AxonEventInvoke);
+        event_set.entity_map.insert(
+            type_id,
+            T::axon_event_invoke_entity as AxonEntityEventInvoke,
+        );
     }
     fn add_axon_object<T: AxonObject + Component>(&mut self) {
+        let type_id = T::axon_object_type();
+        if !register_axon_type(
+            self,
+            AxonTypeKind::Object,
+            type_id,
+            T::axon_object_schema(),
+            std::any::type_name::<T>(),
+        ) {
+            return;
+        }
         self.add_systems(
             PostUpdate,
-            (
-                reg_object_add::<T>.in_set(AxonSystemSet::Spawn),
-                reg_object_removed::<T>.in_set(AxonSystemSet::Despawn),
-            ),
+            (reg_object_add::<T>, reg_object_removed::<T>)
+                .chain()
+                .in_set(AxonSystemSet::Detect),
         );
     }
     fn add_axon_variant<T: AxonVariant + Component + Serialize>(&mut self) {
+        let type_id = T::axon_variant_type();
+        if !register_axon_type(
+            self,
+            AxonTypeKind::Variant,
+            type_id,
+            T::axon_variant_schema(),
+            std::any::type_name::<T>(),
+        ) {
+            return;
+        }
         self.add_systems(
             PostUpdate,
             reg_variant_change::<T>.in_set(AxonSystemSet::Change),
         );
     }
+    fn add_axon_resource<T: AxonResource + Resource + Serialize>(&mut self) {
+        let type_id = T::axon_resource_type();
+        if !register_axon_type(
+            self,
+            AxonTypeKind::Resource,
+            type_id,
+            T::axon_resource_schema(),
+            std::any::type_name::<T>(),
+        ) {
+            return;
+        }
+        self.add_systems(
+            PostUpdate,
+            reg_resource_change::<T>.in_set(AxonSystemSet::Change),
+        );
+    }
     fn send_axon_client_event<T: AxonEvent + Serialize>(
         &mut self,
         id: u64,
         event: &T,
         client_id: u64,
-    ) {
-        let type_id = T::axon_event_type();
-        let j = serde_sbin::to_bytes(event).unwrap();
-        self.world_mut().commands().trigger(AxonActionEvent {
-            act: ACTION_TYPE_INVOKE,
-            id: id,
-            t: type_id,
-            v: j,
-            client_id: client_id,
-        });
+    ) -> Result<(), SbinError> {
+        let action = invoke_action(id, event, client_id)?;
+        self.world_mut().commands().trigger(action);
+        Ok(())
     }
-    fn broadcast_axon_client_event<T: AxonEvent + Serialize>(&mut self, id: u64, event: &T) {
-        let type_id = T::axon_event_type();
-        let j = serde_sbin::to_bytes(event).unwrap();
-        self.world_mut().commands().trigger(AxonActionEvent {
-            act: ACTION_TYPE_INVOKE,
-            id: id,
-            t: type_id,
-            v: j,
-            client_id: 0,
-        });
+    fn broadcast_axon_client_event<T: AxonEvent + Serialize>(
+        &mut self,
+        id: u64,
+        event: &T,
+    ) -> Result<(), SbinError> {
+        let action = invoke_action(id, event, 0)?;
+        self.world_mut().commands().trigger(action);
+        Ok(())
     }
 }
 
+fn invoke_action<T: AxonEvent + Serialize>(
+    id: u64,
+    event: &T,
+    client_id: u64,
+) -> Result<AxonActionEvent, SbinError> {
+    Ok(AxonActionEvent {
+        act: ACTION_TYPE_INVOKE,
+        id,
+        t: T::axon_event_type(),
+        v: serde_sbin::to_bytes(event)?,
+        client_id,
+    })
+}
+
+#[derive(Debug)]
+pub enum AxonSendError {
+    Encode(SbinError),
+    /// 实体没有 AxonOwner
+    NoOwner(Entity),
+}
+
+impl fmt::Display for AxonSendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AxonSendError::Encode(e) => write!(f, "encode error: {}", e),
+            AxonSendError::NoOwner(entity) => write!(f, "entity {} has no owner", entity),
+        }
+    }
+}
+
+impl std::error::Error for AxonSendError {}
+
+impl From<SbinError> for AxonSendError {
+    fn from(e: SbinError) -> Self {
+        AxonSendError::Encode(e)
+    }
+}
+
+/// 在普通系统中向客户端发送事件，id 为目标对象（0 表示无目标）
+#[derive(SystemParam)]
+pub struct AxonSender<'w, 's> {
+    commands: Commands<'w, 's>,
+    clients: Query<'w, 's, (&'static AxonClient, Option<&'static AxonRoom>)>,
+    owners: Query<'w, 's, &'static AxonOwner>,
+}
+
+impl AxonSender<'_, '_> {
+    pub fn send_to<T: AxonEvent + Serialize>(
+        &mut self,
+        client_id: u64,
+        id: u64,
+        event: &T,
+    ) -> Result<(), AxonSendError> {
+        let action = invoke_action(id, event, client_id)?;
+        self.commands.trigger(action);
+        Ok(())
+    }
+
+    pub fn broadcast<T: AxonEvent + Serialize>(
+        &mut self,
+        id: u64,
+        event: &T,
+    ) -> Result<(), AxonSendError> {
+        self.send_to(0, id, event)
+    }
+
+    /// 发送给房间内的所有客户端，返回接收的客户端数
+    pub fn send_to_room<T: AxonEvent + Serialize>(
+        &mut self,
+        room: AxonRoom,
+        id: u64,
+        event: &T,
+    ) -> Result<usize, AxonSendError> {
+        let v = serde_sbin::to_bytes(event)?;
+        let mut n = 0;
+        for (client, _) in self.clients.iter().filter(|(_, r)| *r == Some(&room)) {
+            self.commands.trigger(AxonActionEvent {
+                act: ACTION_TYPE_INVOKE,
+                id,
+                t: T::axon_event_type(),
+                v: v.clone(),
+                client_id: client.id,
+            });
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    /// 发送给实体的所属客户端
+    pub fn send_to_owner<T: AxonEvent + Serialize>(
+        &mut self,
+        entity: Entity,
+        id: u64,
+        event: &T,
+    ) -> Result<(), AxonSendError> {
+        let owner = self
+            .owners
+            .get(entity)
+            .map_err(|_| AxonSendError::NoOwner(entity))?;
+        self.send_to(owner.client_id, id, event)
+    }
+}
+
+fn register_axon_type(
+    app: &mut App,
+    kind: AxonTypeKind,
+    type_id: u32,
+    schema: &'static [AxonField],
+    type_name: &'static str,
+) -> bool {
+    app.world_mut()
+        .resource_mut::<AxonTypeRegistry>()
+        .register(AxonTypeInfo {
+            kind,
+            type_id,
+            type_name,
+            schema,
+            restore: None,
+        })
+}
+
 #[inline]
 pub fn parse_u64(bytes: &[u8]) -> u64 {
     let mut n = 0u64;
@@ -181,43 +590,132 @@ pub fn parse_u32(bytes: &[u8]) -> u32 {
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
 pub enum AxonSystemSet {
+    /// 收集对象组件的增删
+    Detect,
     Spawn,
     Change,
     Despawn,
 }
 
+/// 实体上的同步对象类型，最后加入的类型为对外类型
+#[derive(Resource, Default)]
+pub struct AxonObjectSet {
+    types: HashMap<Entity, Vec<u32>>,
+    replicated: HashMap<Entity, u32>,
+    dirty: HashSet<Entity>,
+}
+
+impl AxonObjectSet {
+    /// 已同步给客户端的对象类型
+    pub fn object_type(&self, entity: Entity) -> Option<u32> {
+        self.replicated.get(&entity).copied()
+    }
+
+    /// 实体上的所有对象类型，按加入顺序排列
+    pub fn object_types(&self, entity: Entity) -> &[u32] {
+        self.types.get(&entity).map_or(&[], |v| v.as_slice())
+    }
+
+    /// 预先记录实体的对象类型顺序，用于从存档恢复时保持对外类型
+    #[cfg(feature = "server")]
+    pub(crate) fn restore_types(&mut self, entity: Entity, types: Vec<u32>) {
+        self.types.insert(entity, types);
+    }
+}
+
 fn reg_object_add<E: AxonObject + Component>(
     query: Query<Entity, Added<E>>,
-    mut commands: Commands<'_, '_>,
+    mut objects: ResMut<AxonObjectSet>,
 ) {
+    let t = E::axon_object_type();
     for entity in query.iter() {
-        let id: u64 = entity.to_bits();
-        let t = E::axon_object_type();
-        commands.trigger(AxonActionEvent {
-            act: ACTION_TYPE_SPAWN,
-            id,
-            t,
-            v: Vec::new(),
-            client_id: 0,
-        });
-        // println!("spawn: {}, {}", id, t);
+        // 已记录的类型保持原位置（如从存档恢复的顺序）
+        let types = objects.types.entry(entity).or_default();
+        if !types.contains(&t) {
+            types.push(t);
+        }
+        objects.dirty.insert(entity);
     }
 }
 
 fn reg_object_removed<E: AxonObject + Component>(
     mut removed: RemovedComponents<E>,
-    mut commands: Commands<'_, '_>,
+    query: Query<(), With<E>>,
+    mut objects: ResMut<AxonObjectSet>,
 ) {
+    let t = E::axon_object_type();
     for entity in removed.read() {
-        let id: u64 = entity.to_bits();
+        // 同一帧内移除后又重新插入
+        if query.contains(entity) {
+            continue;
+        }
+        if let Some(types) = objects.types.get_mut(&entity) {
+            types.retain(|v| *v != t);
+        }
+        objects.dirty.insert(entity);
+    }
+}
+
+/// 新对象发送 SPAWN，对外类型变化时发送 RETYPE
+fn reg_object_spawn(
+    mut objects: ResMut<AxonObjectSet>,
+    entities: &Entities,
+    mut commands: Commands<'_, '_>,
+) {
+    let objects = objects.as_mut();
+    let mut done = Vec::new();
+    for entity in objects.dirty.iter() {
+        if !entities.contains_spawned(*entity) {
+            continue;
+        }
+        let Some(&t) = objects.types.get(entity).and_then(|v| v.last()) else {
+            continue;
+        };
+        let act = match objects.replicated.insert(*entity, t) {
+            None => ACTION_TYPE_SPAWN,
+            Some(old) if old != t => ACTION_TYPE_RETYPE,
+            Some(_) => {
+                done.push(*entity);
+                continue;
+            }
+        };
         commands.trigger(AxonActionEvent {
-            act: ACTION_TYPE_DESPAWN,
-            id,
-            t: E::axon_object_type(),
+            act,
+            id: entity.to_bits(),
+            t,
             v: Vec::new(),
             client_id: 0,
         });
-        // println!("despawn: {}, {}", id, E::axon_object_type());
+        done.push(*entity);
+    }
+    for entity in done {
+        objects.dirty.remove(&entity);
+    }
+}
+
+/// 实体被销毁或不再带有任何对象组件时发送 DESPAWN
+fn reg_object_despawn(
+    mut objects: ResMut<AxonObjectSet>,
+    entities: &Entities,
+    mut commands: Commands<'_, '_>,
+) {
+    let objects = objects.as_mut();
+    for entity in objects.dirty.drain() {
+        let alive = entities.contains_spawned(entity);
+        let empty = objects.types.get(&entity).is_none_or(|v| v.is_empty());
+        if alive && !empty {
+            continue;
+        }
+        objects.types.remove(&entity);
+        if let Some(t) = objects.replicated.remove(&entity) {
+            commands.trigger(AxonActionEvent {
+                act: ACTION_TYPE_DESPAWN,
+                id: entity.to_bits(),
+                t,
+                v: Vec::new(),
+                client_id: 0,
+            });
+        }
     }
 }
 
@@ -228,7 +726,13 @@ fn reg_variant_change<V: AxonVariant + Component + Serialize>(
     for (entity, variant) in changed.iter() {
         let id: u64 = entity.to_bits();
         let t = V::axon_variant_type();
-        let j = serde_sbin::to_bytes(variant).unwrap();
+        let j = match serde_sbin::to_bytes(variant) {
+            Ok(j) => j,
+            Err(e) => {
+                warn!("axon variant {} encode error: {}", t, e);
+                continue;
+            }
+        };
         commands.trigger(AxonActionEvent {
             act: ACTION_TYPE_CHANGE,
             id,
@@ -239,3 +743,99 @@ fn reg_variant_change<V: AxonVariant + Component + Serialize>(
         // println!("change: {}, {}", id, t);
     }
 }
+
+fn reg_resource_change<R: AxonResource + Resource + Serialize>(
+    resource: Option<Res<R>>,
+    mut commands: Commands<'_, '_>,
+) {
+    let Some(resource) = resource else {
+        return;
+    };
+    if !resource.is_changed() {
+        return;
+    }
+    let t = R::axon_resource_type();
+    match serde_sbin::to_bytes(&*resource) {
+        Ok(j) => commands.trigger(AxonActionEvent {
+            act: ACTION_TYPE_RESOURCE,
+            id: 0,
+            t,
+            v: j,
+            client_id: 0,
+        }),
+        Err(e) => warn!("axon resource {} encode error: {}", t, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct TestObject;
+
+    impl AxonObject for TestObject {
+        fn axon_object_type() -> u32 {
+            1
+        }
+    }
+
+    #[derive(Component, Serialize)]
+    struct TestVariant {
+        x: f32,
+    }
+
+    impl AxonVariant for TestVariant {
+        fn axon_variant_type() -> u32 {
+            1
+        }
+        fn axon_variant_schema() -> &'static [AxonField] {
+            &[AxonField {
+                name: "x",
+                ty: "f32",
+            }]
+        }
+    }
+
+    #[test]
+    fn test_registry_idempotent() {
+        let mut app = App::new();
+        app.add_plugins(AxonPlugin);
+        app.add_axon_object::<TestObject>();
+        app.add_axon_object::<TestObject>();
+        app.add_axon_variant::<TestVariant>();
+
+        let registry = app.world().resource::<AxonTypeRegistry>();
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.iter_kind(AxonTypeKind::Object).count(), 1);
+
+        let info = registry.get(AxonTypeKind::Variant, 1).unwrap();
+        assert!(info.type_name.ends_with("TestVariant"));
+        assert_eq!(
+            info.schema,
+            &[AxonField {
+                name: "x",
+                ty: "f32"
+            }]
+        );
+        assert!(registry.find_by_name(info.type_name).is_some());
+    }
+
+    #[derive(Component)]
+    struct OtherObject;
+
+    impl AxonObject for OtherObject {
+        fn axon_object_type() -> u32 {
+            1
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "type id 1 collision")]
+    fn test_registry_collision() {
+        let mut app = App::new();
+        app.add_plugins(AxonPlugin);
+        app.add_axon_object::<TestObject>();
+        app.add_axon_object::<OtherObject>();
+    }
+}
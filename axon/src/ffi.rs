@@ -1,261 +1,1550 @@
-use renet::{ConnectionConfig, DefaultChannel, RenetClient};
-use renet_netcode::{ClientAuthentication, NetcodeClientTransport};
-use std::ffi::{c_char, c_float, c_int, c_uchar, CStr, c_ulong};
-use std::net::UdpSocket;
-use std::ptr::addr_of_mut;
-use std::time::SystemTime;
+use crate::json::{axon_json_str_to_sbin, axon_sbin_to_json, AxonJsonError};
+use crate::mirror::{AxonChange, AxonMirror};
+use crate::netcode::AxonNetcodeClientTransport;
+use crate::protocol::{
+    axon_compression_flag, axon_decode_message, axon_parse_action, axon_user_data, axon_user_flags,
+    axon_write_action, ACTION_TYPE_CHANGE, ACTION_TYPE_DESPAWN, ACTION_TYPE_INVOKE,
+    ACTION_TYPE_SPAWN, AXON_CHANNEL_RELIABLE_ORDERED, AXON_FLAG_COMPRESSION, AXON_USER_DATA_BYTES,
+};
+use crate::transport::AxonClientTransport;
+use renet::{ChannelConfig, ConnectionConfig, DisconnectReason, SendType};
+use renet_netcode::NetcodeDisconnectReason;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::ffi::{c_char, c_float, c_int, c_uchar, c_void, CStr, CString};
+use std::net::SocketAddr;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
 
-/// 错误消息缓冲区大小
-const ERROR_BUF_SIZE: usize = 512;
+/// FFI 函数的返回码
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxonFfiError {
+    Ok = 0,
+    /// 必需的指针参数为 null
+    NullPointer = 1,
+    /// 参数不合法，如地址无法解析、dt 为负数
+    InvalidArgument = 2,
+    /// 字符串不是合法的 UTF-8
+    InvalidUtf8 = 3,
+    /// 传输层错误，如 socket 绑定失败、发送失败
+    Transport = 4,
+    /// 尚未连接到服务器
+    NotConnected = 5,
+    /// 收到无法解码的消息
+    Decode = 6,
+    /// bevy_axon_ffi_next_action 已取完本帧的动作
+    End = 7,
+    /// 世界镜像中没有要查询的对象、变体或资源
+    NotFound = 8,
+    /// 函数内部发生 panic，game 已中毒，之后的调用都返回 Panic，只能 exit
+    Panic = 9,
+}
 
-/// 全局固定错误消息缓冲区
-static mut ERROR_BUF: [c_char; ERROR_BUF_SIZE] = [0; ERROR_BUF_SIZE];
+/// 连接状态
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxonFfiState {
+    Connecting = 0,
+    Connected = 1,
+    Disconnected = 2,
+}
 
-/// 设置错误消息到固定缓冲区
-fn set_error(msg: &str) {
-    unsafe {
-        // 清空缓冲区
-        ERROR_BUF = [0; ERROR_BUF_SIZE];
-        // 复制消息到缓冲区（确保不越界）
-        let bytes = msg.as_bytes();
-        let len = bytes.len().min(ERROR_BUF_SIZE - 1);
-        for i in 0..len {
-            ERROR_BUF[i] = bytes[i] as c_char;
+/// 断开原因，由 renet 与 netcode 的断开原因映射而来
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxonDisconnectReason {
+    /// 尚未断开
+    None = 0,
+    /// 本地调用了 bevy_axon_ffi_disconnect
+    DisconnectedByClient = 1,
+    /// 服务器主动断开
+    DisconnectedByServer = 2,
+    /// 服务器拒绝连接，通常是服务器已满
+    ConnectionDenied = 3,
+    /// 连接过程中超时：服务器不可达、协议 id 不一致或超过 connect_timeout_ms
+    ConnectTimedOut = 4,
+    /// 连接后长时间没有收到服务器的包
+    ConnectionTimedOut = 5,
+    /// 连接令牌过期
+    ConnectTokenExpired = 6,
+    /// socket 收发失败
+    Transport = 7,
+    /// 收到无法解析的包或未知通道
+    Protocol = 8,
+    /// 通道出错，如可靠通道未确认的消息超过内存上限
+    Channel = 9,
+    /// 函数内部发生 panic，game 已中毒
+    Panicked = 10,
+}
+
+impl From<NetcodeDisconnectReason> for AxonDisconnectReason {
+    fn from(reason: NetcodeDisconnectReason) -> Self {
+        match reason {
+            NetcodeDisconnectReason::ConnectTokenExpired => Self::ConnectTokenExpired,
+            NetcodeDisconnectReason::ConnectionTimedOut => Self::ConnectionTimedOut,
+            NetcodeDisconnectReason::ConnectionResponseTimedOut
+            | NetcodeDisconnectReason::ConnectionRequestTimedOut => Self::ConnectTimedOut,
+            NetcodeDisconnectReason::ConnectionDenied => Self::ConnectionDenied,
+            NetcodeDisconnectReason::DisconnectedByClient => Self::DisconnectedByClient,
+            NetcodeDisconnectReason::DisconnectedByServer => Self::DisconnectedByServer,
         }
-        // 确保以 null 结尾
-        ERROR_BUF[len] = 0;
     }
 }
 
-/// 清除错误消息
-fn clear_error() {
-    unsafe {
-        ERROR_BUF = [0; ERROR_BUF_SIZE];
+impl From<DisconnectReason> for AxonDisconnectReason {
+    fn from(reason: DisconnectReason) -> Self {
+        match reason {
+            DisconnectReason::Transport => Self::Transport,
+            DisconnectReason::DisconnectedByClient => Self::DisconnectedByClient,
+            DisconnectReason::DisconnectedByServer => Self::DisconnectedByServer,
+            DisconnectReason::PacketSerialization(_)
+            | DisconnectReason::PacketDeserialization(_)
+            | DisconnectReason::ReceivedInvalidChannelId(_) => Self::Protocol,
+            DisconnectReason::SendChannelError { .. }
+            | DisconnectReason::ReceiveChannelError { .. } => Self::Channel,
+        }
+    }
+}
+
+/// 通道发送方式，对应 renet 的 SendType
+pub const AXON_SEND_UNRELIABLE: u32 = 0;
+pub const AXON_SEND_RELIABLE_UNORDERED: u32 = 1;
+pub const AXON_SEND_RELIABLE_ORDERED: u32 = 2;
+
+/// 单个通道的配置，客户端与服务器两个方向使用同一组通道
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AxonChannelOptions {
+    pub channel_id: u8,
+    /// AXON_SEND_* 之一
+    pub send_type: u32,
+    /// 未确认消息最多占用的字节数
+    pub max_memory_usage_bytes: usize,
+    /// 可靠通道的重发间隔（毫秒），0 为默认 300
+    pub resend_time_ms: u64,
+}
+
+/// bevy_axon_ffi_create_ex 的参数，全部置零（除 server_addr）即为默认值
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AxonClientOptions {
+    /// 服务器地址，如 "192.168.1.2:5000" 或 "[::1]:5000"
+    pub server_addr: *const c_char,
+    /// 本地绑定地址，null 时按服务器地址族绑定 0.0.0.0:0 或 [::]:0
+    pub bind_addr: *const c_char,
+    pub client_id: u64,
+    /// 需与服务器一致
+    pub protocol_id: u64,
+    /// 连接时携带的数据（见 protocol 模块），null 时只声明支持压缩
+    pub user_data: *const u8,
+    /// 不超过 256
+    pub user_data_len: usize,
+    /// 每次更新可发送的字节数，0 为默认 60000
+    pub available_bytes_per_tick: u64,
+    /// 通道配置（需与服务器一致），null 时使用默认的三个通道
+    pub channels: *const AxonChannelOptions,
+    pub channel_count: usize,
+    /// 超过该时间（毫秒）仍未连接则断开，0 时只依赖 netcode 自身的超时
+    pub connect_timeout_ms: u64,
+}
+
+/// AxonNetStats 最多记录的通道数，超出的通道不计数
+pub const AXON_STATS_MAX_CHANNELS: usize = 8;
+
+/// 单个通道的消息计数，从创建起累计
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AxonChannelStats {
+    pub channel_id: u8,
+    /// 经 invoke 发出的消息数
+    pub messages_sent: u64,
+    /// update 收到的消息数
+    pub messages_received: u64,
+}
+
+/// 网络统计，rtt、丢包率与速率在每次 update 时从 renet 刷新
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AxonNetStats {
+    /// 往返时间（毫秒）
+    pub rtt_ms: f64,
+    /// 丢包率，0 到 1
+    pub packet_loss: f64,
+    pub bytes_sent_per_second: f64,
+    pub bytes_received_per_second: f64,
+    /// channels 中有效的项数，顺序与 update 读取通道的顺序一致
+    pub channel_count: usize,
+    pub channels: [AxonChannelStats; AXON_STATS_MAX_CHANNELS],
+}
+
+/// 动作回调: user_data, id, type, payload, len；payload 只在回调内有效
+/// 回调中的 panic 会被 update 捕获，之后 game 被标记为中毒
+pub type AxonActionCallback =
+    Option<unsafe extern "C-unwind" fn(*mut c_void, u64, u32, *const u8, usize)>;
+
+/// 其他动作（RESOURCE、RETYPE、SYNC 等）的回调: user_data, act, id, type, payload, len
+pub type AxonOtherActionCallback =
+    Option<unsafe extern "C-unwind" fn(*mut c_void, u8, u64, u32, *const u8, usize)>;
+
+/// 连接状态变化回调: user_data, state, reason
+pub type AxonStateCallback =
+    Option<unsafe extern "C-unwind" fn(*mut c_void, AxonFfiState, AxonDisconnectReason)>;
+
+/// bevy_axon_ffi_update 中同步调用的回调，为 null 的回调被跳过
+/// 回调内可调用 bevy_axon_ffi_invoke（本帧发出）与查询函数，不可调用 update 与 exit
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AxonCallbacks {
+    /// 原样传给每个回调
+    pub user_data: *mut c_void,
+    pub on_spawn: AxonActionCallback,
+    pub on_despawn: AxonActionCallback,
+    pub on_change: AxonActionCallback,
+    pub on_invoke: AxonActionCallback,
+    pub on_other: AxonOtherActionCallback,
+    pub on_state: AxonStateCallback,
+}
+
+impl AxonCallbacks {
+    /// 调用动作对应的回调
+    unsafe fn dispatch(&self, act: u8, id: u64, t: u32, v: &[u8]) {
+        let callback = match act {
+            ACTION_TYPE_SPAWN => self.on_spawn,
+            ACTION_TYPE_DESPAWN => self.on_despawn,
+            ACTION_TYPE_CHANGE => self.on_change,
+            ACTION_TYPE_INVOKE => self.on_invoke,
+            _ => {
+                if let Some(f) = self.on_other {
+                    f(self.user_data, act, id, t, v.as_ptr(), v.len());
+                }
+                return;
+            }
+        };
+        if let Some(f) = callback {
+            f(self.user_data, id, t, v.as_ptr(), v.len());
+        }
     }
 }
 
+thread_local! {
+    /// 没有 Game 实例可记录时（如创建失败）的错误消息
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+    /// JSON 与 sbin 转换的输出，在当前线程下一次转换前有效
+    static CONVERTED: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+fn to_cstring(msg: &str) -> CString {
+    CString::new(msg.replace('\0', " ")).unwrap_or_default()
+}
+
+/// 记录错误消息到当前线程
+fn fail(code: AxonFfiError, msg: String) -> AxonFfiError {
+    LAST_ERROR.with(|v| *v.borrow_mut() = Some(to_cstring(&msg)));
+    code
+}
+
 pub struct Game {
     buf: Vec<u8>,
-    client: RenetClient,
-    transport: NetcodeClientTransport,
+    /// buf 中每个通道数据的结束位置与通道 id
+    segments: Vec<(usize, u8)>,
+    /// next_action 在 buf 中的位置
+    cursor: usize,
+    /// 已配置的通道，可靠有序通道在前
+    channels: Vec<u8>,
+    /// next_action 最后取出或回调中正在分发的动作所在通道
+    channel: u8,
+    transport: AxonNetcodeClientTransport,
+    /// 该实例最后一次错误消息，只读的函数（*const Game）也能记录
+    error: RefCell<Option<CString>>,
+    /// 是否声明了压缩，服务器消息是否带消息头
+    compressed: bool,
+    connect_timeout: Option<Duration>,
+    connecting: Duration,
+    /// 超过 connect_timeout 后由本地断开
+    timed_out: bool,
+    callbacks: Option<AxonCallbacks>,
+    /// 上一次通知回调的状态
+    last_state: AxonFfiState,
+    /// 正在 update 中调用回调
+    dispatching: bool,
+    stats: AxonNetStats,
+    mirror: AxonMirror,
+    /// next_change 在本帧变化中的位置
+    change_cursor: usize,
+    /// 曾在函数内部 panic，内部状态可能不一致
+    poisoned: Cell<bool>,
 }
 
-/// 获取最后一次错误消息
-/// 返回: 指向错误消息字符串的指针（以 null 结尾的 C 字符串）
-/// 如果错误消息为空，返回 null
-/// 注意: 返回的指针指向全局固定缓冲区，调用者不应释放
-#[no_mangle]
-pub extern "C" fn bevy_axon_ffi_errmsg() -> *const c_char {
-    unsafe {
-        let ptr = addr_of_mut!(ERROR_BUF);
-        if (*ptr)[0] == 0 {
-            std::ptr::null()
+impl Game {
+    fn new(transport: AxonNetcodeClientTransport, compressed: bool) -> Self {
+        let channels = transport.channels();
+        let mut stats = AxonNetStats::default();
+        for (i, channel) in channels.iter().take(AXON_STATS_MAX_CHANNELS).enumerate() {
+            stats.channels[i].channel_id = *channel;
+        }
+        stats.channel_count = channels.len().min(AXON_STATS_MAX_CHANNELS);
+        Self {
+            buf: Vec::new(),
+            segments: Vec::new(),
+            cursor: 0,
+            channels,
+            channel: AXON_CHANNEL_RELIABLE_ORDERED,
+            transport,
+            error: RefCell::new(None),
+            compressed,
+            connect_timeout: None,
+            connecting: Duration::ZERO,
+            timed_out: false,
+            callbacks: None,
+            last_state: AxonFfiState::Connecting,
+            dispatching: false,
+            stats,
+            mirror: AxonMirror::default(),
+            change_cursor: 0,
+            poisoned: Cell::new(false),
+        }
+    }
+
+    /// 通道的消息计数，未记录的通道返回 None
+    fn channel_stats(&mut self, channel: u8) -> Option<&mut AxonChannelStats> {
+        self.stats.channels[..self.stats.channel_count]
+            .iter_mut()
+            .find(|v| v.channel_id == channel)
+    }
+
+    fn refresh_stats(&mut self) {
+        let info = self.transport.client().network_info();
+        self.stats.rtt_ms = info.rtt * 1000.0;
+        self.stats.packet_loss = info.packet_loss;
+        self.stats.bytes_sent_per_second = info.bytes_sent_per_second;
+        self.stats.bytes_received_per_second = info.bytes_received_per_second;
+    }
+
+    fn state(&self) -> AxonFfiState {
+        if self.poisoned.get()
+            || self.transport.is_disconnected()
+            || self.transport.transport().disconnect_reason().is_some()
+        {
+            AxonFfiState::Disconnected
+        } else if self.transport.is_connected() {
+            AxonFfiState::Connected
         } else {
-            (*ptr).as_mut_ptr()
+            AxonFfiState::Connecting
+        }
+    }
+
+    /// netcode 的原因更具体，优先使用
+    fn disconnect_reason(&self) -> AxonDisconnectReason {
+        if self.timed_out {
+            return AxonDisconnectReason::ConnectTimedOut;
+        }
+        if let Some(reason) = self.transport.transport().disconnect_reason() {
+            return reason.into();
+        }
+        match self.transport.client().disconnect_reason() {
+            Some(reason) => reason.into(),
+            None => AxonDisconnectReason::None,
         }
     }
+
+    /// 推进传输层并把收到的消息解码到 buf
+    fn receive(&mut self, duration: Duration) -> AxonFfiError {
+        let mut result = AxonFfiError::Ok;
+        // 断开后 netcode 会返回错误，此时以断开原因为准
+        if let Err(e) = self.transport.update(duration) {
+            if self.state() != AxonFfiState::Disconnected {
+                result = self.fail(
+                    AxonFfiError::Transport,
+                    format!("[bevy_axon_ffi_update] transport update error: {}", e),
+                );
+            }
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            if self.transport.is_connected() {
+                self.connect_timeout = None;
+            } else if !self.transport.is_disconnected() {
+                self.connecting += duration;
+                if self.connecting >= timeout {
+                    self.connect_timeout = None;
+                    self.timed_out = true;
+                    self.transport.disconnect();
+                }
+            }
+        }
+
+        if self.transport.is_connected() {
+            for i in 0..self.channels.len() {
+                let channel = self.channels[i];
+                while let Some(message) = self.transport.receive(channel) {
+                    if let Some(stats) = self.channel_stats(channel) {
+                        stats.messages_received += 1;
+                    }
+                    if !self.compressed {
+                        self.buf.extend_from_slice(&message);
+                        continue;
+                    }
+                    match axon_decode_message(&message) {
+                        Some(message) => self.buf.extend_from_slice(&message),
+                        None => {
+                            result = self.fail(
+                                AxonFfiError::Decode,
+                                format!(
+                                    "[bevy_axon_ffi_update] message decode error on channel {}",
+                                    channel
+                                ),
+                            );
+                        }
+                    }
+                }
+                if self.segments.last().map_or(0, |v| v.0) < self.buf.len() {
+                    self.segments.push((self.buf.len(), channel));
+                }
+            }
+        }
+        result
+    }
+
+    /// buf 中 pos 处的动作所在通道
+    fn channel_at(&self, pos: usize) -> u8 {
+        self.segments
+            .iter()
+            .find(|(end, _)| pos < *end)
+            .map_or(AXON_CHANNEL_RELIABLE_ORDERED, |v| v.1)
+    }
+
+    fn fail(&self, code: AxonFfiError, msg: String) -> AxonFfiError {
+        *self.error.borrow_mut() = Some(to_cstring(&msg));
+        code
+    }
 }
 
-/// 创建游戏实例，返回 Game 指针（C# 用 IntPtr 接收）
-/// addr: 服务器地址字符串（以 null 结尾的 C 字符串）
-/// 失败返回 null (0)
-#[no_mangle]
-pub extern "C" fn bevy_axon_ffi_create(addr: *const c_char, client_id: c_ulong) -> *mut Game {
-    if addr.is_null() {
-        let msg = "[bevy_axon_ffi_create] error: addr is null";
-        println!("{}", msg);
-        set_error(msg);
-        return std::ptr::null_mut();
-    }
-
-    let addr_str = unsafe {
-        match CStr::from_ptr(addr).to_str() {
-            Ok(s) => s,
-            Err(e) => {
-                let msg = format!("[bevy_axon_ffi_create] error: invalid utf8 string: {:?}", e);
-                println!("{}", msg);
-                set_error(&msg);
-                return std::ptr::null_mut();
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "unknown panic"
+    }
+}
+
+/// 捕获 f 中的 panic，不让它展开到 C# 或 C++ 中
+/// panic 时消息记录到当前线程与 game（非 null 时），game 被标记为中毒，返回 panicked
+unsafe fn guard<R>(name: &str, game: *const Game, panicked: R, f: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(v) => v,
+        Err(payload) => {
+            let msg = format!("[{}] panic: {}", name, panic_message(&*payload));
+            fail(AxonFfiError::Panic, msg.clone());
+            if !game.is_null() {
+                // panic 后 f 中的引用均已失效，只通过共享引用写入 Cell
+                let game = &*game;
+                game.poisoned.set(true);
+                *game.error.borrow_mut() = Some(to_cstring(&msg));
             }
+            panicked
         }
-    };
+    }
+}
 
-    let client = RenetClient::new(ConnectionConfig::default());
+/// 同 guard，已中毒的 game 直接返回 Panic，错误消息保留为最初的 panic
+unsafe fn guard_game(
+    name: &str,
+    game: *const Game,
+    f: impl FnOnce() -> AxonFfiError,
+) -> AxonFfiError {
+    guard_game_or(name, game, AxonFfiError::Panic, f)
+}
 
-    let socket = match UdpSocket::bind("127.0.0.1:0") {
-        Ok(s) => s,
-        Err(e) => {
-            let msg = format!(
-                "[bevy_axon_ffi_create] error: failed to bind udp socket: {:?}",
-                e
-            );
-            println!("{}", msg);
-            set_error(&msg);
-            return std::ptr::null_mut();
+/// 同 guard_game，用于不返回 AxonFfiError 的函数：已中毒或 panic 时返回 panicked
+unsafe fn guard_game_or<R: Copy>(
+    name: &str,
+    game: *const Game,
+    panicked: R,
+    f: impl FnOnce() -> R,
+) -> R {
+    if !game.is_null() && (*game).poisoned.get() {
+        return panicked;
+    }
+    guard(name, game, panicked, f)
+}
+
+/// 获取最后一次错误消息
+/// game: Game 指针；为 null 时返回当前线程上不属于任何实例的错误（如创建失败）
+/// 返回: 以 null 结尾的 C 字符串，没有错误时返回 null
+/// 注意: 指针在该 game 下一次出错或被释放前有效，调用者不应释放
+///
+/// # Safety
+/// game 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_errmsg(game: *const Game) -> *const c_char {
+    guard("bevy_axon_ffi_errmsg", game, std::ptr::null(), || {
+        if game.is_null() {
+            return LAST_ERROR.with(|v| {
+                v.borrow()
+                    .as_ref()
+                    .map(|v| v.as_ptr())
+                    .unwrap_or(std::ptr::null())
+            });
         }
-    };
+        // CString 的数据在堆上，借用结束后指针仍有效，直到下一次出错
+        let game = &*game;
+        game.error
+            .borrow()
+            .as_ref()
+            .map(|v| v.as_ptr())
+            .unwrap_or(std::ptr::null())
+    })
+}
 
-    if let Err(e) = socket.set_nonblocking(true) {
-        let msg = format!(
-            "[bevy_axon_ffi_create] error: failed to set nonblocking: {:?}",
-            e
-        );
-        println!("{}", msg);
-        set_error(&msg);
-        return std::ptr::null_mut();
+unsafe fn read_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, (AxonFfiError, String)> {
+    if ptr.is_null() {
+        return Err((AxonFfiError::NullPointer, format!("{} is null", name)));
     }
+    CStr::from_ptr(ptr).to_str().map_err(|e| {
+        (
+            AxonFfiError::InvalidUtf8,
+            format!("{} is not a valid utf8 string: {:?}", name, e),
+        )
+    })
+}
+
+fn parse_addr(addr: &str, name: &str) -> Result<SocketAddr, (AxonFfiError, String)> {
+    addr.parse().map_err(|e| {
+        (
+            AxonFfiError::InvalidArgument,
+            format!("failed to parse {} '{}': {:?}", name, addr, e),
+        )
+    })
+}
 
-    let current_time = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-        Ok(t) => t,
-        Err(e) => {
-            let msg = format!("[bevy_axon_ffi_create] error: system time error: {:?}", e);
-            println!("{}", msg);
-            set_error(&msg);
-            return std::ptr::null_mut();
+fn channel_config(options: &AxonChannelOptions) -> Result<ChannelConfig, (AxonFfiError, String)> {
+    let resend_time = match options.resend_time_ms {
+        0 => Duration::from_millis(300),
+        v => Duration::from_millis(v),
+    };
+    let send_type = match options.send_type {
+        AXON_SEND_UNRELIABLE => SendType::Unreliable,
+        AXON_SEND_RELIABLE_UNORDERED => SendType::ReliableUnordered { resend_time },
+        AXON_SEND_RELIABLE_ORDERED => SendType::ReliableOrdered { resend_time },
+        v => {
+            return Err((
+                AxonFfiError::InvalidArgument,
+                format!("channel {} has unknown send type {}", options.channel_id, v),
+            ));
         }
     };
+    if options.max_memory_usage_bytes == 0 {
+        return Err((
+            AxonFfiError::InvalidArgument,
+            format!(
+                "channel {} has zero max_memory_usage_bytes",
+                options.channel_id
+            ),
+        ));
+    }
+    Ok(ChannelConfig {
+        channel_id: options.channel_id,
+        max_memory_usage_bytes: options.max_memory_usage_bytes,
+        send_type,
+    })
+}
 
-    let server_addr = match addr_str.parse() {
-        Ok(addr) => addr,
-        Err(e) => {
-            let msg = format!(
-                "[bevy_axon_ffi_create] error: failed to parse server address '{}': {:?}",
-                addr_str, e
-            );
-            println!("{}", msg);
-            set_error(&msg);
-            return std::ptr::null_mut();
+/// 按选项创建 Game，错误返回码与不带函数名前缀的消息
+unsafe fn create_game(options: &AxonClientOptions) -> Result<Game, (AxonFfiError, String)> {
+    let server_addr = parse_addr(read_str(options.server_addr, "server_addr")?, "server_addr")?;
+    let bind_addr = if options.bind_addr.is_null() {
+        if server_addr.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
         }
+    } else {
+        parse_addr(read_str(options.bind_addr, "bind_addr")?, "bind_addr")?
     };
+    if bind_addr.is_ipv4() != server_addr.is_ipv4() {
+        return Err((
+            AxonFfiError::InvalidArgument,
+            format!(
+                "bind_addr {} and server_addr {} are different address families",
+                bind_addr, server_addr
+            ),
+        ));
+    }
 
-    let authentication = ClientAuthentication::Unsecure {
-        server_addr,
-        client_id: client_id as u64,
-        user_data: None,
-        protocol_id: 0,
+    let user_data = if options.user_data.is_null() {
+        if options.user_data_len > 0 {
+            return Err((AxonFfiError::NullPointer, "user_data is null".to_string()));
+        }
+        axon_user_data(axon_compression_flag())
+    } else {
+        if options.user_data_len > AXON_USER_DATA_BYTES {
+            return Err((
+                AxonFfiError::InvalidArgument,
+                format!(
+                    "user_data_len {} exceeds {} bytes",
+                    options.user_data_len, AXON_USER_DATA_BYTES
+                ),
+            ));
+        }
+        let mut data = [0u8; AXON_USER_DATA_BYTES];
+        data[..options.user_data_len].copy_from_slice(std::slice::from_raw_parts(
+            options.user_data,
+            options.user_data_len,
+        ));
+        data
     };
+    let compressed = axon_user_flags(&user_data) & AXON_FLAG_COMPRESSION != 0;
 
-    let transport = match NetcodeClientTransport::new(current_time, authentication, socket) {
-        Ok(t) => t,
-        Err(e) => {
-            let msg = format!(
-                "[bevy_axon_ffi_create] error: failed to create transport: {:?}",
-                e
-            );
-            println!("{}", msg);
-            set_error(&msg);
-            return std::ptr::null_mut();
+    let mut config = ConnectionConfig::default();
+    if options.available_bytes_per_tick > 0 {
+        config.available_bytes_per_tick = options.available_bytes_per_tick;
+    }
+    if options.channel_count > 0 {
+        if options.channels.is_null() {
+            return Err((AxonFfiError::NullPointer, "channels is null".to_string()));
         }
-    };
+        let channels = std::slice::from_raw_parts(options.channels, options.channel_count);
+        let mut ids = HashSet::new();
+        let mut list = Vec::new();
+        for channel in channels {
+            if !ids.insert(channel.channel_id) {
+                return Err((
+                    AxonFfiError::InvalidArgument,
+                    format!("duplicate channel id {}", channel.channel_id),
+                ));
+            }
+            list.push(channel_config(channel)?);
+        }
+        // 同步消息固定走可靠有序通道
+        if !list.iter().any(|v| {
+            v.channel_id == AXON_CHANNEL_RELIABLE_ORDERED
+                && matches!(v.send_type, SendType::ReliableOrdered { .. })
+        }) {
+            return Err((
+                AxonFfiError::InvalidArgument,
+                format!(
+                    "channel {} must be reliable ordered",
+                    AXON_CHANNEL_RELIABLE_ORDERED
+                ),
+            ));
+        }
+        config.server_channels_config = list.clone();
+        config.client_channels_config = list;
+    }
+
+    let transport = AxonNetcodeClientTransport::connect_with(
+        &bind_addr.to_string(),
+        &server_addr.to_string(),
+        options.client_id,
+        Some(user_data),
+        options.protocol_id,
+        config,
+    )
+    .map_err(|e| (AxonFfiError::Transport, e.to_string()))?;
+
+    let mut game = Game::new(transport, compressed);
+    if options.connect_timeout_ms > 0 {
+        game.connect_timeout = Some(Duration::from_millis(options.connect_timeout_ms));
+    }
+    Ok(game)
+}
 
-    // 清除之前的错误
-    clear_error();
+/// 创建 Game 并写入 out_game
+unsafe fn create_into(
+    name: &str,
+    options: &AxonClientOptions,
+    out_game: *mut *mut Game,
+) -> AxonFfiError {
+    match create_game(options) {
+        Ok(game) => {
+            LAST_ERROR.with(|v| *v.borrow_mut() = None);
+            *out_game = Box::into_raw(Box::new(game));
+            AxonFfiError::Ok
+        }
+        Err((code, msg)) => fail(code, format!("[{}] error: {}", name, msg)),
+    }
+}
 
-    let game = Box::new(Game {
-        buf: Vec::new(),
-        client,
-        transport,
-    });
+/// 创建游戏实例
+/// addr: 服务器地址字符串（以 null 结尾的 C 字符串）
+/// out_game: 成功时写入 Game 指针，失败时写入 null
+/// 其余选项使用默认值，见 bevy_axon_ffi_create_ex
+///
+/// # Safety
+/// addr 为 null 或以 null 结尾的字符串；out_game 为 null 或可写
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_create(
+    addr: *const c_char,
+    client_id: u64,
+    out_game: *mut *mut Game,
+) -> AxonFfiError {
+    guard(
+        "bevy_axon_ffi_create",
+        std::ptr::null(),
+        AxonFfiError::Panic,
+        || {
+            if out_game.is_null() {
+                return fail(
+                    AxonFfiError::NullPointer,
+                    "[bevy_axon_ffi_create] error: out_game is null".to_string(),
+                );
+            }
+            *out_game = std::ptr::null_mut();
+            let options = AxonClientOptions {
+                server_addr: addr,
+                bind_addr: std::ptr::null(),
+                client_id,
+                protocol_id: 0,
+                user_data: std::ptr::null(),
+                user_data_len: 0,
+                available_bytes_per_tick: 0,
+                channels: std::ptr::null(),
+                channel_count: 0,
+                connect_timeout_ms: 0,
+            };
+            create_into("bevy_axon_ffi_create", &options, out_game)
+        },
+    )
+}
 
-    Box::into_raw(game)
+/// 按选项创建游戏实例
+/// options: 连接选项，调用返回后即可释放
+/// out_game: 成功时写入 Game 指针，失败时写入 null
+///
+/// # Safety
+/// options 为 null 或指向有效的 AxonClientOptions，其中的指针满足各字段说明；out_game 为 null 或可写
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_create_ex(
+    options: *const AxonClientOptions,
+    out_game: *mut *mut Game,
+) -> AxonFfiError {
+    guard(
+        "bevy_axon_ffi_create_ex",
+        std::ptr::null(),
+        AxonFfiError::Panic,
+        || {
+            if out_game.is_null() {
+                return fail(
+                    AxonFfiError::NullPointer,
+                    "[bevy_axon_ffi_create_ex] error: out_game is null".to_string(),
+                );
+            }
+            *out_game = std::ptr::null_mut();
+            if options.is_null() {
+                return fail(
+                    AxonFfiError::NullPointer,
+                    "[bevy_axon_ffi_create_ex] error: options is null".to_string(),
+                );
+            }
+            create_into("bevy_axon_ffi_create_ex", &*options, out_game)
+        },
+    )
 }
 
-/// 退出游戏实例
+/// 退出游戏实例，尚未断开时先发送断开包
 /// ptr: Game 指针（从 create 返回）
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
 #[no_mangle]
-pub extern "C" fn bevy_axon_ffi_exit(ptr: *mut Game) {
-    if ptr.is_null() {
-        return;
-    }
-    unsafe {
+pub unsafe extern "C" fn bevy_axon_ffi_exit(ptr: *mut Game) {
+    guard("bevy_axon_ffi_exit", std::ptr::null(), (), || {
+        if ptr.is_null() {
+            return;
+        }
         // 释放 Game 内存
-        let _ = Box::from_raw(ptr);
-    }
+        let mut game = Box::from_raw(ptr);
+        game.transport.disconnect();
+    })
+}
+
+/// 立即向服务器发送断开包，之后状态为 Disconnected，句柄仍需 exit 释放
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_disconnect(ptr: *mut Game) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_disconnect", ptr, || {
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_disconnect] error: game is null".to_string(),
+            );
+        }
+        let game = &mut *ptr;
+        game.transport.disconnect();
+        AxonFfiError::Ok
+    })
+}
+
+/// 获取连接状态，ptr 为 null 时返回 Disconnected
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_state(ptr: *const Game) -> AxonFfiState {
+    guard_game_or(
+        "bevy_axon_ffi_state",
+        ptr,
+        AxonFfiState::Disconnected,
+        || {
+            if ptr.is_null() {
+                return AxonFfiState::Disconnected;
+            }
+            (*ptr).state()
+        },
+    )
+}
+
+/// 获取断开原因，尚未断开时返回 None，game 中毒后返回 Panicked
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_disconnect_reason(ptr: *const Game) -> AxonDisconnectReason {
+    guard_game_or(
+        "bevy_axon_ffi_disconnect_reason",
+        ptr,
+        AxonDisconnectReason::Panicked,
+        || {
+            if ptr.is_null() {
+                return AxonDisconnectReason::None;
+            }
+            (*ptr).disconnect_reason()
+        },
+    )
 }
 
 /// 检查游戏实例是否已连接
 /// 返回: 1 表示已连接，0 表示未连接
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
 #[no_mangle]
-pub extern "C" fn bevy_axon_ffi_is_connected(ptr: *const Game) -> c_int {
-    if ptr.is_null() {
-        return 0;
-    }
-    let game = unsafe { &*ptr };
-    if game.client.is_connected() {
-        1
-    } else {
-        0
-    }
+pub unsafe extern "C" fn bevy_axon_ffi_is_connected(ptr: *const Game) -> c_int {
+    guard_game_or("bevy_axon_ffi_is_connected", ptr, 0, || {
+        if ptr.is_null() {
+            return 0;
+        }
+        let game = &*ptr;
+        if game.state() == AxonFfiState::Connected {
+            1
+        } else {
+            0
+        }
+    })
 }
 
 /// 更新游戏实例
 /// dt: 时间增量（秒）
-/// out_len: 输出数据长度指针
-/// 返回: 指向输出数据的指针（字节数组，C# 需立即复制）
+/// out_data: 写入输出数据指针（字节数组，在下一次 update 前有效，C# 需立即复制）
+/// out_len: 写入输出数据长度
+/// 出错时仍会写入本帧已收到的数据；断开不算错误，用 state 与 disconnect_reason 查询
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_data、out_len 为 null 或可写
 #[no_mangle]
-pub extern "C" fn bevy_axon_ffi_update(
+pub unsafe extern "C" fn bevy_axon_ffi_update(
     ptr: *mut Game,
     dt: c_float,
+    out_data: *mut *const u8,
     out_len: *mut usize,
-) -> *const u8 {
-    if ptr.is_null() {
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_update", ptr, || {
+        if !out_data.is_null() {
+            *out_data = std::ptr::null();
+        }
         if !out_len.is_null() {
-            unsafe {
-                *out_len = 0;
+            *out_len = 0;
+        }
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_update] error: game is null".to_string(),
+            );
+        }
+
+        let game = &mut *ptr;
+        if game.dispatching {
+            return game.fail(
+                AxonFfiError::InvalidArgument,
+                "[bevy_axon_ffi_update] error: called from a callback".to_string(),
+            );
+        }
+        game.buf.clear();
+        game.segments.clear();
+        game.cursor = 0;
+
+        let Ok(duration) = Duration::try_from_secs_f32(dt) else {
+            return game.fail(
+                AxonFfiError::InvalidArgument,
+                format!("[bevy_axon_ffi_update] error: invalid dt: {}", dt),
+            );
+        };
+
+        let mut result = AxonFfiError::Ok;
+        if game.state() != AxonFfiState::Disconnected {
+            result = game.receive(duration);
+        }
+
+        // 回调运行前镜像已是本帧结束时的状态
+        game.mirror.begin_frame();
+        game.change_cursor = 0;
+        game.mirror.apply(&game.buf);
+        if game.state() == AxonFfiState::Disconnected {
+            game.mirror.clear();
+        }
+
+        if let Some(callbacks) = game.callbacks {
+            // 回调可能重入 invoke 等函数，调用期间不持有 game 的引用
+            let state = game.state();
+            let changed = state != game.last_state;
+            game.last_state = state;
+            let reason = game.disconnect_reason();
+            let buf = std::mem::take(&mut game.buf);
+            game.dispatching = true;
+
+            if changed {
+                if let Some(f) = callbacks.on_state {
+                    f(callbacks.user_data, state, reason);
+                }
+            }
+            let mut pos = 0;
+            while pos < buf.len() {
+                let Some((action, next)) = axon_parse_action(&buf, pos) else {
+                    break;
+                };
+                // 回调中可用 bevy_axon_ffi_action_channel 查询
+                let game = &mut *ptr;
+                game.channel = game.channel_at(pos);
+                callbacks.dispatch(action.act, action.id, action.t, action.v);
+                pos = next;
+            }
+
+            let game = &mut *ptr;
+            game.dispatching = false;
+            game.buf = buf;
+            if pos < game.buf.len() {
+                result = game.fail(
+                    AxonFfiError::Decode,
+                    format!(
+                        "[bevy_axon_ffi_update] malformed action at byte {} of {}",
+                        pos,
+                        game.buf.len()
+                    ),
+                );
             }
         }
-        return std::ptr::null();
-    }
 
-    let game = unsafe { &mut *ptr };
-    game.buf.clear();
+        // 回调中 invoke 的消息在本帧发出
+        let game = &mut *ptr;
+        if game.transport.is_connected() {
+            if let Err(e) = game.transport.flush() {
+                if game.state() != AxonFfiState::Disconnected {
+                    result = game.fail(
+                        AxonFfiError::Transport,
+                        format!("[bevy_axon_ffi_update] transport send_packets error: {}", e),
+                    );
+                }
+            }
+        }
+
+        game.refresh_stats();
+
+        if !out_data.is_null() {
+            *out_data = game.buf.as_ptr();
+        }
+        if !out_len.is_null() {
+            *out_len = game.buf.len();
+        }
+        result
+    })
+}
+
+/// 设置 update 中调用的回调，callbacks 为 null 时清除；设置后 update 的输出与 next_action 仍可用
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；callbacks 为 null 或指向有效的 AxonCallbacks
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_set_callbacks(
+    ptr: *mut Game,
+    callbacks: *const AxonCallbacks,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_set_callbacks", ptr, || {
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_set_callbacks] error: game is null".to_string(),
+            );
+        }
+        let game = &mut *ptr;
+        game.callbacks = if callbacks.is_null() {
+            None
+        } else {
+            Some(*callbacks)
+        };
+        AxonFfiError::Ok
+    })
+}
+
+/// 依次取出本帧 update 收到的动作，不复制数据
+/// out_payload: 写入动作数据指针，在下一次 update 前有效
+/// 返回: Ok 表示写入了一条动作，End 表示已取完，Decode 表示数据损坏（剩余动作被丢弃）
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；各输出指针为 null 或可写
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_next_action(
+    ptr: *mut Game,
+    out_act: *mut u8,
+    out_id: *mut u64,
+    out_type: *mut u32,
+    out_payload: *mut *const u8,
+    out_len: *mut usize,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_next_action", ptr, || {
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_next_action] error: game is null".to_string(),
+            );
+        }
+        let game = &mut *ptr;
+        if game.cursor >= game.buf.len() {
+            return AxonFfiError::End;
+        }
+        let Some((action, next)) = axon_parse_action(&game.buf, game.cursor) else {
+            let pos = game.cursor;
+            game.cursor = game.buf.len();
+            return game.fail(
+                AxonFfiError::Decode,
+                format!(
+                    "[bevy_axon_ffi_next_action] malformed action at byte {} of {}",
+                    pos,
+                    game.buf.len()
+                ),
+            );
+        };
+        if !out_act.is_null() {
+            *out_act = action.act;
+        }
+        if !out_id.is_null() {
+            *out_id = action.id;
+        }
+        if !out_type.is_null() {
+            *out_type = action.t;
+        }
+        if !out_payload.is_null() {
+            *out_payload = action.v.as_ptr();
+        }
+        if !out_len.is_null() {
+            *out_len = action.v.len();
+        }
+        game.channel = game.channel_at(game.cursor);
+        game.cursor = next;
+        AxonFfiError::Ok
+    })
+}
+
+/// 获取 next_action 最后取出的动作（回调中为正在分发的动作）所在的通道 id
+/// ptr 为 null 或还没有取出动作时返回可靠有序通道
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_action_channel(ptr: *const Game) -> u8 {
+    guard_game_or(
+        "bevy_axon_ffi_action_channel",
+        ptr,
+        AXON_CHANNEL_RELIABLE_ORDERED,
+        || {
+            if ptr.is_null() {
+                return AXON_CHANNEL_RELIABLE_ORDERED;
+            }
+            (*ptr).channel
+        },
+    )
+}
 
-    let duration = std::time::Duration::from_secs_f32(dt);
+/// 在可靠有序通道上调用游戏实例的方法，见 bevy_axon_ffi_invoke_on
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；raw 为 null 或指向至少 raw_len 字节
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_invoke(
+    ptr: *mut Game,
+    raw: *const c_uchar,
+    raw_len: usize,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_invoke", ptr, || {
+        send_on(
+            "bevy_axon_ffi_invoke",
+            ptr,
+            AXON_CHANNEL_RELIABLE_ORDERED,
+            raw,
+            raw_len,
+        )
+    })
+}
 
-    game.client.update(duration);
+/// 在指定通道上调用游戏实例的方法，高频输入可走不可靠通道
+/// channel: 已配置的通道 id，默认通道见 AXON_CHANNEL_*
+/// raw: 输入数据指针
+/// raw_len: 输入数据长度
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；raw 为 null 或指向至少 raw_len 字节
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_invoke_on(
+    ptr: *mut Game,
+    channel: u8,
+    raw: *const c_uchar,
+    raw_len: usize,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_invoke_on", ptr, || {
+        send_on("bevy_axon_ffi_invoke_on", ptr, channel, raw, raw_len)
+    })
+}
+
+unsafe fn send_on(
+    name: &str,
+    ptr: *mut Game,
+    channel: u8,
+    raw: *const c_uchar,
+    raw_len: usize,
+) -> AxonFfiError {
+    if ptr.is_null() {
+        return fail(
+            AxonFfiError::NullPointer,
+            format!("[{}] error: game is null", name),
+        );
+    }
+    let game = &mut *ptr;
+    if !game.channels.contains(&channel) {
+        return game.fail(
+            AxonFfiError::InvalidArgument,
+            format!("[{}] error: channel {} is not configured", name, channel),
+        );
+    }
+    if raw_len == 0 {
+        return AxonFfiError::Ok;
+    }
+    if raw.is_null() {
+        return game.fail(
+            AxonFfiError::NullPointer,
+            format!("[{}] error: raw is null", name),
+        );
+    }
+    if game.state() != AxonFfiState::Connected {
+        return game.fail(
+            AxonFfiError::NotConnected,
+            format!("[{}] error: not connected", name),
+        );
+    }
 
-    if let Err(e) = game.transport.update(duration, &mut game.client) {
-        let msg = format!("[bevy_axon_ffi_update] transport update error: {:?}", e);
-        println!("{}", msg);
-        set_error(&msg);
+    let data = std::slice::from_raw_parts(raw, raw_len);
+    game.transport.send(channel, data.to_vec());
+    if let Some(stats) = game.channel_stats(channel) {
+        stats.messages_sent += 1;
     }
+    AxonFfiError::Ok
+}
 
-    if game.client.is_connected() {
-        while let Some(message) = game.client.receive_message(DefaultChannel::ReliableOrdered) {
-            game.buf.extend_from_slice(&message);
+/// 获取网络统计
+/// out_stats: 写入统计，未连接时 rtt 等为 renet 的初始值
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_stats 为 null 或可写
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_stats(
+    ptr: *const Game,
+    out_stats: *mut AxonNetStats,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_stats", ptr, || {
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_stats] error: game is null".to_string(),
+            );
         }
+        if out_stats.is_null() {
+            return (*ptr).fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_stats] error: out_stats is null".to_string(),
+            );
+        }
+        *out_stats = (*ptr).stats;
+        AxonFfiError::Ok
+    })
+}
+
+/// JSON 输入的问题为 InvalidArgument，sbin 数据损坏为 Decode
+fn json_error(e: AxonJsonError) -> (AxonFfiError, String) {
+    let code = match e {
+        AxonJsonError::Json(_) | AxonJsonError::InvalidValue(_) => AxonFfiError::InvalidArgument,
+        _ => AxonFfiError::Decode,
+    };
+    (code, e.to_string())
+}
 
-        if let Err(e) = game.transport.send_packets(&mut game.client) {
-            let msg = format!(
-                "[bevy_axon_ffi_update] transport send_packets error: {:?}",
-                e
+/// 把 JSON 文本编码为 sbin，规则见 json 模块：数字默认为 i64/f64，其他类型用 {"$u32": 1} 等包装
+/// out_data: 写入 sbin 数据指针，在当前线程下一次转换前有效，调用者不应释放
+/// 返回: JSON 无法解析或包装不合法时为 InvalidArgument，消息见 bevy_axon_ffi_errmsg(NULL)
+///
+/// # Safety
+/// json 为 null 或以 null 结尾的字符串；out_data、out_len 为 null 或可写
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_json_to_sbin(
+    json: *const c_char,
+    out_data: *mut *const u8,
+    out_len: *mut usize,
+) -> AxonFfiError {
+    guard(
+        "bevy_axon_ffi_json_to_sbin",
+        std::ptr::null(),
+        AxonFfiError::Panic,
+        || {
+            if out_data.is_null() || out_len.is_null() {
+                return fail(
+                    AxonFfiError::NullPointer,
+                    "[bevy_axon_ffi_json_to_sbin] error: out_data or out_len is null".to_string(),
+                );
+            }
+            *out_data = std::ptr::null();
+            *out_len = 0;
+            match read_str(json, "json").and_then(|v| axon_json_str_to_sbin(v).map_err(json_error))
+            {
+                Ok(data) => CONVERTED.with(|v| {
+                    let mut v = v.borrow_mut();
+                    *v = data;
+                    *out_data = v.as_ptr();
+                    *out_len = v.len();
+                    AxonFfiError::Ok
+                }),
+                Err((code, msg)) => {
+                    fail(code, format!("[bevy_axon_ffi_json_to_sbin] error: {}", msg))
+                }
+            }
+        },
+    )
+}
+
+/// 把一个完整的 sbin 值（如动作的 payload）转换为 JSON 文本
+/// typed: 非 0 时数字、bytes 与非字符串键的映射输出为 {"$u32": 1} 等包装，可无损转回 sbin
+/// out_json: 写入以 null 结尾的 JSON 文本，在当前线程下一次转换前有效，调用者不应释放
+/// 返回: 数据损坏时为 Decode
+///
+/// # Safety
+/// data 为 null 或指向至少 len 字节；out_json 为 null 或可写
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_sbin_to_json(
+    data: *const u8,
+    len: usize,
+    typed: c_int,
+    out_json: *mut *const c_char,
+) -> AxonFfiError {
+    guard(
+        "bevy_axon_ffi_sbin_to_json",
+        std::ptr::null(),
+        AxonFfiError::Panic,
+        || {
+            if out_json.is_null() || (data.is_null() && len > 0) {
+                return fail(
+                    AxonFfiError::NullPointer,
+                    "[bevy_axon_ffi_sbin_to_json] error: data or out_json is null".to_string(),
+                );
+            }
+            *out_json = std::ptr::null();
+            let data = if len == 0 {
+                &[]
+            } else {
+                std::slice::from_raw_parts(data, len)
+            };
+            match axon_sbin_to_json(data, typed != 0) {
+                Ok(value) => CONVERTED.with(|v| {
+                    let mut v = v.borrow_mut();
+                    *v = value.to_string().into_bytes();
+                    v.push(0);
+                    *out_json = v.as_ptr() as *const c_char;
+                    AxonFfiError::Ok
+                }),
+                Err(e) => {
+                    let (code, msg) = json_error(e);
+                    fail(code, format!("[bevy_axon_ffi_sbin_to_json] error: {}", msg))
+                }
+            }
+        },
+    )
+}
+
+/// 把 JSON 编码为 sbin 后作为事件发给服务器（可靠有序通道）
+/// id: 目标对象的网络 id，0 表示无目标
+/// event_type: 事件类型 id
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；json 为 null 或以 null 结尾的字符串
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_invoke_json(
+    ptr: *mut Game,
+    id: u64,
+    event_type: u32,
+    json: *const c_char,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_invoke_json", ptr, || {
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_invoke_json] error: game is null".to_string(),
             );
-            println!("{}", msg);
-            set_error(&msg);
         }
-    }
+        let game = &mut *ptr;
+        let v = match read_str(json, "json")
+            .and_then(|v| axon_json_str_to_sbin(v).map_err(json_error))
+        {
+            Ok(v) => v,
+            Err((code, msg)) => {
+                return game.fail(code, format!("[bevy_axon_ffi_invoke_json] error: {}", msg));
+            }
+        };
+        let mut raw = Vec::new();
+        axon_write_action(&mut raw, ACTION_TYPE_INVOKE, id, event_type, &v);
+        send_on(
+            "bevy_axon_ffi_invoke_json",
+            ptr,
+            AXON_CHANNEL_RELIABLE_ORDERED,
+            raw.as_ptr(),
+            raw.len(),
+        )
+    })
+}
 
-    if !out_len.is_null() {
-        unsafe {
-            *out_len = game.buf.len();
+/// 列出世界镜像中的对象 id（升序）
+/// out_ids: 最多写入 capacity 个 id，为 null 时只返回数量
+/// 返回: 对象总数，大于 capacity 时需用更大的缓冲重试
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_ids 为 null 或可写 capacity 个 u64
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_objects(
+    ptr: *const Game,
+    out_ids: *mut u64,
+    capacity: usize,
+) -> usize {
+    guard_game_or("bevy_axon_ffi_objects", ptr, 0, || {
+        if ptr.is_null() {
+            return 0;
+        }
+        let mirror = &(*ptr).mirror;
+        if !out_ids.is_null() {
+            for (i, id) in mirror.ids().take(capacity).enumerate() {
+                *out_ids.add(i) = id;
+            }
+        }
+        mirror.len()
+    })
+}
+
+/// 获取对象的类型
+/// 返回: 对象不存在时为 NotFound（不记录错误消息）
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_type 为 null 或可写
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_object_type(
+    ptr: *const Game,
+    id: u64,
+    out_type: *mut u32,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_object_type", ptr, || {
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_object_type] error: game is null".to_string(),
+            );
+        }
+        let Some(object) = (*ptr).mirror.object(id) else {
+            return AxonFfiError::NotFound;
+        };
+        if !out_type.is_null() {
+            *out_type = object.t;
         }
+        AxonFfiError::Ok
+    })
+}
+
+unsafe fn write_payload(v: &[u8], out_payload: *mut *const u8, out_len: *mut usize) {
+    if !out_payload.is_null() {
+        *out_payload = v.as_ptr();
     }
+    if !out_len.is_null() {
+        *out_len = v.len();
+    }
+}
 
-    game.buf.as_ptr()
+/// 获取对象某个变体最新的数据
+/// out_payload: 写入数据指针，在下一次 update 前有效
+/// 返回: 对象不存在或尚未收到该变体时为 NotFound（不记录错误消息）
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_payload、out_len 为 null 或可写
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_variant(
+    ptr: *const Game,
+    id: u64,
+    variant_type: u32,
+    out_payload: *mut *const u8,
+    out_len: *mut usize,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_variant", ptr, || {
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_variant] error: game is null".to_string(),
+            );
+        }
+        let Some(v) = (*ptr)
+            .mirror
+            .object(id)
+            .and_then(|v| v.variants.get(&variant_type))
+        else {
+            return AxonFfiError::NotFound;
+        };
+        write_payload(v, out_payload, out_len);
+        AxonFfiError::Ok
+    })
 }
 
-/// 调用游戏实例的方法
-/// raw: 输入数据指针
-/// raw_len: 输入数据长度
+/// 获取资源最新的数据
+/// out_payload: 写入数据指针，在下一次 update 前有效
+/// 返回: 尚未收到该资源时为 NotFound（不记录错误消息）
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_payload、out_len 为 null 或可写
 #[no_mangle]
-pub extern "C" fn bevy_axon_ffi_invoke(ptr: *mut Game, raw: *const c_uchar, raw_len: usize) {
-    if ptr.is_null() || raw.is_null() || raw_len <= 0 {
-        return;
+pub unsafe extern "C" fn bevy_axon_ffi_resource(
+    ptr: *const Game,
+    resource_type: u32,
+    out_payload: *mut *const u8,
+    out_len: *mut usize,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_resource", ptr, || {
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_resource] error: game is null".to_string(),
+            );
+        }
+        let Some(v) = (*ptr).mirror.resource(resource_type) else {
+            return AxonFfiError::NotFound;
+        };
+        write_payload(v, out_payload, out_len);
+        AxonFfiError::Ok
+    })
+}
+
+/// 依次取出最近一次 update 中世界镜像的变化，按动作顺序，断开时每个对象记为 Despawn
+/// 返回: Ok 表示写入了一条变化，End 表示已取完
+///
+/// # Safety
+/// ptr 为 null 或由 bevy_axon_ffi_create 返回且尚未释放；out_change 为 null 或可写
+#[no_mangle]
+pub unsafe extern "C" fn bevy_axon_ffi_next_change(
+    ptr: *mut Game,
+    out_change: *mut AxonChange,
+) -> AxonFfiError {
+    guard_game("bevy_axon_ffi_next_change", ptr, || {
+        if ptr.is_null() {
+            return fail(
+                AxonFfiError::NullPointer,
+                "[bevy_axon_ffi_next_change] error: game is null".to_string(),
+            );
+        }
+        let game = &mut *ptr;
+        let Some(change) = game.mirror.changes().get(game.change_cursor) else {
+            return AxonFfiError::End;
+        };
+        if !out_change.is_null() {
+            *out_change = *change;
+        }
+        game.change_cursor += 1;
+        AxonFfiError::Ok
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::AXON_CHANNEL_UNRELIABLE;
+    use std::net::UdpSocket;
+
+    fn errmsg(game: *const Game) -> String {
+        let p = unsafe { bevy_axon_ffi_errmsg(game) };
+        assert!(!p.is_null());
+        unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned()
     }
 
-    let game = unsafe { &mut *ptr };
-    let data = unsafe { std::slice::from_raw_parts(raw, raw_len) };
+    fn create() -> *mut Game {
+        let port = UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let addr = CString::new(format!("127.0.0.1:{}", port)).unwrap();
+        let mut game = std::ptr::null_mut();
+        let err = unsafe { bevy_axon_ffi_create(addr.as_ptr(), 1, &mut game) };
+        assert_eq!(err, AxonFfiError::Ok);
+        game
+    }
+
+    #[test]
+    fn test_guard_poisons_game() {
+        let game = create();
+        let err = unsafe { guard_game("test", game, || panic!("boom")) };
+        assert_eq!(err, AxonFfiError::Panic);
+        assert_eq!(errmsg(game), "[test] panic: boom");
+        assert_eq!(errmsg(std::ptr::null()), "[test] panic: boom");
+
+        // 中毒后不再进入函数体，错误消息保留为最初的 panic
+        let (mut data, mut len) = (std::ptr::null(), 0);
+        let err = unsafe { bevy_axon_ffi_update(game, 0.016, &mut data, &mut len) };
+        assert_eq!(err, AxonFfiError::Panic);
+        let err =
+            unsafe { bevy_axon_ffi_invoke_on(game, AXON_CHANNEL_UNRELIABLE, [0].as_ptr(), 1) };
+        assert_eq!(err, AxonFfiError::Panic);
+        let err = unsafe { guard_game("again", game, || AxonFfiError::Ok) };
+        assert_eq!(err, AxonFfiError::Panic);
+        assert_eq!(errmsg(game), "[test] panic: boom");
+        assert_eq!(
+            unsafe { bevy_axon_ffi_state(game) },
+            AxonFfiState::Disconnected
+        );
+        assert_eq!(unsafe { bevy_axon_ffi_is_connected(game) }, 0);
+        unsafe { bevy_axon_ffi_exit(game) };
+    }
+
+    #[test]
+    fn test_guard_without_game() {
+        let v = unsafe {
+            guard("test", std::ptr::null(), 7, || -> i32 {
+                panic!("{} failed", "format")
+            })
+        };
+        assert_eq!(v, 7);
+        assert_eq!(errmsg(std::ptr::null()), "[test] panic: format failed");
+        assert_eq!(unsafe { guard("test", std::ptr::null(), 7, || 1) }, 1);
 
-    game.client
-        .send_message(DefaultChannel::ReliableOrdered, data);
+        // 未中毒的 game 正常执行
+        let game = create();
+        let err = unsafe { guard_game("test", game, || AxonFfiError::Ok) };
+        assert_eq!(err, AxonFfiError::Ok);
+        assert_ne!(
+            unsafe { bevy_axon_ffi_state(game) },
+            AxonFfiState::Disconnected
+        );
+        unsafe { bevy_axon_ffi_exit(game) };
+    }
 }
@@ -0,0 +1,390 @@
+//! sbin 与 JSON 的互相转换，不需要 Rust 类型定义，供脚本客户端经 FFI 收发事件。
+//!
+//! 普通 JSON 的映射：null ↔ Nil，bool ↔ Bool，字符串 ↔ Str，数组 ↔ Array，对象 ↔ 以 Str 为键的 Object；
+//! 整数写为 I64（超出 i64 的正数为 U64），小数写为 F64。
+//! 需要指定 sbin 类型时使用只有一个键的包装对象：`{"$u8": 1}` 至 `{"$i64": -1}`、`{"$f32": 1.5}`、
+//! `{"$f64": "NaN"}`（另有 `"inf"`、`"-inf"`）、`{"$bytes": [1, 2]}`，以及键不是字符串的映射
+//! `{"$map": [[1, "a"]]}`。serde_sbin 按字段类型严格匹配，发往 Rust 的非 i64/f64 数字需要包装。
+//! sbin 转 JSON 时 typed 为 true 会输出这些包装，再转回 sbin 后字节不变。
+
+use crate::protocol::sbin::*;
+use serde_json::{Map, Number, Value};
+use std::fmt;
+
+/// 包装对象的键与对应的 sbin 类型
+const WRAPPERS: [(&str, u8); 11] = [
+    ("$u8", SBIN_U8),
+    ("$u16", SBIN_U16),
+    ("$u32", SBIN_U32),
+    ("$u64", SBIN_U64),
+    ("$i8", SBIN_I8),
+    ("$i16", SBIN_I16),
+    ("$i32", SBIN_I32),
+    ("$i64", SBIN_I64),
+    ("$f32", SBIN_F32),
+    ("$f64", SBIN_F64),
+    ("$bytes", SBIN_BYTES),
+];
+
+const WRAPPER_MAP: &str = "$map";
+
+/// 数组与对象的最大嵌套层数
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug)]
+pub enum AxonJsonError {
+    /// JSON 文本无法解析
+    Json(serde_json::Error),
+    UnexpectedEof,
+    InvalidType(u8),
+    InvalidUtf8,
+    /// 一个完整的值之后还有多余的字节
+    TrailingBytes(usize),
+    /// 值不合法，如包装对象中超出范围的整数
+    InvalidValue(String),
+}
+
+impl fmt::Display for AxonJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AxonJsonError::Json(e) => write!(f, "json error: {}", e),
+            AxonJsonError::UnexpectedEof => write!(f, "unexpected end of input"),
+            AxonJsonError::InvalidType(ty) => write!(f, "invalid sbin type: {}", ty),
+            AxonJsonError::InvalidUtf8 => write!(f, "invalid utf8"),
+            AxonJsonError::TrailingBytes(pos) => write!(f, "trailing bytes at {}", pos),
+            AxonJsonError::InvalidValue(msg) => write!(f, "invalid value: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AxonJsonError {}
+
+impl From<serde_json::Error> for AxonJsonError {
+    fn from(e: serde_json::Error) -> Self {
+        AxonJsonError::Json(e)
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> AxonJsonError {
+    AxonJsonError::InvalidValue(msg.into())
+}
+
+// ==================== JSON -> sbin ====================
+
+/// 把 JSON 值编码为 sbin
+pub fn axon_json_to_sbin(value: &Value) -> Result<Vec<u8>, AxonJsonError> {
+    let mut out = Vec::new();
+    write_value(value, &mut out, 0)?;
+    Ok(out)
+}
+
+/// 解析 JSON 文本并编码为 sbin
+pub fn axon_json_str_to_sbin(json: &str) -> Result<Vec<u8>, AxonJsonError> {
+    axon_json_to_sbin(&serde_json::from_str(json)?)
+}
+
+fn write_str(s: &str, out: &mut Vec<u8>) {
+    out.push(SBIN_STR);
+    out.extend((s.len() as u32).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+}
+
+fn write_value(value: &Value, out: &mut Vec<u8>, depth: usize) -> Result<(), AxonJsonError> {
+    if depth > MAX_DEPTH {
+        return Err(invalid("nesting too deep"));
+    }
+    match value {
+        Value::Null => out.push(SBIN_NIL),
+        Value::Bool(v) => out.extend([SBIN_BOOL, *v as u8]),
+        Value::Number(n) => {
+            if let Some(v) = n.as_i64() {
+                out.push(SBIN_I64);
+                out.extend(v.to_le_bytes());
+            } else if let Some(v) = n.as_u64() {
+                out.push(SBIN_U64);
+                out.extend(v.to_le_bytes());
+            } else {
+                out.push(SBIN_F64);
+                out.extend(n.as_f64().unwrap_or_default().to_le_bytes());
+            }
+        }
+        Value::String(s) => write_str(s, out),
+        Value::Array(items) => {
+            out.push(SBIN_ARRAY);
+            for item in items {
+                write_value(item, out, depth + 1)?;
+            }
+            out.push(SBIN_END);
+        }
+        Value::Object(map) => {
+            if map.len() == 1 {
+                let (key, v) = map.iter().next().unwrap();
+                if key == WRAPPER_MAP {
+                    return write_map(v, out, depth);
+                }
+                if let Some((_, ty)) = WRAPPERS.iter().find(|(name, _)| name == key) {
+                    return write_typed(*ty, key, v, out);
+                }
+            }
+            out.push(SBIN_OBJECT);
+            for (key, v) in map {
+                write_str(key, out);
+                write_value(v, out, depth + 1)?;
+            }
+            out.push(SBIN_END);
+        }
+    }
+    Ok(())
+}
+
+/// `{"$map": [[k, v], ...]}`
+fn write_map(value: &Value, out: &mut Vec<u8>, depth: usize) -> Result<(), AxonJsonError> {
+    let Value::Array(pairs) = value else {
+        return Err(invalid("$map must be an array of [key, value] pairs"));
+    };
+    out.push(SBIN_OBJECT);
+    for pair in pairs {
+        match pair.as_array().map(Vec::as_slice) {
+            Some([k, v]) => {
+                write_value(k, out, depth + 1)?;
+                write_value(v, out, depth + 1)?;
+            }
+            _ => return Err(invalid("$map must be an array of [key, value] pairs")),
+        }
+    }
+    out.push(SBIN_END);
+    Ok(())
+}
+
+fn write_int<T>(
+    key: &str,
+    value: &Value,
+    out: &mut Vec<u8>,
+    to_le: fn(T) -> Vec<u8>,
+) -> Result<(), AxonJsonError>
+where
+    T: TryFrom<i64> + TryFrom<u64>,
+{
+    let v = match value {
+        Value::Number(n) if n.is_i64() => T::try_from(n.as_i64().unwrap()).ok(),
+        Value::Number(n) if n.is_u64() => T::try_from(n.as_u64().unwrap()).ok(),
+        _ => None,
+    };
+    match v {
+        Some(v) => {
+            out.extend(to_le(v));
+            Ok(())
+        }
+        None => Err(invalid(format!("{} out of range: {}", key, value))),
+    }
+}
+
+fn float_of(key: &str, value: &Value) -> Result<f64, AxonJsonError> {
+    match value {
+        Value::Number(n) => Ok(n.as_f64().unwrap_or_default()),
+        Value::String(s) if s == "NaN" => Ok(f64::NAN),
+        Value::String(s) if s == "inf" => Ok(f64::INFINITY),
+        Value::String(s) if s == "-inf" => Ok(f64::NEG_INFINITY),
+        _ => Err(invalid(format!("{} is not a number: {}", key, value))),
+    }
+}
+
+fn write_typed(ty: u8, key: &str, value: &Value, out: &mut Vec<u8>) -> Result<(), AxonJsonError> {
+    out.push(ty);
+    match ty {
+        SBIN_U8 => write_int::<u8>(key, value, out, |v| v.to_le_bytes().to_vec()),
+        SBIN_U16 => write_int::<u16>(key, value, out, |v| v.to_le_bytes().to_vec()),
+        SBIN_U32 => write_int::<u32>(key, value, out, |v| v.to_le_bytes().to_vec()),
+        SBIN_U64 => write_int::<u64>(key, value, out, |v| v.to_le_bytes().to_vec()),
+        SBIN_I8 => write_int::<i8>(key, value, out, |v| v.to_le_bytes().to_vec()),
+        SBIN_I16 => write_int::<i16>(key, value, out, |v| v.to_le_bytes().to_vec()),
+        SBIN_I32 => write_int::<i32>(key, value, out, |v| v.to_le_bytes().to_vec()),
+        SBIN_I64 => write_int::<i64>(key, value, out, |v| v.to_le_bytes().to_vec()),
+        SBIN_F32 => {
+            out.extend((float_of(key, value)? as f32).to_le_bytes());
+            Ok(())
+        }
+        SBIN_F64 => {
+            out.extend(float_of(key, value)?.to_le_bytes());
+            Ok(())
+        }
+        _ => {
+            let bytes = value
+                .as_array()
+                .and_then(|items| {
+                    items
+                        .iter()
+                        .map(|v| v.as_u64().and_then(|v| u8::try_from(v).ok()))
+                        .collect::<Option<Vec<u8>>>()
+                })
+                .ok_or_else(|| invalid("$bytes must be an array of 0-255 integers"))?;
+            out.extend((bytes.len() as u32).to_le_bytes());
+            out.extend(bytes);
+            Ok(())
+        }
+    }
+}
+
+// ==================== sbin -> JSON ====================
+
+/// 把一个完整的 sbin 值转换为 JSON，typed 为 true 时输出类型包装
+pub fn axon_sbin_to_json(data: &[u8], typed: bool) -> Result<Value, AxonJsonError> {
+    let mut reader = Reader {
+        data,
+        pos: 0,
+        typed,
+    };
+    let value = reader.read_value(0)?;
+    if reader.pos != data.len() {
+        return Err(AxonJsonError::TrailingBytes(reader.pos));
+    }
+    Ok(value)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    typed: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], AxonJsonError> {
+        let v = self
+            .data
+            .get(self.pos..self.pos + N)
+            .ok_or(AxonJsonError::UnexpectedEof)?;
+        self.pos += N;
+        Ok(v.try_into().unwrap())
+    }
+
+    fn take_slice(&mut self, len: usize) -> Result<&'a [u8], AxonJsonError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .ok_or(AxonJsonError::UnexpectedEof)?;
+        let v = self
+            .data
+            .get(self.pos..end)
+            .ok_or(AxonJsonError::UnexpectedEof)?;
+        self.pos = end;
+        Ok(v)
+    }
+
+    fn wrap(typed: bool, ty: u8, value: Value) -> Value {
+        if !typed {
+            return value;
+        }
+        let (name, _) = WRAPPERS.iter().find(|(_, t)| *t == ty).unwrap();
+        let mut map = Map::new();
+        map.insert(name.to_string(), value);
+        Value::Object(map)
+    }
+
+    fn float(typed: bool, ty: u8, v: f64) -> Value {
+        let value = match Number::from_f64(v) {
+            Some(n) => Value::Number(n),
+            None if !typed => Value::Null,
+            None if v.is_nan() => Value::from("NaN"),
+            None if v > 0.0 => Value::from("inf"),
+            None => Value::from("-inf"),
+        };
+        Self::wrap(typed, ty, value)
+    }
+
+    fn read_value(&mut self, depth: usize) -> Result<Value, AxonJsonError> {
+        if depth > MAX_DEPTH {
+            return Err(invalid("nesting too deep"));
+        }
+        let [ty] = self.take::<1>()?;
+        let typed = self.typed;
+        let value = match ty {
+            SBIN_NIL => Value::Null,
+            SBIN_U8 => Self::wrap(typed, ty, self.take::<1>()?[0].into()),
+            SBIN_U16 => Self::wrap(typed, ty, u16::from_le_bytes(self.take()?).into()),
+            SBIN_U32 => Self::wrap(typed, ty, u32::from_le_bytes(self.take()?).into()),
+            SBIN_U64 => Self::wrap(typed, ty, u64::from_le_bytes(self.take()?).into()),
+            SBIN_I8 => Self::wrap(typed, ty, i8::from_le_bytes(self.take()?).into()),
+            SBIN_I16 => Self::wrap(typed, ty, i16::from_le_bytes(self.take()?).into()),
+            SBIN_I32 => Self::wrap(typed, ty, i32::from_le_bytes(self.take()?).into()),
+            SBIN_I64 => Self::wrap(typed, ty, i64::from_le_bytes(self.take()?).into()),
+            // 经字符串转换，1.1f32 输出为 1.1 而不是 1.100000023841858
+            SBIN_F32 => {
+                let v = f32::from_le_bytes(self.take()?);
+                let v = v.to_string().parse::<f64>().unwrap_or(v as f64);
+                Self::float(typed, ty, v)
+            }
+            SBIN_F64 => Self::float(typed, ty, f64::from_le_bytes(self.take()?)),
+            SBIN_BOOL => Value::Bool(self.take::<1>()?[0] != 0),
+            SBIN_STR => {
+                let len = u32::from_le_bytes(self.take()?) as usize;
+                let bytes = self.take_slice(len)?;
+                let s = std::str::from_utf8(bytes).map_err(|_| AxonJsonError::InvalidUtf8)?;
+                // 字符串后的 0 结尾
+                self.take::<1>()?;
+                Value::String(s.to_string())
+            }
+            SBIN_BYTES => {
+                let len = u32::from_le_bytes(self.take()?) as usize;
+                let bytes = self.take_slice(len)?;
+                Self::wrap(typed, ty, bytes.iter().map(|v| Value::from(*v)).collect())
+            }
+            SBIN_ARRAY => {
+                let mut items = Vec::new();
+                while !self.at_end()? {
+                    items.push(self.read_value(depth + 1)?);
+                }
+                Value::Array(items)
+            }
+            SBIN_OBJECT => self.read_object(depth)?,
+            _ => return Err(AxonJsonError::InvalidType(ty)),
+        };
+        Ok(value)
+    }
+
+    /// 下一个字节为 End 时跳过它并返回 true
+    fn at_end(&mut self) -> Result<bool, AxonJsonError> {
+        match self.data.get(self.pos) {
+            Some(&SBIN_END) => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(AxonJsonError::UnexpectedEof),
+        }
+    }
+
+    fn read_object(&mut self, depth: usize) -> Result<Value, AxonJsonError> {
+        let mut pairs = Vec::new();
+        while !self.at_end()? {
+            let k = self.read_value(depth + 1)?;
+            let v = self.read_value(depth + 1)?;
+            pairs.push((k, v));
+        }
+
+        let mut map = Map::new();
+        let plain = pairs.iter().all(|(k, _)| k.is_string());
+        if plain || !self.typed {
+            for (k, v) in pairs.iter().cloned() {
+                let key = match k {
+                    Value::String(s) => s,
+                    k => k.to_string(),
+                };
+                map.insert(key, v);
+            }
+        }
+        // 重复的键或会被当作包装的单键对象只能用 $map 表示
+        let exact = map.len() == pairs.len()
+            && !(map.len() == 1 && map.keys().next().unwrap().starts_with('$'));
+        if !self.typed || (plain && exact) {
+            return Ok(Value::Object(map));
+        }
+        let pairs = pairs
+            .into_iter()
+            .map(|(k, v)| Value::Array(vec![k, v]))
+            .collect();
+        let mut map = Map::new();
+        map.insert(WRAPPER_MAP.to_string(), Value::Array(pairs));
+        Ok(Value::Object(map))
+    }
+}
@@ -1,6 +1,30 @@
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(any(feature = "server", feature = "client"))]
+pub mod core;
 #[cfg(feature = "ffi")]
 pub mod ffi;
+#[cfg(feature = "ffi")]
+pub mod json;
 #[cfg(feature = "server")]
-pub mod core;
+pub mod loopback;
+#[cfg(feature = "ffi")]
+pub mod mirror;
+#[cfg(feature = "netcode")]
+pub mod netcode;
+#[cfg(feature = "server")]
+pub mod persist;
+pub mod protocol;
+#[cfg(feature = "server")]
+pub mod record;
 #[cfg(feature = "server")]
 pub mod server;
+pub mod transport;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+/// 单元测试用的临时文件路径，文件名带进程 id，同时运行的测试进程不会互相覆盖
+#[cfg(all(test, feature = "server"))]
+pub(crate) fn test_temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("bevy_axon_{}_{}", std::process::id(), name))
+}
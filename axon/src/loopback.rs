@@ -0,0 +1,308 @@
+use crate::core::*;
+use crate::protocol::*;
+use crate::server::AxonServer;
+use crate::transport::*;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use serde::Serialize;
+use serde_sbin::SbinError;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Default)]
+struct LoopbackPeer {
+    /// 客户端发往服务器的消息
+    inbound: VecDeque<(u8, Vec<u8>)>,
+    /// 服务器发往客户端的消息
+    outbound: VecDeque<(u8, Vec<u8>)>,
+}
+
+#[derive(Default)]
+struct LoopbackState {
+    peers: HashMap<u64, LoopbackPeer>,
+    events: VecDeque<AxonServerEvent>,
+}
+
+/// 进程内服务器传输层，消息在内存队列中直接交付
+#[derive(Clone, Default)]
+pub struct AxonLoopbackServerTransport {
+    state: Arc<Mutex<LoopbackState>>,
+}
+
+impl AxonLoopbackServerTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 建立一条回环连接，user_data 与 netcode 连接时携带的数据含义相同
+    pub fn connect(
+        &self,
+        client_id: u64,
+        user_data: Option<Vec<u8>>,
+    ) -> AxonLoopbackClientTransport {
+        let mut state = self.state.lock().unwrap();
+        state.peers.insert(client_id, LoopbackPeer::default());
+        state.events.push_back(AxonServerEvent::Connected {
+            client_id,
+            user_data,
+        });
+        AxonLoopbackClientTransport {
+            id: client_id,
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl AxonServerTransport for AxonLoopbackServerTransport {
+    fn update(&mut self, _dt: Duration) -> Result<(), AxonTransportError> {
+        Ok(())
+    }
+
+    fn next_event(&mut self) -> Option<AxonServerEvent> {
+        self.state.lock().unwrap().events.pop_front()
+    }
+
+    fn clients(&self) -> Vec<u64> {
+        self.state.lock().unwrap().peers.keys().copied().collect()
+    }
+
+    fn receive(&mut self, client_id: u64, channel: u8) -> Option<Vec<u8>> {
+        let mut state = self.state.lock().unwrap();
+        let peer = state.peers.get_mut(&client_id)?;
+        let i = peer.inbound.iter().position(|v| v.0 == channel)?;
+        peer.inbound.remove(i).map(|v| v.1)
+    }
+
+    fn send(&mut self, client_id: u64, channel: u8, data: Vec<u8>) {
+        if let Some(peer) = self.state.lock().unwrap().peers.get_mut(&client_id) {
+            peer.outbound.push_back((channel, data));
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), AxonTransportError> {
+        Ok(())
+    }
+
+    fn disconnect(&mut self, client_id: u64) {
+        let mut state = self.state.lock().unwrap();
+        if state.peers.remove(&client_id).is_some() {
+            state.events.push_back(AxonServerEvent::Disconnected {
+                client_id,
+                reason: "disconnected by server".to_string(),
+            });
+        }
+    }
+}
+
+/// 回环连接的客户端一侧
+pub struct AxonLoopbackClientTransport {
+    id: u64,
+    state: Arc<Mutex<LoopbackState>>,
+}
+
+impl AxonLoopbackClientTransport {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl AxonClientTransport for AxonLoopbackClientTransport {
+    fn update(&mut self, _dt: Duration) -> Result<(), AxonTransportError> {
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.state.lock().unwrap().peers.contains_key(&self.id)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        !self.is_connected()
+    }
+
+    fn receive(&mut self, channel: u8) -> Option<Vec<u8>> {
+        let mut state = self.state.lock().unwrap();
+        let peer = state.peers.get_mut(&self.id)?;
+        let i = peer.outbound.iter().position(|v| v.0 == channel)?;
+        peer.outbound.remove(i).map(|v| v.1)
+    }
+
+    fn send(&mut self, channel: u8, data: Vec<u8>) {
+        if let Some(peer) = self.state.lock().unwrap().peers.get_mut(&self.id) {
+            peer.inbound.push_back((channel, data));
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), AxonTransportError> {
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if state.peers.remove(&self.id).is_some() {
+            state.events.push_back(AxonServerEvent::Disconnected {
+                client_id: self.id,
+                reason: "disconnected by client".to_string(),
+            });
+        }
+    }
+}
+
+/// 进程内客户端，直接解析收到的动作
+pub struct AxonLoopbackClient {
+    transport: AxonLoopbackClientTransport,
+    compressed: bool,
+}
+
+impl AxonLoopbackClient {
+    pub fn id(&self) -> u64 {
+        self.transport.id
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.transport.is_connected()
+    }
+
+    /// 发送原始动作流
+    pub fn send(&mut self, data: Vec<u8>) {
+        self.transport.send(AXON_CHANNEL_RELIABLE_ORDERED, data);
+    }
+
+    /// 向服务器发送事件，id 为目标对象（0 表示无目标）
+    pub fn invoke<T: AxonEvent + Serialize>(
+        &mut self,
+        id: u64,
+        event: &T,
+    ) -> Result<(), SbinError> {
+        let v = serde_sbin::to_bytes(event)?;
+        self.send(encode_axon_action(
+            ACTION_TYPE_INVOKE,
+            id,
+            T::axon_event_type(),
+            &v,
+        ));
+        Ok(())
+    }
+
+    /// 取出所有已收到的动作，无法解码的消息被跳过
+    pub fn receive(&mut self) -> Vec<AxonAction> {
+        let mut actions = Vec::new();
+        for channel in AXON_CHANNELS {
+            while let Some(message) = self.transport.receive(channel) {
+                let message = if self.compressed {
+                    match axon_decode_message(&message) {
+                        Some(v) => v,
+                        None => {
+                            warn!(
+                                "axon loopback client {} message decode error on channel {}",
+                                self.id(),
+                                channel
+                            );
+                            continue;
+                        }
+                    }
+                } else {
+                    message
+                };
+                actions.extend(read_axon_actions(&message));
+            }
+        }
+        actions
+    }
+}
+
+/// 回环服务器与其上的进程内客户端
+#[derive(Resource, Default)]
+pub struct AxonLoopback {
+    server: AxonLoopbackServerTransport,
+    clients: Vec<AxonLoopbackClient>,
+}
+
+impl AxonLoopback {
+    pub fn server(&self) -> &AxonLoopbackServerTransport {
+        &self.server
+    }
+
+    pub fn client(&self, id: u64) -> Option<&AxonLoopbackClient> {
+        self.clients.iter().find(|v| v.id() == id)
+    }
+
+    pub fn client_mut(&mut self, id: u64) -> Option<&mut AxonLoopbackClient> {
+        self.clients.iter_mut().find(|v| v.id() == id)
+    }
+
+    pub fn clients_mut(&mut self) -> impl Iterator<Item = &mut AxonLoopbackClient> {
+        self.clients.iter_mut()
+    }
+}
+
+pub trait AppAxonLoopback {
+    /// 启动不绑定 socket 的回环服务器，需要 AxonServerPlugin
+    fn start_loopback_server(&mut self);
+    fn connect_loopback_client(&mut self, client_id: u64);
+    /// 以指定的 user data 标志位连接，如 AXON_FLAG_SPECTATOR
+    fn connect_loopback_client_with_flags(&mut self, client_id: u64, flags: u8);
+    fn disconnect_loopback_client(&mut self, client_id: u64);
+    fn loopback_client(&mut self, client_id: u64) -> &mut AxonLoopbackClient;
+    /// 将另一个 App（如 AxonClientPlugin 客户端）以回环方式连接到本服务器
+    #[cfg(feature = "client")]
+    fn connect_loopback_app(&mut self, client_id: u64, client: &mut App);
+    /// 以固定时间步长运行一帧
+    fn step(&mut self, dt: Duration);
+}
+
+impl AppAxonLoopback for App {
+    fn start_loopback_server(&mut self) {
+        let loopback = AxonLoopback::default();
+        self.world_mut()
+            .get_resource_or_init::<AxonServer>()
+            .add_transport(loopback.server.clone());
+        self.insert_resource(loopback);
+    }
+
+    fn connect_loopback_client(&mut self, client_id: u64) {
+        self.connect_loopback_client_with_flags(client_id, 0);
+    }
+
+    fn connect_loopback_client_with_flags(&mut self, client_id: u64, flags: u8) {
+        let mut loopback = self.world_mut().resource_mut::<AxonLoopback>();
+        let transport = loopback
+            .server
+            .connect(client_id, Some(axon_user_data(flags).to_vec()));
+        loopback.clients.push(AxonLoopbackClient {
+            transport,
+            compressed: flags & AXON_FLAG_COMPRESSION != 0,
+        });
+    }
+
+    fn disconnect_loopback_client(&mut self, client_id: u64) {
+        let mut loopback = self.world_mut().resource_mut::<AxonLoopback>();
+        let Some(i) = loopback.clients.iter().position(|v| v.id() == client_id) else {
+            return;
+        };
+        loopback.clients.remove(i).transport.disconnect();
+    }
+
+    fn loopback_client(&mut self, client_id: u64) -> &mut AxonLoopbackClient {
+        self.world_mut()
+            .resource_mut::<AxonLoopback>()
+            .into_inner()
+            .client_mut(client_id)
+            .expect("loopback client not connected")
+    }
+
+    #[cfg(feature = "client")]
+    fn connect_loopback_app(&mut self, client_id: u64, client: &mut App) {
+        let transport = self
+            .world()
+            .resource::<AxonLoopback>()
+            .server
+            .connect(client_id, None);
+        client.insert_resource(crate::client::AxonClientConnection::new(transport));
+    }
+
+    fn step(&mut self, dt: Duration) {
+        self.insert_resource(TimeUpdateStrategy::ManualDuration(dt));
+        self.update();
+    }
+}
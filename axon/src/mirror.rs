@@ -0,0 +1,146 @@
+//! FFI 客户端维护的世界镜像：按 id 记录对象类型与各变体最新的数据，
+//! 引擎可直接查询状态，不必自己重放动作流
+
+use crate::protocol::{
+    axon_parse_action, ACTION_TYPE_CHANGE, ACTION_TYPE_DESPAWN, ACTION_TYPE_RESOURCE,
+    ACTION_TYPE_RETYPE, ACTION_TYPE_SPAWN,
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// 镜像变化的种类
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxonChangeKind {
+    /// 对象生成，t 为对象类型
+    Spawn = 0,
+    /// 对象移除（包括断开时清空），t 为移除前的对象类型
+    Despawn = 1,
+    /// 变体更新，t 为变体类型
+    Change = 2,
+    /// 对象类型变更，t 为新类型
+    Retype = 3,
+    /// 资源更新，id 为 0，t 为资源类型
+    Resource = 4,
+}
+
+/// 一次镜像变化
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxonChange {
+    pub kind: AxonChangeKind,
+    pub id: u64,
+    pub t: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct AxonMirrorObject {
+    pub t: u32,
+    pub variants: HashMap<u32, Vec<u8>>,
+}
+
+#[derive(Debug, Default)]
+pub struct AxonMirror {
+    /// 按 id 排序，列出对象时顺序稳定
+    objects: BTreeMap<u64, AxonMirrorObject>,
+    resources: HashMap<u32, Vec<u8>>,
+    /// 最近一次 apply 或 clear 产生的变化，按动作顺序
+    changes: Vec<AxonChange>,
+}
+
+impl AxonMirror {
+    /// 开始新的一帧，丢弃上一帧的变化
+    pub fn begin_frame(&mut self) {
+        self.changes.clear();
+    }
+
+    /// 应用动作流，规则与 Bevy 客户端一致：重复的生成与未知对象的动作被忽略；
+    /// 遇到无法解析的动作时停止（错误由 next_action 或回调分发报告）
+    pub fn apply(&mut self, data: &[u8]) {
+        let mut pos = 0;
+        while let Some((action, next)) = axon_parse_action(data, pos) {
+            self.apply_action(action.act, action.id, action.t, action.v);
+            pos = next;
+        }
+    }
+
+    fn apply_action(&mut self, act: u8, id: u64, t: u32, v: &[u8]) {
+        let (kind, t) = match act {
+            ACTION_TYPE_SPAWN => {
+                if self.objects.contains_key(&id) {
+                    return;
+                }
+                self.objects.insert(
+                    id,
+                    AxonMirrorObject {
+                        t,
+                        variants: HashMap::new(),
+                    },
+                );
+                (AxonChangeKind::Spawn, t)
+            }
+            ACTION_TYPE_DESPAWN => {
+                let Some(object) = self.objects.remove(&id) else {
+                    return;
+                };
+                (AxonChangeKind::Despawn, object.t)
+            }
+            // 已同步的变体保留
+            ACTION_TYPE_RETYPE => {
+                let Some(object) = self.objects.get_mut(&id) else {
+                    return;
+                };
+                object.t = t;
+                (AxonChangeKind::Retype, t)
+            }
+            ACTION_TYPE_CHANGE => {
+                let Some(object) = self.objects.get_mut(&id) else {
+                    return;
+                };
+                object.variants.insert(t, v.to_vec());
+                (AxonChangeKind::Change, t)
+            }
+            ACTION_TYPE_RESOURCE => {
+                self.resources.insert(t, v.to_vec());
+                (AxonChangeKind::Resource, t)
+            }
+            _ => return,
+        };
+        self.changes.push(AxonChange { kind, id, t });
+    }
+
+    /// 断开时清空，每个对象记为 Despawn
+    pub fn clear(&mut self) {
+        for (id, object) in std::mem::take(&mut self.objects) {
+            self.changes.push(AxonChange {
+                kind: AxonChangeKind::Despawn,
+                id,
+                t: object.t,
+            });
+        }
+        self.resources.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.objects.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn object(&self, id: u64) -> Option<&AxonMirrorObject> {
+        self.objects.get(&id)
+    }
+
+    pub fn resource(&self, t: u32) -> Option<&[u8]> {
+        self.resources.get(&t).map(Vec::as_slice)
+    }
+
+    pub fn changes(&self) -> &[AxonChange] {
+        &self.changes
+    }
+}
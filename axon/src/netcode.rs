@@ -0,0 +1,300 @@
+//! renet + netcode 传输层实现
+
+use crate::protocol::{AXON_CHANNELS, AXON_CHANNEL_RELIABLE_ORDERED};
+use crate::transport::*;
+use renet::{ChannelConfig, ConnectionConfig, RenetClient, RenetServer, SendType, ServerEvent};
+use renet_netcode::{
+    ClientAuthentication, NetcodeClientTransport, NetcodeServerTransport, ServerAuthentication,
+    ServerConfig, NETCODE_USER_DATA_BYTES,
+};
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, SystemTime};
+
+fn current_time() -> Result<Duration, AxonTransportError> {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|e| AxonTransportError(format!("system time error: {:?}", e)))
+}
+
+fn bind_socket(addr: &str) -> Result<UdpSocket, AxonTransportError> {
+    let socket = UdpSocket::bind(addr)
+        .map_err(|e| AxonTransportError(format!("failed to bind udp socket: {:?}", e)))?;
+    socket
+        .set_nonblocking(true)
+        .map_err(|e| AxonTransportError(format!("failed to set nonblocking: {:?}", e)))?;
+    Ok(socket)
+}
+
+fn parse_addr(addr: &str) -> Result<SocketAddr, AxonTransportError> {
+    addr.parse()
+        .map_err(|e| AxonTransportError(format!("failed to parse address '{}': {:?}", addr, e)))
+}
+
+/// 同步流固定使用可靠有序通道，双向都必须配置
+fn check_config(config: &ConnectionConfig) -> Result<(), AxonTransportError> {
+    for channels in [
+        &config.server_channels_config,
+        &config.client_channels_config,
+    ] {
+        let ok = channels.iter().any(|v| {
+            v.channel_id == AXON_CHANNEL_RELIABLE_ORDERED
+                && matches!(v.send_type, SendType::ReliableOrdered { .. })
+        });
+        if !ok {
+            return Err(AxonTransportError(format!(
+                "channel {} must be reliable ordered",
+                AXON_CHANNEL_RELIABLE_ORDERED
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 配置中的通道 id，按 AXON_CHANNELS 的顺序排列，其余通道按配置顺序在后
+fn channel_ids(channels: &[ChannelConfig]) -> Vec<u8> {
+    let mut ids: Vec<u8> = channels.iter().map(|v| v.channel_id).collect();
+    ids.sort_by_key(|id| {
+        AXON_CHANNELS
+            .iter()
+            .position(|v| v == id)
+            .unwrap_or(AXON_CHANNELS.len())
+    });
+    ids
+}
+
+/// 基于 UDP 的 renet 服务器传输层
+pub struct AxonNetcodeServerTransport {
+    server: RenetServer,
+    transport: NetcodeServerTransport,
+    /// 客户端发往服务器的通道
+    channels: Vec<u8>,
+}
+
+impl AxonNetcodeServerTransport {
+    /// server 需使用默认的通道配置，自定义通道请用 bind_with
+    pub fn new(server: RenetServer, transport: NetcodeServerTransport) -> Self {
+        Self {
+            server,
+            transport,
+            channels: AXON_CHANNELS.to_vec(),
+        }
+    }
+
+    /// 在 addr 上监听非加密连接
+    pub fn bind(addr: &str, max_clients: usize) -> Result<Self, AxonTransportError> {
+        Self::bind_with(addr, max_clients, 0, ConnectionConfig::default())
+    }
+
+    /// 同 bind，指定协议 id 与通道配置（需与客户端一致）。
+    /// 通道 2 必须为可靠有序，其余通道可省略
+    pub fn bind_with(
+        addr: &str,
+        max_clients: usize,
+        protocol_id: u64,
+        config: ConnectionConfig,
+    ) -> Result<Self, AxonTransportError> {
+        check_config(&config)?;
+        let server_addr = parse_addr(addr)?;
+        let socket = bind_socket(addr)?;
+        let server_config = ServerConfig {
+            current_time: current_time()?,
+            max_clients,
+            protocol_id,
+            public_addresses: vec![server_addr],
+            authentication: ServerAuthentication::Unsecure,
+        };
+        let transport = NetcodeServerTransport::new(server_config, socket)
+            .map_err(|e| AxonTransportError(format!("failed to create transport: {:?}", e)))?;
+        let channels = channel_ids(&config.client_channels_config);
+        Ok(Self {
+            channels,
+            ..Self::new(RenetServer::new(config), transport)
+        })
+    }
+
+    pub fn server(&self) -> &RenetServer {
+        &self.server
+    }
+
+    pub fn server_mut(&mut self) -> &mut RenetServer {
+        &mut self.server
+    }
+
+    pub fn transport(&self) -> &NetcodeServerTransport {
+        &self.transport
+    }
+}
+
+impl AxonServerTransport for AxonNetcodeServerTransport {
+    fn update(&mut self, dt: Duration) -> Result<(), AxonTransportError> {
+        self.server.update(dt);
+        self.transport
+            .update(dt, &mut self.server)
+            .map_err(|e| AxonTransportError(e.to_string()))
+    }
+
+    fn next_event(&mut self) -> Option<AxonServerEvent> {
+        let event = match self.server.get_event()? {
+            ServerEvent::ClientConnected { client_id } => AxonServerEvent::Connected {
+                client_id,
+                user_data: self.transport.user_data(client_id).map(|v| v.to_vec()),
+            },
+            ServerEvent::ClientDisconnected { client_id, reason } => {
+                AxonServerEvent::Disconnected {
+                    client_id,
+                    reason: reason.to_string(),
+                }
+            }
+        };
+        Some(event)
+    }
+
+    fn clients(&self) -> Vec<u64> {
+        self.server.clients_id()
+    }
+
+    fn channels(&self) -> Vec<u8> {
+        self.channels.clone()
+    }
+
+    fn receive(&mut self, client_id: u64, channel: u8) -> Option<Vec<u8>> {
+        self.server
+            .receive_message(client_id, channel)
+            .map(|v| v.to_vec())
+    }
+
+    fn send(&mut self, client_id: u64, channel: u8, data: Vec<u8>) {
+        self.server.send_message(client_id, channel, data);
+    }
+
+    fn broadcast(&mut self, channel: u8, data: Vec<u8>) {
+        self.server.broadcast_message(channel, data);
+    }
+
+    fn flush(&mut self) -> Result<(), AxonTransportError> {
+        self.transport.send_packets(&mut self.server);
+        Ok(())
+    }
+
+    fn disconnect(&mut self, client_id: u64) {
+        self.server.disconnect(client_id);
+    }
+
+    fn disconnect_all(&mut self) {
+        self.transport.disconnect_all(&mut self.server);
+    }
+}
+
+/// 基于 UDP 的 renet 客户端传输层
+pub struct AxonNetcodeClientTransport {
+    client: RenetClient,
+    transport: NetcodeClientTransport,
+    /// 服务器发往客户端的通道
+    channels: Vec<u8>,
+}
+
+impl AxonNetcodeClientTransport {
+    /// client 需使用默认的通道配置，自定义通道请用 connect_with
+    pub fn new(client: RenetClient, transport: NetcodeClientTransport) -> Self {
+        Self {
+            client,
+            transport,
+            channels: AXON_CHANNELS.to_vec(),
+        }
+    }
+
+    /// 从 bind_addr 发起到 server_addr 的非加密连接
+    pub fn connect(
+        bind_addr: &str,
+        server_addr: &str,
+        client_id: u64,
+        user_data: Option<[u8; NETCODE_USER_DATA_BYTES]>,
+    ) -> Result<Self, AxonTransportError> {
+        Self::connect_with(
+            bind_addr,
+            server_addr,
+            client_id,
+            user_data,
+            0,
+            ConnectionConfig::default(),
+        )
+    }
+
+    /// 同 connect，指定协议 id 与通道配置（需与服务器 bind_with 一致）
+    pub fn connect_with(
+        bind_addr: &str,
+        server_addr: &str,
+        client_id: u64,
+        user_data: Option<[u8; NETCODE_USER_DATA_BYTES]>,
+        protocol_id: u64,
+        config: ConnectionConfig,
+    ) -> Result<Self, AxonTransportError> {
+        check_config(&config)?;
+        let server_addr = parse_addr(server_addr)?;
+        let socket = bind_socket(bind_addr)?;
+        let authentication = ClientAuthentication::Unsecure {
+            server_addr,
+            client_id,
+            user_data,
+            protocol_id,
+        };
+        let transport = NetcodeClientTransport::new(current_time()?, authentication, socket)
+            .map_err(|e| AxonTransportError(format!("failed to create transport: {:?}", e)))?;
+        let channels = channel_ids(&config.server_channels_config);
+        Ok(Self {
+            channels,
+            ..Self::new(RenetClient::new(config), transport)
+        })
+    }
+
+    pub fn client(&self) -> &RenetClient {
+        &self.client
+    }
+
+    pub fn transport(&self) -> &NetcodeClientTransport {
+        &self.transport
+    }
+}
+
+impl AxonClientTransport for AxonNetcodeClientTransport {
+    fn update(&mut self, dt: Duration) -> Result<(), AxonTransportError> {
+        self.client.update(dt);
+        self.transport
+            .update(dt, &mut self.client)
+            .map_err(|e| AxonTransportError(e.to_string()))
+    }
+
+    fn is_connected(&self) -> bool {
+        self.client.is_connected()
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.client.is_disconnected()
+    }
+
+    fn channels(&self) -> Vec<u8> {
+        self.channels.clone()
+    }
+
+    fn receive(&mut self, channel: u8) -> Option<Vec<u8>> {
+        self.client.receive_message(channel).map(|v| v.to_vec())
+    }
+
+    fn send(&mut self, channel: u8, data: Vec<u8>) {
+        self.client.send_message(channel, data);
+    }
+
+    fn flush(&mut self) -> Result<(), AxonTransportError> {
+        if !self.client.is_connected() {
+            return Ok(());
+        }
+        self.transport
+            .send_packets(&mut self.client)
+            .map_err(|e| AxonTransportError(e.to_string()))
+    }
+
+    fn disconnect(&mut self) {
+        self.client.disconnect();
+        self.transport.disconnect();
+    }
+}
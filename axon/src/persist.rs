@@ -0,0 +1,350 @@
+//! 将同步状态保存到文件，并在启动时通过注册的类型重建
+
+use crate::core::*;
+use crate::server::AxonServerSnapshot;
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_sbin::SbinError;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 存档文件头
+const SAVE_MAGIC: &[u8; 4] = b"AXSV";
+/// 版本 2：带多个对象类型的实体每个类型一条 SPAWN，对外类型在最后
+const SAVE_VERSION: u32 = 2;
+
+/// 已加载的存档：快照中的资源、对象与变体
+#[derive(Debug, Clone, Default)]
+pub struct AxonWorldSave {
+    pub actions: Vec<AxonAction>,
+}
+
+impl AxonWorldSave {
+    pub fn from_snapshot(snapshot: &AxonServerSnapshot, objects: &AxonObjectSet) -> Self {
+        let mut actions = Vec::new();
+        for action in read_axon_actions(&snapshot.snapshot()) {
+            // 快照只有对外类型，实体上的其他对象类型写在它之前
+            if action.act == ACTION_TYPE_SPAWN {
+                let types =
+                    Entity::try_from_bits(action.id).map_or(&[][..], |e| objects.object_types(e));
+                for &t in types.iter().filter(|&&t| t != action.t) {
+                    actions.push(AxonAction {
+                        act: ACTION_TYPE_SPAWN,
+                        id: action.id,
+                        t,
+                        v: Vec::new(),
+                    });
+                }
+            }
+            actions.push(action);
+        }
+        Self { actions }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(SAVE_MAGIC);
+        bytes.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+        for action in self.actions.iter() {
+            bytes.extend(encode_axon_action(
+                action.act, action.id, action.t, &action.v,
+            ));
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < 8 || &bytes[0..4] != SAVE_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an axon save",
+            ));
+        }
+        let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        // 版本 1 的存档是版本 2 的子集
+        if version == 0 || version > SAVE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported axon save version: {}", version),
+            ));
+        }
+        Ok(Self {
+            actions: read_axon_actions(&bytes[8..]),
+        })
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// 先写临时文件再改名，崩溃时不会留下半个存档
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, self.to_bytes())?;
+        fs::rename(&tmp, path)
+    }
+}
+
+/// 定期保存同步状态
+#[derive(Resource)]
+pub struct AxonAutosave {
+    path: PathBuf,
+    timer: Timer,
+}
+
+impl AxonAutosave {
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self {
+            path: path.into(),
+            timer: Timer::new(interval, TimerMode::Repeating),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn autosave_system(
+    time: Res<Time>,
+    mut autosave: ResMut<AxonAutosave>,
+    snapshot: Res<AxonServerSnapshot>,
+    objects: Res<AxonObjectSet>,
+) {
+    if !autosave.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    if let Err(e) = AxonWorldSave::from_snapshot(&snapshot, &objects).write(&autosave.path) {
+        warn!("axon autosave error: {:?}", e);
+    }
+}
+
+/// 查找类型的重建函数，找不到时每个类型只警告一次
+fn find_restore(
+    world: &World,
+    kind: AxonTypeKind,
+    t: u32,
+    skipped: &mut HashSet<(AxonTypeKind, u32)>,
+) -> Option<AxonRestore> {
+    let info = world.resource::<AxonTypeRegistry>().get(kind, t);
+    if let Some(restore) = info.and_then(|v| v.restore) {
+        return Some(restore);
+    }
+    if skipped.insert((kind, t)) {
+        match info {
+            Some(info) => warn!(
+                "axon restore skipped {:?} {} ({}): not registered with add_axon_persistent_*",
+                kind, t, info.type_name
+            ),
+            None => warn!("axon restore skipped unregistered {:?} {}", kind, t),
+        }
+    }
+    None
+}
+
+/// 按存档重建实体与资源，返回重建的实体数。未注册为可持久化的类型会被跳过
+pub fn restore_axon_world(world: &mut World, save: &AxonWorldSave) -> usize {
+    let mut entities: HashMap<u64, Entity> = HashMap::new();
+    let mut types: HashMap<Entity, Vec<u32>> = HashMap::new();
+    let mut skipped = HashSet::new();
+    for action in save.actions.iter() {
+        match action.act {
+            ACTION_TYPE_RESOURCE => {
+                let restore = find_restore(world, AxonTypeKind::Resource, action.t, &mut skipped);
+                if let Some(AxonRestore::Resource(restore)) = restore {
+                    if let Err(e) = restore(&action.v, world) {
+                        warn!("axon resource {} restore error: {}", action.t, e);
+                    }
+                }
+            }
+            ACTION_TYPE_SPAWN => {
+                let restore = find_restore(world, AxonTypeKind::Object, action.t, &mut skipped);
+                if let Some(AxonRestore::Object(restore)) = restore {
+                    // 同一 id 的多条 SPAWN 重建到同一个实体上
+                    let entity = *entities
+                        .entry(action.id)
+                        .or_insert_with(|| world.spawn_empty().id());
+                    restore(&mut world.entity_mut(entity));
+                    types.entry(entity).or_default().push(action.t);
+                }
+            }
+            ACTION_TYPE_CHANGE => {
+                let Some(&entity) = entities.get(&action.id) else {
+                    continue;
+                };
+                let restore = find_restore(world, AxonTypeKind::Variant, action.t, &mut skipped);
+                if let Some(AxonRestore::Variant(restore)) = restore {
+                    if let Err(e) = restore(&action.v, &mut world.entity_mut(entity)) {
+                        warn!("axon variant {} restore error: {}", action.t, e);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    // 各对象类型在同一帧加入，按存档顺序决定对外类型
+    if let Some(mut objects) = world.get_resource_mut::<AxonObjectSet>() {
+        for (entity, types) in types {
+            objects.restore_types(entity, types);
+        }
+    }
+    entities.len()
+}
+
+fn set_restore(app: &mut App, kind: AxonTypeKind, type_id: u32, restore: AxonRestore) {
+    app.world_mut()
+        .resource_mut::<AxonTypeRegistry>()
+        .set_restore(kind, type_id, restore);
+}
+
+pub trait AppAxonPersist {
+    /// 同 add_axon_object，恢复存档时以 T::default() 重建对象
+    fn add_axon_persistent_object<T: AxonObject + Component + Default>(&mut self);
+    /// 同 add_axon_variant，恢复存档时解码并插入变体 T
+    fn add_axon_persistent_variant<T: AxonVariant + Component + Serialize + DeserializeOwned>(
+        &mut self,
+    );
+    /// 同 add_axon_resource，恢复存档时解码并插入资源 T
+    fn add_axon_persistent_resource<T: AxonResource + Resource + Serialize + DeserializeOwned>(
+        &mut self,
+    );
+    /// 将当前同步状态保存到 path
+    fn save_axon_world(&mut self, path: impl AsRef<Path>) -> io::Result<()>;
+    /// 从 path 恢复同步状态，返回重建的实体数
+    fn load_axon_world(&mut self, path: impl AsRef<Path>) -> io::Result<usize>;
+    /// 每隔 interval 保存一次到 path，再次调用时替换路径与间隔
+    fn enable_axon_autosave(&mut self, path: impl Into<PathBuf>, interval: Duration);
+}
+
+impl AppAxonPersist for App {
+    fn add_axon_persistent_object<T: AxonObject + Component + Default>(&mut self) {
+        fn restore<T: Component + Default>(e: &mut EntityWorldMut<'_>) {
+            e.insert(T::default());
+        }
+        self.add_axon_object::<T>();
+        set_restore(
+            self,
+            AxonTypeKind::Object,
+            T::axon_object_type(),
+            AxonRestore::Object(restore::<T>),
+        );
+    }
+
+    fn add_axon_persistent_variant<T: AxonVariant + Component + Serialize + DeserializeOwned>(
+        &mut self,
+    ) {
+        fn restore<T: Component + DeserializeOwned>(
+            v: &[u8],
+            e: &mut EntityWorldMut<'_>,
+        ) -> Result<(), SbinError> {
+            e.insert(serde_sbin::from_bytes::<T>(v)?);
+            Ok(())
+        }
+        self.add_axon_variant::<T>();
+        set_restore(
+            self,
+            AxonTypeKind::Variant,
+            T::axon_variant_type(),
+            AxonRestore::Variant(restore::<T>),
+        );
+    }
+
+    fn add_axon_persistent_resource<T: AxonResource + Resource + Serialize + DeserializeOwned>(
+        &mut self,
+    ) {
+        fn restore<T: Resource + DeserializeOwned>(
+            v: &[u8],
+            world: &mut World,
+        ) -> Result<(), SbinError> {
+            world.insert_resource(serde_sbin::from_bytes::<T>(v)?);
+            Ok(())
+        }
+        self.add_axon_resource::<T>();
+        set_restore(
+            self,
+            AxonTypeKind::Resource,
+            T::axon_resource_type(),
+            AxonRestore::Resource(restore::<T>),
+        );
+    }
+
+    fn save_axon_world(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let world = self.world();
+        AxonWorldSave::from_snapshot(
+            world.resource::<AxonServerSnapshot>(),
+            world.resource::<AxonObjectSet>(),
+        )
+        .write(path)
+    }
+
+    fn load_axon_world(&mut self, path: impl AsRef<Path>) -> io::Result<usize> {
+        let save = AxonWorldSave::open(path)?;
+        Ok(restore_axon_world(self.world_mut(), &save))
+    }
+
+    fn enable_axon_autosave(&mut self, path: impl Into<PathBuf>, interval: Duration) {
+        // 再次调用只替换配置，系统只添加一次
+        let enabled = self.world().contains_resource::<AxonAutosave>();
+        self.insert_resource(AxonAutosave::new(path, interval));
+        if enabled {
+            return;
+        }
+        self.add_systems(
+            Last,
+            autosave_system.run_if(resource_exists::<AxonAutosave>),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_autosave_once() {
+        let mut app = App::new();
+        let systems = |app: &mut App| app.get_schedule(Last).map_or(0, |v| v.systems_len());
+        app.enable_axon_autosave("a.axsv", Duration::from_secs(1));
+        let count = systems(&mut app);
+        app.enable_axon_autosave("b.axsv", Duration::from_secs(2));
+        assert_eq!(systems(&mut app), count);
+        assert_eq!(
+            app.world().resource::<AxonAutosave>().path(),
+            Path::new("b.axsv")
+        );
+    }
+
+    #[test]
+    fn test_save_roundtrip() {
+        let mut snapshot = AxonServerSnapshot::default();
+        snapshot.apply(ACTION_TYPE_RESOURCE, 0, 4, &[1, 2]);
+        snapshot.apply(ACTION_TYPE_SPAWN, 7, 1, &[]);
+        snapshot.apply(ACTION_TYPE_CHANGE, 7, 2, &[3]);
+
+        let path = crate::test_temp_path("persist_test.axsv");
+        AxonWorldSave::from_snapshot(&snapshot, &AxonObjectSet::default())
+            .write(&path)
+            .unwrap();
+        let save = AxonWorldSave::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let acts: Vec<(u8, u64, u32)> = save.actions.iter().map(|v| (v.act, v.id, v.t)).collect();
+        assert_eq!(
+            acts,
+            vec![
+                (ACTION_TYPE_RESOURCE, 0, 4),
+                (ACTION_TYPE_SPAWN, 7, 1),
+                (ACTION_TYPE_CHANGE, 7, 2)
+            ]
+        );
+        assert!(AxonWorldSave::from_bytes(b"AXRC\x01\x00\x00\x00").is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! 客户端与服务器共享的协议常量
+
+/// netcode 连接时携带的 user data 长度
+pub const AXON_USER_DATA_BYTES: usize = 256;
+
+/// user data 中标志位所在的字节
+pub const AXON_USER_DATA_FLAGS: usize = 0;
+
+/// 观战连接：只接收同步流，所有上行消息都会被拒绝
+pub const AXON_FLAG_SPECTATOR: u8 = 1 << 0;
+
+/// 支持压缩：服务器发来的每条消息都带 1 字节消息头。
+/// 服务器不确认该标志，客户端按首字节区分（见 axon_decode_message），不支持压缩的服务器照常发送原始动作流
+pub const AXON_FLAG_COMPRESSION: u8 = 1 << 1;
+
+/// 客户端连接时声明的压缩标志：开启 compression 特性时为 AXON_FLAG_COMPRESSION，否则为 0
+pub fn axon_compression_flag() -> u8 {
+    if cfg!(feature = "compression") {
+        AXON_FLAG_COMPRESSION
+    } else {
+        0
+    }
+}
+
+/// 根据标志位构造 user data
+pub fn axon_user_data(flags: u8) -> [u8; AXON_USER_DATA_BYTES] {
+    let mut data = [0u8; AXON_USER_DATA_BYTES];
+    data[AXON_USER_DATA_FLAGS] = flags;
+    data
+}
+
+/// 从 user data 读取标志位
+pub fn axon_user_flags(data: &[u8]) -> u8 {
+    data.get(AXON_USER_DATA_FLAGS).copied().unwrap_or(0)
+}
+
+/// 不可靠通道，编号与 renet DefaultChannel 一致
+pub const AXON_CHANNEL_UNRELIABLE: u8 = 0;
+
+/// 可靠无序通道
+pub const AXON_CHANNEL_RELIABLE_UNORDERED: u8 = 1;
+
+/// 可靠有序通道，同步流默认使用该通道
+pub const AXON_CHANNEL_RELIABLE_ORDERED: u8 = 2;
+
+/// 所有通道，按读取顺序排列
+pub const AXON_CHANNELS: [u8; 3] = [
+    AXON_CHANNEL_RELIABLE_ORDERED,
+    AXON_CHANNEL_RELIABLE_UNORDERED,
+    AXON_CHANNEL_UNRELIABLE,
+];
+
+pub const ACTION_TYPE_SPAWN: u8 = 1;
+pub const ACTION_TYPE_DESPAWN: u8 = 2;
+pub const ACTION_TYPE_CHANGE: u8 = 3;
+pub const ACTION_TYPE_INVOKE: u8 = 4;
+/// 资源变更，id 固定为 0，t 为资源类型
+pub const ACTION_TYPE_RESOURCE: u8 = 5;
+/// 对象类型变更（如 Player 换成 Corpse），t 为新类型，已同步的变体保留
+pub const ACTION_TYPE_RETYPE: u8 = 6;
+/// 入场快照发送完毕，id 与 t 均为 0
+pub const ACTION_TYPE_SYNC: u8 = 7;
+
+/// 消息头：未压缩。消息头取值不与 sbin 类型标记（0..=16）冲突，
+/// 原始动作流总是以 SBIN_U8 开头，不会被误认为消息头
+pub const AXON_MESSAGE_RAW: u8 = 0xa0;
+
+/// 消息头：lz4 压缩，头后为 4 字节小端原始长度与压缩数据
+pub const AXON_MESSAGE_LZ4: u8 = 0xa1;
+
+/// 默认压缩阈值，小于该长度的消息不压缩
+pub const AXON_COMPRESSION_THRESHOLD: usize = 256;
+
+/// 解压后的最大长度，防止恶意长度字段
+pub const AXON_MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// 为协商了压缩的连接编码消息，长度不小于 threshold 且压缩后更短时使用 lz4；
+/// 未开启 compression 特性时总是不压缩
+pub fn axon_encode_message(data: &[u8], threshold: usize) -> Vec<u8> {
+    #[cfg(feature = "compression")]
+    if data.len() >= threshold {
+        let compressed = lz4_flex::compress_prepend_size(data);
+        if compressed.len() < data.len() {
+            let mut out = Vec::with_capacity(compressed.len() + 1);
+            out.push(AXON_MESSAGE_LZ4);
+            out.extend(compressed);
+            return out;
+        }
+    }
+    #[cfg(not(feature = "compression"))]
+    let _ = threshold;
+    let mut out = Vec::with_capacity(data.len() + 1);
+    out.push(AXON_MESSAGE_RAW);
+    out.extend_from_slice(data);
+    out
+}
+
+/// 解码消息：首字节不是消息头时视为未协商压缩的原始动作流原样返回；
+/// 压缩数据损坏或未开启 compression 特性时 lz4 消息返回 None
+pub fn axon_decode_message(data: &[u8]) -> Option<Vec<u8>> {
+    let Some((&header, body)) = data.split_first() else {
+        return Some(Vec::new());
+    };
+    match header {
+        AXON_MESSAGE_RAW => Some(body.to_vec()),
+        #[cfg(feature = "compression")]
+        AXON_MESSAGE_LZ4 => {
+            let size = u32::from_le_bytes(body.get(0..4)?.try_into().ok()?) as usize;
+            if size > AXON_MAX_MESSAGE_BYTES {
+                return None;
+            }
+            lz4_flex::decompress_size_prepended(body).ok()
+        }
+        #[cfg(not(feature = "compression"))]
+        AXON_MESSAGE_LZ4 => None,
+        _ => Some(data.to_vec()),
+    }
+}
+
+/// sbin 类型标记，见 serde_sbin::SbinType；动作流只用到其中几个，json 模块使用全部
+#[cfg_attr(not(feature = "ffi"), allow(dead_code))]
+pub(crate) mod sbin {
+    pub(crate) const SBIN_NIL: u8 = 0;
+    pub(crate) const SBIN_U8: u8 = 1;
+    pub(crate) const SBIN_U16: u8 = 2;
+    pub(crate) const SBIN_U32: u8 = 3;
+    pub(crate) const SBIN_U64: u8 = 4;
+    pub(crate) const SBIN_I8: u8 = 5;
+    pub(crate) const SBIN_I16: u8 = 6;
+    pub(crate) const SBIN_I32: u8 = 7;
+    pub(crate) const SBIN_I64: u8 = 8;
+    pub(crate) const SBIN_F32: u8 = 9;
+    pub(crate) const SBIN_F64: u8 = 10;
+    pub(crate) const SBIN_BOOL: u8 = 11;
+    pub(crate) const SBIN_STR: u8 = 12;
+    pub(crate) const SBIN_BYTES: u8 = 13;
+    pub(crate) const SBIN_ARRAY: u8 = 14;
+    pub(crate) const SBIN_OBJECT: u8 = 15;
+    pub(crate) const SBIN_END: u8 = 16;
+}
+
+use sbin::{SBIN_BYTES, SBIN_U32, SBIN_U64, SBIN_U8};
+
+/// 动作流中的一条动作，v 借用自原始数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxonActionRef<'a> {
+    pub act: u8,
+    pub id: u64,
+    pub t: u32,
+    pub v: &'a [u8],
+}
+
+fn read_tagged<'a>(data: &'a [u8], pos: &mut usize, tag: u8, len: usize) -> Option<&'a [u8]> {
+    if *data.get(*pos)? != tag {
+        return None;
+    }
+    let v = data.get(*pos + 1..*pos + 1 + len)?;
+    *pos += 1 + len;
+    Some(v)
+}
+
+/// 不依赖 serde 解析 data[pos..] 处的一条动作（act, id, t, v），
+/// 返回动作与下一条动作的位置；数据不完整或类型不符时返回 None
+pub fn axon_parse_action(data: &[u8], pos: usize) -> Option<(AxonActionRef<'_>, usize)> {
+    let mut pos = pos;
+    let act = read_tagged(data, &mut pos, SBIN_U8, 1)?[0];
+    let id = u64::from_le_bytes(read_tagged(data, &mut pos, SBIN_U64, 8)?.try_into().ok()?);
+    let t = u32::from_le_bytes(read_tagged(data, &mut pos, SBIN_U32, 4)?.try_into().ok()?);
+    let len = u32::from_le_bytes(
+        read_tagged(data, &mut pos, SBIN_BYTES, 4)?
+            .try_into()
+            .ok()?,
+    );
+    let v = data.get(pos..pos.checked_add(len as usize)?)?;
+    Some((AxonActionRef { act, id, t, v }, pos + v.len()))
+}
+
+/// 不依赖 serde 把一条动作追加到 out，与 axon_parse_action 对应
+pub fn axon_write_action(out: &mut Vec<u8>, act: u8, id: u64, t: u32, v: &[u8]) {
+    out.extend([SBIN_U8, act, SBIN_U64]);
+    out.extend(id.to_le_bytes());
+    out.push(SBIN_U32);
+    out.extend(t.to_le_bytes());
+    out.push(SBIN_BYTES);
+    out.extend((v.len() as u32).to_le_bytes());
+    out.extend_from_slice(v);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_roundtrip() {
+        let small = vec![1u8, 2, 3];
+        let encoded = axon_encode_message(&small, AXON_COMPRESSION_THRESHOLD);
+        assert_eq!(encoded[0], AXON_MESSAGE_RAW);
+        assert_eq!(axon_decode_message(&encoded), Some(small));
+
+        let large = vec![7u8; 4096];
+        let encoded = axon_encode_message(&large, AXON_COMPRESSION_THRESHOLD);
+        if cfg!(feature = "compression") {
+            assert_eq!(encoded[0], AXON_MESSAGE_LZ4);
+            assert!(encoded.len() < large.len());
+        } else {
+            assert_eq!(encoded[0], AXON_MESSAGE_RAW);
+        }
+        assert_eq!(axon_decode_message(&encoded), Some(large));
+
+        // 不支持压缩的服务器发来的原始动作流
+        let mut raw = Vec::new();
+        axon_write_action(&mut raw, ACTION_TYPE_SPAWN, 7, 1, &[]);
+        assert_eq!(raw[0], SBIN_U8);
+        assert_eq!(axon_decode_message(&raw), Some(raw));
+        assert_eq!(axon_decode_message(&[]), Some(Vec::new()));
+        assert_eq!(axon_decode_message(&[AXON_MESSAGE_LZ4, 1]), None);
+        assert_eq!(
+            axon_decode_message(&[AXON_MESSAGE_LZ4, 0xff, 0xff, 0xff, 0xff]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_action() {
+        // act=3, id=7, t=2, v=[9, 8]
+        let mut data = vec![SBIN_U8, 3, SBIN_U64];
+        data.extend(7u64.to_le_bytes());
+        data.push(SBIN_U32);
+        data.extend(2u32.to_le_bytes());
+        data.push(SBIN_BYTES);
+        data.extend(2u32.to_le_bytes());
+        data.extend([9, 8]);
+        let one = data.len();
+        data.extend_from_slice(&data.clone()[..one - 1]);
+
+        let (action, next) = axon_parse_action(&data, 0).unwrap();
+        assert_eq!(
+            action,
+            AxonActionRef {
+                act: 3,
+                id: 7,
+                t: 2,
+                v: &[9, 8]
+            }
+        );
+        assert_eq!(next, one);
+        // 第二条缺一个字节
+        assert_eq!(axon_parse_action(&data, next), None);
+        assert_eq!(axon_parse_action(&data, data.len()), None);
+    }
+
+    #[cfg(any(feature = "server", feature = "client"))]
+    #[test]
+    fn test_parse_encoded_action() {
+        let data = crate::core::encode_axon_action(4, u64::MAX, 11, &[1, 2, 3]);
+        let (action, next) = axon_parse_action(&data, 0).unwrap();
+        assert_eq!((action.act, action.id, action.t), (4, u64::MAX, 11));
+        assert_eq!(action.v, &[1, 2, 3]);
+        assert_eq!(next, data.len());
+
+        let mut written = Vec::new();
+        axon_write_action(&mut written, 4, u64::MAX, 11, &[1, 2, 3]);
+        assert_eq!(written, data);
+    }
+}
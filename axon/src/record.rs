@@ -0,0 +1,365 @@
+use crate::core::*;
+use crate::protocol::*;
+use crate::server::{AxonServer, AxonServerSnapshot};
+use crate::transport::AxonServerEvent;
+use bevy::prelude::*;
+use serde::{Deserialize, Serializer};
+use serde_bytes::ByteBuf;
+use serde_sbin::{SbinDeserializer, SbinSerializer};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// 录制文件头
+const RECORD_MAGIC: &[u8; 4] = b"AXRC";
+const RECORD_VERSION: u32 = 1;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxonRecordKind {
+    /// 服务器发出的消息，client_id 为 0 表示广播
+    Outbound = 1,
+    /// 客户端发来的消息
+    Inbound = 2,
+}
+
+impl AxonRecordKind {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(AxonRecordKind::Outbound),
+            2 => Some(AxonRecordKind::Inbound),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AxonRecordFrame {
+    /// 距离录制开始的秒数
+    pub time: f64,
+    pub kind: AxonRecordKind,
+    pub client_id: u64,
+    pub data: Vec<u8>,
+}
+
+/// 录制服务器同步流：所有发出的消息与收到的客户端消息
+#[derive(Resource)]
+pub struct AxonRecorder {
+    out: BufWriter<File>,
+    start: Instant,
+}
+
+impl AxonRecorder {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(RECORD_MAGIC)?;
+        out.write_all(&RECORD_VERSION.to_le_bytes())?;
+        Ok(Self {
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, kind: AxonRecordKind, client_id: u64, data: &[u8]) {
+        let mut s = SbinSerializer::new();
+        s.serialize_u8(kind as u8).unwrap();
+        s.serialize_f64(self.start.elapsed().as_secs_f64()).unwrap();
+        s.serialize_u64(client_id).unwrap();
+        s.serialize_bytes(data).unwrap();
+        if let Err(e) = self.out.write_all(&s.into_vec()) {
+            warn!("axon recorder write error: {:?}", e);
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+pub(crate) fn record_flush_system(mut recorder: ResMut<AxonRecorder>) {
+    if let Err(e) = recorder.flush() {
+        warn!("axon recorder flush error: {:?}", e);
+    }
+}
+
+/// 已加载的录制文件
+#[derive(Debug, Clone, Default)]
+pub struct AxonRecording {
+    pub frames: Vec<AxonRecordFrame>,
+}
+
+impl AxonRecording {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < 8 || &bytes[0..4] != RECORD_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an axon recording",
+            ));
+        }
+        let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if version != RECORD_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported axon recording version: {}", version),
+            ));
+        }
+
+        let mut dec = SbinDeserializer::from_bytes(&bytes[8..]);
+        let mut frames = Vec::new();
+        loop {
+            let kind: Result<u8, _> = Deserialize::deserialize(&mut dec);
+            let Ok(kind) = kind else { break };
+            let time: Result<f64, _> = Deserialize::deserialize(&mut dec);
+            let Ok(time) = time else { break };
+            let client_id: Result<u64, _> = Deserialize::deserialize(&mut dec);
+            let Ok(client_id) = client_id else { break };
+            let data: Result<ByteBuf, _> = Deserialize::deserialize(&mut dec);
+            let Ok(data) = data else { break };
+            let Some(kind) = AxonRecordKind::from_u8(kind) else {
+                continue;
+            };
+            frames.push(AxonRecordFrame {
+                time,
+                kind,
+                client_id,
+                data: data.into_vec(),
+            });
+        }
+        Ok(Self { frames })
+    }
+
+    /// 录制总时长（秒）
+    pub fn duration(&self) -> f64 {
+        self.frames.last().map(|v| v.time).unwrap_or(0.0)
+    }
+}
+
+/// 回放录制文件，不运行任何游戏逻辑。
+/// 只回放广播消息；发给单个客户端的消息（如入场快照）由回放状态重新生成。
+#[derive(Resource)]
+pub struct AxonReplay {
+    recording: AxonRecording,
+    cursor: usize,
+    clock: f64,
+    speed: f64,
+    paused: bool,
+    seek: Option<f64>,
+    state: AxonServerSnapshot,
+}
+
+impl AxonReplay {
+    pub fn new(recording: AxonRecording) -> Self {
+        Self {
+            recording,
+            cursor: 0,
+            clock: 0.0,
+            speed: 1.0,
+            paused: false,
+            seek: None,
+            state: AxonServerSnapshot::default(),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(AxonRecording::open(path)?))
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.0);
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// 跳转到指定时间（秒），在下一帧生效
+    pub fn seek(&mut self, time: f64) {
+        self.seek = Some(time.clamp(0.0, self.duration()));
+    }
+
+    pub fn position(&self) -> f64 {
+        self.clock
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.recording.duration()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.recording.frames.len()
+    }
+
+    /// 当前回放状态的快照
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.state.snapshot()
+    }
+
+    /// 推进到 time，返回期间需要广播的消息
+    fn advance(&mut self, time: f64) -> Vec<Vec<u8>> {
+        let mut out = Vec::new();
+        while let Some(frame) = self.recording.frames.get(self.cursor) {
+            if frame.time > time {
+                break;
+            }
+            self.cursor += 1;
+            if frame.kind != AxonRecordKind::Outbound || frame.client_id != 0 {
+                continue;
+            }
+            for action in read_axon_actions(&frame.data) {
+                self.state.apply(action.act, action.id, action.t, &action.v);
+            }
+            out.push(frame.data.clone());
+        }
+        self.clock = time;
+        out
+    }
+
+    /// 重建 time 时刻的状态，返回让客户端切换到新状态的消息
+    fn rebuild(&mut self, time: f64) -> Vec<u8> {
+        let old: Vec<u64> = self.state.ids().collect();
+        if time < self.clock {
+            self.state.clear();
+            self.cursor = 0;
+        }
+        self.advance(time);
+
+        let mut data = Vec::new();
+        for id in old {
+            axon_write_action(&mut data, ACTION_TYPE_DESPAWN, id, 0, &[]);
+        }
+        data.extend(self.state.snapshot());
+        data
+    }
+}
+
+#[derive(Default)]
+pub struct AxonReplayPlugin;
+
+impl Plugin for AxonReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AxonServer>();
+        app.add_systems(
+            Update,
+            replay_axon_system.run_if(resource_exists::<AxonReplay>),
+        );
+    }
+}
+
+fn replay_axon_system(
+    time: Res<Time>,
+    mut replay: ResMut<AxonReplay>,
+    mut srv: ResMut<AxonServer>,
+) {
+    for event in srv.update(time.delta()) {
+        if let AxonServerEvent::Connected { client_id, .. } = event {
+            let mut data = replay.snapshot();
+            data.extend(encode_axon_action(ACTION_TYPE_SYNC, 0, 0, &[]));
+            srv.send(client_id, AXON_CHANNEL_RELIABLE_ORDERED, data);
+        }
+    }
+
+    // 回放模式下丢弃所有客户端消息
+    for client_id in srv.clients_id() {
+        for channel in srv.channels(client_id) {
+            while srv.receive(client_id, channel).is_some() {}
+        }
+    }
+
+    if let Some(target) = replay.seek.take() {
+        let data = replay.rebuild(target);
+        if !data.is_empty() {
+            srv.broadcast(AXON_CHANNEL_RELIABLE_ORDERED, data);
+        }
+    } else if !replay.paused {
+        let target = replay.clock + time.delta_secs_f64() * replay.speed;
+        for data in replay.advance(target) {
+            srv.broadcast(AXON_CHANNEL_RELIABLE_ORDERED, data);
+        }
+    }
+
+    srv.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(time: f64, data: Vec<u8>) -> AxonRecordFrame {
+        AxonRecordFrame {
+            time,
+            kind: AxonRecordKind::Outbound,
+            client_id: 0,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_recording_roundtrip() {
+        let path = crate::test_temp_path("record_test.axrc");
+        let spawn = encode_axon_action(ACTION_TYPE_SPAWN, 7, 1, &[]);
+        {
+            let mut recorder = AxonRecorder::create(&path).unwrap();
+            recorder.record(AxonRecordKind::Outbound, 0, &spawn);
+            recorder.record(AxonRecordKind::Inbound, 3, &[1, 2, 3]);
+            recorder.flush().unwrap();
+        }
+        let recording = AxonRecording::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(recording.frames.len(), 2);
+        assert_eq!(recording.frames[0].kind, AxonRecordKind::Outbound);
+        assert_eq!(recording.frames[0].data, spawn);
+        assert_eq!(recording.frames[1].kind, AxonRecordKind::Inbound);
+        assert_eq!(recording.frames[1].client_id, 3);
+        assert!(recording.frames[1].time >= recording.frames[0].time);
+    }
+
+    #[test]
+    fn test_replay_seek() {
+        let mut replay = AxonReplay::new(AxonRecording {
+            frames: vec![
+                frame(0.0, encode_axon_action(ACTION_TYPE_SPAWN, 1, 1, &[])),
+                frame(1.0, encode_axon_action(ACTION_TYPE_SPAWN, 2, 1, &[])),
+                frame(2.0, encode_axon_action(ACTION_TYPE_DESPAWN, 1, 1, &[])),
+            ],
+        });
+
+        assert_eq!(replay.advance(1.5).len(), 2);
+        assert_eq!(replay.state.ids().count(), 2);
+
+        let data = replay.rebuild(0.5);
+        assert_eq!(replay.state.ids().collect::<Vec<_>>(), vec![1]);
+        let actions = read_axon_actions(&data);
+        assert_eq!(
+            actions
+                .iter()
+                .filter(|v| v.act == ACTION_TYPE_DESPAWN)
+                .count(),
+            2
+        );
+        assert_eq!(actions.last().unwrap().act, ACTION_TYPE_SPAWN);
+
+        replay.rebuild(2.0);
+        assert_eq!(replay.state.ids().collect::<Vec<_>>(), vec![2]);
+        assert!(replay.is_finished());
+    }
+}
@@ -1,47 +1,446 @@
 use crate::core::*;
-use serde_sbin::SbinSerializer;
+use crate::netcode::AxonNetcodeServerTransport;
+use crate::protocol::*;
+use crate::record::*;
+use crate::transport::*;
+use bevy::ecs::entity::Entities;
 use bevy::prelude::*;
-use bevy_renet::netcode::{NetcodeServerTransport, ServerAuthentication, ServerConfig};
-use bevy_renet::renet::{ConnectionConfig, DefaultChannel, ServerEvent};
-use bevy_renet::*;
-use serde::Serializer;
-use std::collections::HashMap;
-use std::net::{SocketAddr, UdpSocket};
-use std::time::SystemTime;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+/// 服务器的所有传输层，客户端 id 在传输层之间唯一
+#[derive(Resource)]
+pub struct AxonServer {
+    transports: Vec<Box<dyn AxonServerTransport>>,
+    routes: HashMap<u64, usize>,
+    /// 连接时声明了 AXON_FLAG_COMPRESSION 的客户端
+    compressed: HashSet<u64>,
+    compression_threshold: usize,
+}
+
+impl Default for AxonServer {
+    fn default() -> Self {
+        Self {
+            transports: Vec::new(),
+            routes: HashMap::new(),
+            compressed: HashSet::new(),
+            compression_threshold: AXON_COMPRESSION_THRESHOLD,
+        }
+    }
+}
+
+impl AxonServer {
+    pub fn add_transport(&mut self, transport: impl AxonServerTransport) {
+        self.transports.push(Box::new(transport));
+    }
+
+    pub fn has_transport(&self) -> bool {
+        !self.transports.is_empty()
+    }
+
+    pub fn clients_id(&self) -> Vec<u64> {
+        self.routes.keys().copied().collect()
+    }
+
+    pub fn is_connected(&self, client_id: u64) -> bool {
+        self.routes.contains_key(&client_id)
+    }
+
+    /// 协商了压缩的客户端中，不小于 threshold 字节的消息会被压缩
+    pub fn set_compression_threshold(&mut self, threshold: usize) {
+        self.compression_threshold = threshold;
+    }
+
+    pub fn is_compressed(&self, client_id: u64) -> bool {
+        self.compressed.contains(&client_id)
+    }
+
+    pub fn send(&mut self, client_id: u64, channel: u8, data: Vec<u8>) {
+        let Some(&i) = self.routes.get(&client_id) else {
+            return;
+        };
+        let data = if self.compressed.contains(&client_id) {
+            axon_encode_message(&data, self.compression_threshold)
+        } else {
+            data
+        };
+        self.transports[i].send(client_id, channel, data);
+    }
+
+    /// 发送给一组客户端，压缩最多只做一次
+    pub fn send_to_clients(&mut self, clients: &[u64], channel: u8, data: Vec<u8>) {
+        let threshold = self.compression_threshold;
+        let mut encoded = None;
+        for client_id in clients {
+            let Some(&i) = self.routes.get(client_id) else {
+                continue;
+            };
+            let data = if self.compressed.contains(client_id) {
+                encoded
+                    .get_or_insert_with(|| axon_encode_message(&data, threshold))
+                    .clone()
+            } else {
+                data.clone()
+            };
+            self.transports[i].send(*client_id, channel, data);
+        }
+    }
+
+    pub fn broadcast(&mut self, channel: u8, data: Vec<u8>) {
+        if self.compressed.is_empty() {
+            for transport in self.transports.iter_mut() {
+                transport.broadcast(channel, data.clone());
+            }
+            return;
+        }
+        let clients = self.clients_id();
+        self.send_to_clients(&clients, channel, data);
+    }
+
+    pub fn disconnect(&mut self, client_id: u64) {
+        if let Some(&i) = self.routes.get(&client_id) {
+            self.transports[i].disconnect(client_id);
+        }
+    }
+
+    pub fn disconnect_all(&mut self) {
+        for transport in self.transports.iter_mut() {
+            transport.disconnect_all();
+        }
+    }
+
+    /// 推进所有传输层，返回本帧的连接事件
+    pub(crate) fn update(&mut self, dt: Duration) -> Vec<AxonServerEvent> {
+        let mut events = Vec::new();
+        for (i, transport) in self.transports.iter_mut().enumerate() {
+            if let Err(e) = transport.update(dt) {
+                warn!("axon transport error: {}", e);
+            }
+            while let Some(event) = transport.next_event() {
+                match &event {
+                    AxonServerEvent::Connected {
+                        client_id,
+                        user_data,
+                    } => {
+                        if self.routes.contains_key(client_id) {
+                            warn!("client id {} already connected, rejected", client_id);
+                            transport.disconnect(*client_id);
+                            continue;
+                        }
+                        self.routes.insert(*client_id, i);
+                        let flags = user_data.as_deref().map(axon_user_flags).unwrap_or(0);
+                        if flags & AXON_FLAG_COMPRESSION != 0 {
+                            self.compressed.insert(*client_id);
+                        }
+                    }
+                    AxonServerEvent::Disconnected { client_id, .. } => {
+                        if self.routes.get(client_id) != Some(&i) {
+                            continue;
+                        }
+                        self.routes.remove(client_id);
+                        self.compressed.remove(client_id);
+                    }
+                }
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// 客户端所在传输层的接收通道
+    pub(crate) fn channels(&self, client_id: u64) -> Vec<u8> {
+        match self.routes.get(&client_id) {
+            Some(&i) => self.transports[i].channels(),
+            None => Vec::new(),
+        }
+    }
+
+    pub(crate) fn receive(&mut self, client_id: u64, channel: u8) -> Option<Vec<u8>> {
+        let &i = self.routes.get(&client_id)?;
+        self.transports[i].receive(client_id, channel)
+    }
+
+    pub(crate) fn flush(&mut self) {
+        for transport in self.transports.iter_mut() {
+            if let Err(e) = transport.flush() {
+                warn!("axon transport error: {}", e);
+            }
+        }
+    }
+}
 
 #[derive(Resource, Default)]
 struct AxonServerClientSet {
     map: HashMap<u64, Entity>,
+    spectators: HashSet<u64>,
 }
 
-#[derive(Default)]
+/// 观战配置
+#[derive(Resource, Default)]
+pub struct AxonSpectatorConfig {
+    /// 观战流相对实时流的延迟，为 0 时与玩家同步
+    pub delay: Duration,
+}
+
+/// 延迟观战流：按时间排队的消息，以及对应时刻的快照
+#[derive(Resource, Default)]
+struct AxonSpectatorStream {
+    queue: VecDeque<(Duration, u64, Vec<u8>)>,
+    snapshot: AxonServerSnapshot,
+}
+
+/// 入场快照分段发送的配置
+#[derive(Resource)]
+pub struct AxonJoinConfig {
+    /// 单条消息的目标大小（字节），一个对象及其变体不会被拆开
+    pub chunk_size: usize,
+    /// 每个客户端每帧最多发送的快照字节数，每帧至少发送一段
+    pub frame_budget: usize,
+}
+
+impl Default for AxonJoinConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 16 * 1024,
+            frame_budget: 48 * 1024,
+        }
+    }
+}
+
+/// 一个客户端尚未发出的入场快照，按取出快照时的顺序分段发送
+struct AxonJoinSnapshot {
+    snapshot: AxonServerSnapshot,
+    resources: VecDeque<u32>,
+    entities: VecDeque<u64>,
+}
+
+impl AxonJoinSnapshot {
+    fn new(snapshot: AxonServerSnapshot) -> Self {
+        Self {
+            resources: snapshot.resources.keys().copied().collect(),
+            entities: snapshot.entities.keys().copied().collect(),
+            snapshot,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.snapshot.is_empty()
+    }
+
+    /// 取出一段快照：资源在前，对象与其变体不拆分，超过 max 字节后停止
+    fn take_chunk(&mut self, max: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        while data.len() < max {
+            let Some(t) = self.resources.pop_front() else {
+                break;
+            };
+            if let Some(resource) = self.snapshot.resources.remove(&t) {
+                axon_write_action(&mut data, ACTION_TYPE_RESOURCE, 0, t, &resource);
+            }
+        }
+        while data.len() < max {
+            let Some(id) = self.entities.pop_front() else {
+                break;
+            };
+            self.take_entity(id, &mut data);
+        }
+        data
+    }
+
+    /// 对象尚未发出时取出它及其变体，已销毁或已发出的 id 被忽略
+    fn take_entity(&mut self, id: u64, out: &mut Vec<u8>) {
+        let Some(entity) = self.snapshot.entities.remove(&id) else {
+            return;
+        };
+        axon_write_action(out, ACTION_TYPE_SPAWN, id, entity.t, &[]);
+        for (t, variant) in entity.m.iter() {
+            axon_write_action(out, ACTION_TYPE_CHANGE, id, *t, variant);
+        }
+    }
+
+    /// 动作涉及未发出的对象或资源时合并进快照
+    fn absorb(&mut self, action: &AxonAction) -> bool {
+        let pending = match action.act {
+            ACTION_TYPE_DESPAWN | ACTION_TYPE_CHANGE | ACTION_TYPE_RETYPE => {
+                self.snapshot.entities.contains_key(&action.id)
+            }
+            ACTION_TYPE_RESOURCE => self.snapshot.resources.contains_key(&action.t),
+            _ => false,
+        };
+        if pending {
+            self.snapshot
+                .apply(action.act, action.id, action.t, &action.v);
+        }
+        pending
+    }
+}
+
+/// 正在接收入场快照的客户端。涉及未发出对象的实时更新直接合并进快照，
+/// 发往未发出对象的事件先带出该对象，其余照常发送
+#[derive(Resource, Default)]
+struct AxonJoinStreams {
+    map: HashMap<u64, AxonJoinSnapshot>,
+}
+
+impl AxonJoinStreams {
+    fn send(&mut self, srv: &mut AxonServer, client_id: u64, data: Vec<u8>) {
+        let Some(pending) = self.map.get_mut(&client_id) else {
+            srv.send(client_id, AXON_CHANNEL_RELIABLE_ORDERED, data);
+            return;
+        };
+        let mut rest = Vec::new();
+        for action in read_axon_actions(&data) {
+            if action.act == ACTION_TYPE_INVOKE && action.id != 0 {
+                pending.take_entity(action.id, &mut rest);
+            } else if pending.absorb(&action) {
+                continue;
+            }
+            axon_write_action(&mut rest, action.act, action.id, action.t, &action.v);
+        }
+        if !rest.is_empty() {
+            srv.send(client_id, AXON_CHANNEL_RELIABLE_ORDERED, rest);
+        }
+    }
+
+    /// 发送给一组客户端，已完成入场的客户端共用一次压缩
+    fn send_many(&mut self, srv: &mut AxonServer, clients: &[u64], data: Vec<u8>) {
+        let (joining, ready): (Vec<u64>, Vec<u64>) =
+            clients.iter().partition(|id| self.map.contains_key(id));
+        for client_id in joining {
+            self.send(srv, client_id, data.clone());
+        }
+        srv.send_to_clients(&ready, AXON_CHANNEL_RELIABLE_ORDERED, data);
+    }
+
+    fn broadcast(&mut self, srv: &mut AxonServer, data: Vec<u8>) {
+        if self.map.is_empty() {
+            srv.broadcast(AXON_CHANNEL_RELIABLE_ORDERED, data);
+            return;
+        }
+        let clients = srv.clients_id();
+        self.send_many(srv, &clients, data);
+    }
+}
+
+/// 发送一条消息，client_id 为 0 时广播；开启观战延迟时观战者的消息进入延迟队列
+#[allow(clippy::too_many_arguments)]
+fn server_send(
+    srv: &mut AxonServer,
+    joins: &mut AxonJoinStreams,
+    client_set: &AxonServerClientSet,
+    stream: &mut AxonSpectatorStream,
+    config: &AxonSpectatorConfig,
+    now: Duration,
+    client_id: u64,
+    data: Vec<u8>,
+) {
+    if config.delay.is_zero() {
+        if client_id == 0 {
+            joins.broadcast(srv, data);
+        } else {
+            joins.send(srv, client_id, data);
+        }
+        return;
+    }
+    if client_id == 0 {
+        let players: Vec<u64> = srv
+            .clients_id()
+            .into_iter()
+            .filter(|id| !client_set.spectators.contains(id))
+            .collect();
+        joins.send_many(srv, &players, data.clone());
+        stream.queue.push_back((now, 0, data));
+    } else if client_set.spectators.contains(&client_id) {
+        stream.queue.push_back((now, client_id, data));
+    } else {
+        joins.send(srv, client_id, data);
+    }
+}
+
+#[derive(Clone, Default)]
 struct AxonServerEntitySnapshot {
     t: u32,
     m: HashMap<u32, Vec<u8>>,
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource, Clone, Default)]
 pub struct AxonServerSnapshot {
     entities: HashMap<u64, AxonServerEntitySnapshot>,
+    resources: HashMap<u32, Vec<u8>>,
 }
 
 impl AxonServerSnapshot {
-    fn snapshot(&mut self) -> Vec<u8> {
-        let mut s = SbinSerializer::new();
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (t, resource) in self.resources.iter() {
+            axon_write_action(&mut data, ACTION_TYPE_RESOURCE, 0, *t, resource);
+        }
         for (id, entity) in self.entities.iter() {
-            s.serialize_u8(ACTION_TYPE_SPAWN).unwrap();
-            s.serialize_u64(*id).unwrap();
-            s.serialize_u32(entity.t).unwrap();
-            s.serialize_bytes(&[]).unwrap();
-
+            axon_write_action(&mut data, ACTION_TYPE_SPAWN, *id, entity.t, &[]);
             for (t, variant) in entity.m.iter() {
-                s.serialize_u8(ACTION_TYPE_CHANGE).unwrap();
-                s.serialize_u64(*id).unwrap();
-                s.serialize_u32(*t).unwrap();
-                s.serialize_bytes(variant).unwrap();
+                axon_write_action(&mut data, ACTION_TYPE_CHANGE, *id, *t, variant);
+            }
+        }
+        data
+    }
+
+    /// 将一条广播动作应用到快照
+    pub fn apply(&mut self, act: u8, id: u64, t: u32, v: &[u8]) {
+        match act {
+            ACTION_TYPE_SPAWN => {
+                self.entities.insert(
+                    id,
+                    AxonServerEntitySnapshot {
+                        t,
+                        m: HashMap::new(),
+                    },
+                );
+            }
+            ACTION_TYPE_DESPAWN => {
+                self.entities.remove(&id);
+            }
+            ACTION_TYPE_CHANGE => {
+                if let Some(m) = self.entities.get_mut(&id) {
+                    m.m.insert(t, v.to_vec());
+                }
+            }
+            ACTION_TYPE_RETYPE => {
+                if let Some(m) = self.entities.get_mut(&id) {
+                    m.t = t;
+                }
             }
+            ACTION_TYPE_RESOURCE => {
+                self.resources.insert(t, v.to_vec());
+            }
+            _ => {}
         }
-        s.into_vec()
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.entities.contains_key(&id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.resources.is_empty()
+    }
+
+    /// 对象的当前类型
+    pub fn object_type(&self, id: u64) -> Option<u32> {
+        self.entities.get(&id).map(|v| v.t)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.entities.keys().copied()
+    }
+
+    /// 资源的最新值
+    pub fn resource(&self, t: u32) -> Option<&[u8]> {
+        self.resources.get(&t).map(|v| v.as_slice())
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+        self.resources.clear();
     }
 }
 
@@ -50,41 +449,74 @@ pub struct AxonServerPlugin;
 
 impl Plugin for AxonServerPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<AxonServer>();
         app.init_resource::<AxonServerClientSet>();
         app.init_resource::<AxonServerSnapshot>();
+        app.init_resource::<AxonSpectatorConfig>();
+        app.init_resource::<AxonSpectatorStream>();
+        app.init_resource::<AxonJoinConfig>();
+        app.init_resource::<AxonJoinStreams>();
         app.add_systems(PreUpdate, server_axon_system);
-        app.add_observer(server_axon_event_system);
+        app.add_systems(
+            PostUpdate,
+            (
+                server_spectator_system,
+                server_join_system,
+                server_flush_system,
+            )
+                .chain()
+                .after(AxonSystemSet::Despawn),
+        );
         app.add_observer(server_axon_action_system);
+        app.add_systems(
+            Last,
+            record_flush_system.run_if(resource_exists::<AxonRecorder>),
+        );
+        app.add_systems(Last, server_exit_system);
     }
 }
 
-const CHANNELS: [u8; 3] = [
-    DefaultChannel::ReliableOrdered as u8,
-    DefaultChannel::ReliableUnordered as u8,
-    DefaultChannel::Unreliable as u8,
-];
-
-fn server_axon_event_system(
-    trigger: On<RenetServerEvent>,
-    mut commands: Commands,
-    mut client_set: ResMut<AxonServerClientSet>,
-    mut snapshot: ResMut<AxonServerSnapshot>,
-    mut srv: ResMut<RenetServer>,
+#[allow(clippy::too_many_arguments)]
+fn server_axon_connect(
+    commands: &mut Commands,
+    client_set: &mut AxonServerClientSet,
+    joins: &mut AxonJoinStreams,
+    snapshot: &AxonServerSnapshot,
+    stream: &AxonSpectatorStream,
+    config: &AxonSpectatorConfig,
+    event: AxonServerEvent,
 ) {
-    let event = trigger.event();
-
-    match event.0 {
-        ServerEvent::ClientConnected { client_id } => {
-            println!("Client {} connected", client_id);
-            let entity = commands.spawn(AxonClient { id: client_id }).id();
+    match event {
+        AxonServerEvent::Connected {
+            client_id,
+            user_data,
+        } => {
+            let flags = user_data.map(|v| axon_user_flags(&v)).unwrap_or(0);
+            let spectator = flags & AXON_FLAG_SPECTATOR != 0;
+            println!(
+                "Client {} connected{}",
+                client_id,
+                if spectator { " (spectator)" } else { "" }
+            );
+            let entity = if spectator {
+                client_set.spectators.insert(client_id);
+                commands.spawn(AxonSpectator { id: client_id }).id()
+            } else {
+                commands.spawn(AxonClient { id: client_id }).id()
+            };
             client_set.map.insert(client_id, entity);
-            let data = snapshot.snapshot();
-            if !data.is_empty() {
-                srv.send_message(client_id, DefaultChannel::ReliableOrdered, data);
-            }
+            // 快照在 server_join_system 中分段发送
+            let pending = if spectator && !config.delay.is_zero() {
+                stream.snapshot.clone()
+            } else {
+                snapshot.clone()
+            };
+            joins.map.insert(client_id, AxonJoinSnapshot::new(pending));
         }
-        ServerEvent::ClientDisconnected { client_id, reason } => {
-            println!("Client {} disconnected: {:?}", client_id, reason);
+        AxonServerEvent::Disconnected { client_id, reason } => {
+            println!("Client {} disconnected: {}", client_id, reason);
+            client_set.spectators.remove(&client_id);
+            joins.map.remove(&client_id);
             if let Some(entity) = client_set.map.remove(&client_id) {
                 commands.entity(entity).despawn();
             }
@@ -92,118 +524,193 @@ fn server_axon_event_system(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn server_axon_system(
-    mut srv: ResMut<RenetServer>,
+    time: Res<Time>,
+    mut srv: ResMut<AxonServer>,
     mut commands: Commands,
     event_set: Res<AxonEventInvokeSet>,
+    mut client_set: ResMut<AxonServerClientSet>,
+    mut joins: ResMut<AxonJoinStreams>,
+    snapshot: Res<AxonServerSnapshot>,
+    stream: Res<AxonSpectatorStream>,
+    config: Res<AxonSpectatorConfig>,
+    mut recorder: Option<ResMut<AxonRecorder>>,
+    entities: &Entities,
 ) {
-    let client_ids = srv.clients_id();
+    for event in srv.update(time.delta()) {
+        server_axon_connect(
+            &mut commands,
+            &mut client_set,
+            &mut joins,
+            &snapshot,
+            &stream,
+            &config,
+            event,
+        );
+    }
 
-    if !client_ids.is_empty() {
-        for client_id in client_ids {
-            for channel in CHANNELS {
-                while let Some(message) = srv.receive_message(client_id, channel) {
-                    event_set.invoke(&message, &mut commands);
+    for client_id in srv.clients_id() {
+        for channel in srv.channels(client_id) {
+            while let Some(message) = srv.receive(client_id, channel) {
+                if let Some(recorder) = recorder.as_mut() {
+                    recorder.record(AxonRecordKind::Inbound, client_id, &message);
+                }
+                if client_set.spectators.contains(&client_id) {
+                    debug!("rejected message from spectator {}", client_id);
+                    continue;
                 }
+                // 目标必须是仍存活的同步对象
+                let resolve = |id| {
+                    Entity::try_from_bits(id)
+                        .filter(|e| snapshot.contains(id) && entities.contains_spawned(*e))
+                };
+                event_set.invoke(&message, client_id, resolve, &mut commands);
             }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn server_axon_action_system(
     event: On<AxonActionEvent>,
-    mut srv: ResMut<RenetServer>,
+    mut srv: ResMut<AxonServer>,
+    mut joins: ResMut<AxonJoinStreams>,
     mut snapshot: ResMut<AxonServerSnapshot>,
+    client_set: Res<AxonServerClientSet>,
+    mut stream: ResMut<AxonSpectatorStream>,
+    config: Res<AxonSpectatorConfig>,
+    time: Res<Time>,
+    mut recorder: Option<ResMut<AxonRecorder>>,
 ) {
     let action = event.event();
     match action.act {
-        ACTION_TYPE_SPAWN => {
-            snapshot.entities.insert(
-                action.id,
-                AxonServerEntitySnapshot {
-                    t: action.t,
-                    m: HashMap::new(),
-                },
-            );
-            let mut s = SbinSerializer::new();
-            s.serialize_u8(ACTION_TYPE_SPAWN).unwrap();
-            s.serialize_u64(action.id).unwrap();
-            s.serialize_u32(action.t).unwrap();
-            s.serialize_bytes(&[]).unwrap();
-            srv.broadcast_message(DefaultChannel::ReliableOrdered, s.into_vec());
-        }
-        ACTION_TYPE_DESPAWN => {
-            snapshot.entities.remove(&action.id);
-            let mut s = SbinSerializer::new();
-            s.serialize_u8(ACTION_TYPE_DESPAWN).unwrap();
-            s.serialize_u64(action.id).unwrap();
-            s.serialize_u32(action.t).unwrap();
-            s.serialize_bytes(&[]).unwrap();
-            srv.broadcast_message(DefaultChannel::ReliableOrdered, s.into_vec());
-        }
-        ACTION_TYPE_CHANGE => {
-            let id = action.id;
-            let t = action.t;
-            let v = &action.v;
-            if let Some(m) = snapshot.entities.get_mut(&id) {
-                m.m.insert(t, v.to_vec());
-            }
-            let mut s = SbinSerializer::new();
-            s.serialize_u8(ACTION_TYPE_CHANGE).unwrap();
-            s.serialize_u64(action.id).unwrap();
-            s.serialize_u32(action.t).unwrap();
-            s.serialize_bytes(v).unwrap();
-            srv.broadcast_message(DefaultChannel::ReliableOrdered, s.into_vec());
-        }
-        ACTION_TYPE_INVOKE => {
-            let v = &action.v;
-            let mut s = SbinSerializer::new();
-            s.serialize_u8(ACTION_TYPE_INVOKE).unwrap();
-            s.serialize_u64(action.id).unwrap();
-            s.serialize_u32(action.t).unwrap();
-            s.serialize_bytes(v).unwrap();
-            if action.client_id == 0 {
-                srv.broadcast_message(DefaultChannel::ReliableOrdered, s.into_vec());
-            } else {
-                srv.send_message(
-                    action.client_id,
-                    DefaultChannel::ReliableOrdered,
-                    s.into_vec(),
-                );
+        ACTION_TYPE_SPAWN | ACTION_TYPE_DESPAWN | ACTION_TYPE_CHANGE | ACTION_TYPE_RETYPE
+        | ACTION_TYPE_RESOURCE => {
+            snapshot.apply(action.act, action.id, action.t, &action.v);
+        }
+        ACTION_TYPE_INVOKE => {}
+        _ => return,
+    }
+    let data = encode_axon_action(action.act, action.id, action.t, &action.v);
+    if let Some(recorder) = recorder.as_mut() {
+        recorder.record(AxonRecordKind::Outbound, action.client_id, &data);
+    }
+    server_send(
+        &mut srv,
+        &mut joins,
+        &client_set,
+        &mut stream,
+        &config,
+        time.elapsed(),
+        action.client_id,
+        data,
+    );
+}
+
+fn server_spectator_system(
+    mut srv: ResMut<AxonServer>,
+    mut joins: ResMut<AxonJoinStreams>,
+    client_set: Res<AxonServerClientSet>,
+    mut stream: ResMut<AxonSpectatorStream>,
+    config: Res<AxonSpectatorConfig>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+    while let Some((at, _, _)) = stream.queue.front() {
+        if now.saturating_sub(*at) < config.delay {
+            break;
+        }
+        let (_, client_id, data) = stream.queue.pop_front().unwrap();
+        if client_id != 0 {
+            joins.send(&mut srv, client_id, data);
+            continue;
+        }
+        for action in read_axon_actions(&data) {
+            stream
+                .snapshot
+                .apply(action.act, action.id, action.t, &action.v);
+        }
+        let spectators: Vec<u64> = client_set.spectators.iter().copied().collect();
+        joins.send_many(&mut srv, &spectators, data);
+    }
+}
+
+/// 按预算分段发送入场快照，发完后发送 SYNC
+fn server_join_system(
+    mut srv: ResMut<AxonServer>,
+    mut joins: ResMut<AxonJoinStreams>,
+    config: Res<AxonJoinConfig>,
+    mut recorder: Option<ResMut<AxonRecorder>>,
+) {
+    let mut done = Vec::new();
+    for (client_id, pending) in joins.map.iter_mut() {
+        let mut budget = config.frame_budget;
+        loop {
+            let mut data = pending.take_chunk(config.chunk_size);
+            if pending.is_empty() {
+                data.extend(encode_axon_action(ACTION_TYPE_SYNC, 0, 0, &[]));
+                done.push(*client_id);
+            }
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.record(AxonRecordKind::Outbound, *client_id, &data);
+            }
+            budget = budget.saturating_sub(data.len());
+            srv.send(*client_id, AXON_CHANNEL_RELIABLE_ORDERED, data);
+            if pending.is_empty() || budget == 0 {
+                break;
             }
         }
-        _ => {}
+    }
+    for client_id in done {
+        joins.map.remove(&client_id);
+    }
+}
+
+fn server_flush_system(mut srv: ResMut<AxonServer>) {
+    srv.flush();
+}
+
+fn server_exit_system(exit: MessageReader<AppExit>, mut srv: ResMut<AxonServer>) {
+    if !exit.is_empty() {
+        srv.disconnect_all();
     }
 }
 
 pub trait AppServerAxon {
+    /// 在 addr 上启动 UDP（renet/netcode）服务器
     fn start_server(&mut self, addr: &str);
+    /// 在 addr 上启动 WebSocket 服务器，可与 start_server 同时使用
+    #[cfg(feature = "websocket")]
+    fn start_websocket_server(&mut self, addr: &str);
+    /// 添加一个传输层，可与其他传输层同时使用
+    fn add_axon_transport(&mut self, transport: impl AxonServerTransport);
+    /// 将同步流录制到文件
+    fn start_recording(&mut self, path: &str);
 }
 
 impl AppServerAxon for App {
     fn start_server(&mut self, addr: &str) {
-        let server = RenetServer::new(ConnectionConfig::default());
-
-        let socket = UdpSocket::bind(addr).expect("Failed to bind UDP socket");
-        socket
-            .set_nonblocking(true)
-            .expect("Failed to set nonblocking");
-
-        let server_addr: SocketAddr = addr.parse().expect("Failed to parse address");
-        let current_time = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .expect("Time went backwards");
-        let server_config = ServerConfig {
-            current_time,
-            max_clients: 64,
-            protocol_id: 0,
-            public_addresses: vec![server_addr],
-            authentication: ServerAuthentication::Unsecure,
-        };
         let transport =
-            NetcodeServerTransport::new(server_config, socket).expect("Failed to create transport");
+            AxonNetcodeServerTransport::bind(addr, 64).expect("Failed to create transport");
+        self.add_axon_transport(transport);
+    }
+
+    #[cfg(feature = "websocket")]
+    fn start_websocket_server(&mut self, addr: &str) {
+        let transport = crate::websocket::AxonWebSocketServerTransport::bind(addr)
+            .expect("Failed to create transport");
+        self.add_axon_transport(transport);
+    }
+
+    fn add_axon_transport(&mut self, transport: impl AxonServerTransport) {
+        self.world_mut()
+            .get_resource_or_init::<AxonServer>()
+            .add_transport(transport);
+    }
 
-        self.insert_resource(server);
-        self.insert_resource(transport);
+    fn start_recording(&mut self, path: &str) {
+        let recorder = AxonRecorder::create(path).expect("Failed to create recording file");
+        self.insert_resource(recorder);
     }
 }
@@ -0,0 +1,77 @@
+//! 传输层抽象：同步逻辑只通过这里的接口收发消息，
+//! renet/netcode 与进程内回环都是其中一种实现
+
+use crate::protocol::AXON_CHANNELS;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AxonTransportError(pub String);
+
+impl fmt::Display for AxonTransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for AxonTransportError {}
+
+/// 服务器传输层产生的连接事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AxonServerEvent {
+    /// user_data 为客户端连接时携带的数据（见 protocol 模块）
+    Connected {
+        client_id: u64,
+        user_data: Option<Vec<u8>>,
+    },
+    Disconnected {
+        client_id: u64,
+        reason: String,
+    },
+}
+
+/// 服务器端传输层
+pub trait AxonServerTransport: Send + Sync + 'static {
+    /// 推进传输层并收取网络数据
+    fn update(&mut self, dt: Duration) -> Result<(), AxonTransportError>;
+    /// 取出下一条连接事件
+    fn next_event(&mut self) -> Option<AxonServerEvent>;
+    /// 当前已连接的客户端
+    fn clients(&self) -> Vec<u64>;
+    /// 接收客户端消息的通道，按读取顺序排列
+    fn channels(&self) -> Vec<u8> {
+        AXON_CHANNELS.to_vec()
+    }
+    fn receive(&mut self, client_id: u64, channel: u8) -> Option<Vec<u8>>;
+    fn send(&mut self, client_id: u64, channel: u8, data: Vec<u8>);
+    fn broadcast(&mut self, channel: u8, data: Vec<u8>) {
+        for client_id in self.clients() {
+            self.send(client_id, channel, data.clone());
+        }
+    }
+    /// 将待发送的消息写入网络
+    fn flush(&mut self) -> Result<(), AxonTransportError>;
+    fn disconnect(&mut self, client_id: u64);
+    fn disconnect_all(&mut self) {
+        for client_id in self.clients() {
+            self.disconnect(client_id);
+        }
+    }
+}
+
+/// 客户端传输层
+pub trait AxonClientTransport: Send + Sync + 'static {
+    /// 推进传输层并收取网络数据
+    fn update(&mut self, dt: Duration) -> Result<(), AxonTransportError>;
+    fn is_connected(&self) -> bool;
+    fn is_disconnected(&self) -> bool;
+    /// 接收服务器消息的通道，按读取顺序排列
+    fn channels(&self) -> Vec<u8> {
+        AXON_CHANNELS.to_vec()
+    }
+    fn receive(&mut self, channel: u8) -> Option<Vec<u8>>;
+    fn send(&mut self, channel: u8, data: Vec<u8>);
+    /// 将待发送的消息写入网络
+    fn flush(&mut self) -> Result<(), AxonTransportError>;
+    fn disconnect(&mut self);
+}
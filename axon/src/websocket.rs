@@ -0,0 +1,437 @@
+//! WebSocket 传输层，供无法使用 UDP 的浏览器与 WebGL 客户端连接。
+//! 每个二进制帧承载一条与 renet 消息相同的 sbin 动作流；
+//! 连接地址可带查询参数 `client_id` 与 `flags`（见 protocol 模块），如 `ws://host:port/?client_id=7&flags=1`。
+//! 不带 client_id 时由服务器分配；自选的 client_id 须经 set_auth 设置的校验函数通过，否则握手被拒绝。
+
+use crate::protocol::*;
+use crate::transport::*;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tungstenite::handshake::client::{ClientHandshake, Request, Response};
+use tungstenite::handshake::server::{self, ErrorResponse, ServerHandshake};
+use tungstenite::handshake::{HandshakeError, MidHandshake};
+use tungstenite::http::StatusCode;
+use tungstenite::{Error, Message, WebSocket};
+
+/// 服务器自动分配的 client id 从这里开始，避免与客户端自选的 id 冲突
+const WEBSOCKET_CLIENT_ID_BASE: u64 = 1 << 63;
+
+/// 默认同时进行中的握手上限，超出的连接直接关闭
+pub const WEBSOCKET_MAX_PENDING: usize = 64;
+
+/// 默认握手期限，未在期限内完成的连接被关闭
+pub const WEBSOCKET_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 校验客户端自选的 client id，参数为 client id 与完整查询串（可携带 token），返回 true 时接受
+pub type AxonWebSocketAuth = Arc<dyn Fn(u64, &str) -> bool + Send + Sync>;
+
+fn is_would_block(e: &Error) -> bool {
+    matches!(e, Error::Io(e) if e.kind() == io::ErrorKind::WouldBlock)
+}
+
+/// 从查询串解析 client_id 与标志位
+fn parse_query(query: &str) -> (Option<u64>, u8) {
+    let mut client_id = None;
+    let mut flags = 0;
+    for pair in query.split('&') {
+        let mut kv = pair.splitn(2, '=');
+        match (kv.next(), kv.next()) {
+            (Some("client_id"), Some(v)) => client_id = v.parse().ok(),
+            (Some("flags"), Some(v)) => flags = v.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    (client_id, flags)
+}
+
+/// 握手时记录请求的查询串，并校验自选的 client id
+#[derive(Clone, Default)]
+struct QueryCallback {
+    query: Arc<Mutex<Option<String>>>,
+    auth: Option<AxonWebSocketAuth>,
+}
+
+impl server::Callback for QueryCallback {
+    fn on_request(
+        self,
+        request: &server::Request,
+        response: server::Response,
+    ) -> Result<server::Response, ErrorResponse> {
+        let query = request.uri().query().unwrap_or("");
+        if let (Some(client_id), _) = parse_query(query) {
+            if !self
+                .auth
+                .as_ref()
+                .is_some_and(|auth| auth(client_id, query))
+            {
+                let mut response = ErrorResponse::new(Some("client_id not allowed".to_string()));
+                *response.status_mut() = StatusCode::FORBIDDEN;
+                return Err(response);
+            }
+        }
+        *self.query.lock().unwrap() = Some(query.to_string());
+        Ok(response)
+    }
+}
+
+type ServerMidHandshake = MidHandshake<ServerHandshake<TcpStream, QueryCallback>>;
+
+struct WebSocketPeer {
+    ws: WebSocket<TcpStream>,
+    inbound: VecDeque<Vec<u8>>,
+    closed: Option<String>,
+}
+
+impl WebSocketPeer {
+    fn new(ws: WebSocket<TcpStream>) -> Self {
+        Self {
+            ws,
+            inbound: VecDeque::new(),
+            closed: None,
+        }
+    }
+
+    /// 读取所有可读的帧，连接断开时记录原因
+    fn read(&mut self) {
+        while self.closed.is_none() {
+            match self.ws.read() {
+                Ok(Message::Binary(data)) => self.inbound.push_back(data.to_vec()),
+                Ok(Message::Close(frame)) => {
+                    self.closed = Some(
+                        frame
+                            .map(|v| v.reason.to_string())
+                            .unwrap_or_else(|| "closed".to_string()),
+                    );
+                }
+                Ok(_) => {}
+                Err(e) if is_would_block(&e) => break,
+                Err(e) => self.closed = Some(e.to_string()),
+            }
+        }
+    }
+
+    fn write(&mut self, data: Vec<u8>) {
+        if self.closed.is_some() {
+            return;
+        }
+        match self.ws.write(Message::Binary(data.into())) {
+            Ok(()) => {}
+            Err(e) if is_would_block(&e) => {}
+            Err(e) => self.closed = Some(e.to_string()),
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.closed.is_some() {
+            return;
+        }
+        match self.ws.flush() {
+            Ok(()) => {}
+            Err(e) if is_would_block(&e) => {}
+            Err(e) => self.closed = Some(e.to_string()),
+        }
+    }
+
+    fn close(&mut self) {
+        let _ = self.ws.close(None);
+        let _ = self.ws.flush();
+    }
+}
+
+/// WebSocket 服务器传输层，所有通道都映射为可靠有序的 WebSocket 帧
+pub struct AxonWebSocketServerTransport {
+    listener: TcpListener,
+    /// 进行中的握手与开始时间
+    pending: Vec<(ServerMidHandshake, QueryCallback, Instant)>,
+    peers: HashMap<u64, WebSocketPeer>,
+    events: VecDeque<AxonServerEvent>,
+    next_id: u64,
+    auth: Option<AxonWebSocketAuth>,
+    max_pending: usize,
+    handshake_timeout: Duration,
+}
+
+impl AxonWebSocketServerTransport {
+    pub fn bind(addr: &str) -> Result<Self, AxonTransportError> {
+        let listener = TcpListener::bind(addr)
+            .map_err(|e| AxonTransportError(format!("failed to bind tcp listener: {:?}", e)))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| AxonTransportError(format!("failed to set nonblocking: {:?}", e)))?;
+        Ok(Self {
+            listener,
+            pending: Vec::new(),
+            peers: HashMap::new(),
+            events: VecDeque::new(),
+            next_id: WEBSOCKET_CLIENT_ID_BASE,
+            auth: None,
+            max_pending: WEBSOCKET_MAX_PENDING,
+            handshake_timeout: WEBSOCKET_HANDSHAKE_TIMEOUT,
+        })
+    }
+
+    /// 设置自选 client id 的校验函数，未设置时只接受服务器分配的 id
+    pub fn set_auth(&mut self, auth: impl Fn(u64, &str) -> bool + Send + Sync + 'static) {
+        self.auth = Some(Arc::new(auth));
+    }
+
+    /// 设置同时进行中的握手上限与握手期限
+    pub fn set_handshake_limits(&mut self, max_pending: usize, timeout: Duration) {
+        self.max_pending = max_pending;
+        self.handshake_timeout = timeout;
+    }
+
+    /// 进行中的握手数量
+    pub fn pending_handshakes(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    fn handshake(
+        &mut self,
+        result: Result<
+            WebSocket<TcpStream>,
+            HandshakeError<ServerHandshake<TcpStream, QueryCallback>>,
+        >,
+        callback: QueryCallback,
+        started: Instant,
+    ) {
+        match result {
+            Ok(ws) => {
+                let query = callback.query.lock().unwrap().take().unwrap_or_default();
+                self.connected(ws, &query);
+            }
+            Err(HandshakeError::Interrupted(mid)) => self.pending.push((mid, callback, started)),
+            Err(HandshakeError::Failure(_)) => {}
+        }
+    }
+
+    fn connected(&mut self, ws: WebSocket<TcpStream>, query: &str) {
+        let (client_id, flags) = parse_query(query);
+        let client_id = client_id.unwrap_or_else(|| {
+            self.next_id += 1;
+            self.next_id
+        });
+        let mut peer = WebSocketPeer::new(ws);
+        if self.peers.contains_key(&client_id) {
+            peer.close();
+            return;
+        }
+        self.peers.insert(client_id, peer);
+        self.events.push_back(AxonServerEvent::Connected {
+            client_id,
+            user_data: Some(axon_user_data(flags).to_vec()),
+        });
+    }
+}
+
+impl AxonServerTransport for AxonWebSocketServerTransport {
+    fn update(&mut self, _dt: Duration) -> Result<(), AxonTransportError> {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // 未完成的握手过多时直接丢弃新连接
+                    if self.pending.len() >= self.max_pending
+                        || stream.set_nonblocking(true).is_err()
+                    {
+                        continue;
+                    }
+                    let _ = stream.set_nodelay(true);
+                    let callback = QueryCallback {
+                        query: Arc::default(),
+                        auth: self.auth.clone(),
+                    };
+                    let result = tungstenite::accept_hdr(stream, callback.clone());
+                    self.handshake(result, callback, Instant::now());
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(AxonTransportError(format!("accept error: {:?}", e))),
+            }
+        }
+
+        for (mid, callback, started) in std::mem::take(&mut self.pending) {
+            // 超时的握手随 mid 一起丢弃，连接被关闭
+            if started.elapsed() >= self.handshake_timeout {
+                continue;
+            }
+            self.handshake(mid.handshake(), callback, started);
+        }
+
+        let mut closed = Vec::new();
+        for (client_id, peer) in self.peers.iter_mut() {
+            peer.read();
+            if let Some(reason) = peer.closed.take() {
+                closed.push((*client_id, reason));
+            }
+        }
+        for (client_id, reason) in closed {
+            self.peers.remove(&client_id);
+            self.events
+                .push_back(AxonServerEvent::Disconnected { client_id, reason });
+        }
+        Ok(())
+    }
+
+    fn next_event(&mut self) -> Option<AxonServerEvent> {
+        self.events.pop_front()
+    }
+
+    fn clients(&self) -> Vec<u64> {
+        self.peers.keys().copied().collect()
+    }
+
+    fn receive(&mut self, client_id: u64, channel: u8) -> Option<Vec<u8>> {
+        if channel != AXON_CHANNEL_RELIABLE_ORDERED {
+            return None;
+        }
+        self.peers.get_mut(&client_id)?.inbound.pop_front()
+    }
+
+    fn send(&mut self, client_id: u64, _channel: u8, data: Vec<u8>) {
+        if let Some(peer) = self.peers.get_mut(&client_id) {
+            peer.write(data);
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), AxonTransportError> {
+        for peer in self.peers.values_mut() {
+            peer.flush();
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self, client_id: u64) {
+        if let Some(mut peer) = self.peers.remove(&client_id) {
+            peer.close();
+            self.events.push_back(AxonServerEvent::Disconnected {
+                client_id,
+                reason: "disconnected by server".to_string(),
+            });
+        }
+    }
+}
+
+type ClientMidHandshake = MidHandshake<ClientHandshake<TcpStream>>;
+
+enum WebSocketClientState {
+    Connecting(ClientMidHandshake),
+    Connected(WebSocketPeer),
+    Disconnected(String),
+}
+
+/// WebSocket 客户端传输层，主要用于测试与 Rust 工具
+pub struct AxonWebSocketClientTransport {
+    state: WebSocketClientState,
+}
+
+impl AxonWebSocketClientTransport {
+    /// url 形如 `ws://127.0.0.1:7778/?client_id=7`
+    pub fn connect(url: &str) -> Result<Self, AxonTransportError> {
+        let request: Request = tungstenite::client::IntoClientRequest::into_client_request(url)
+            .map_err(|e| AxonTransportError(format!("invalid url '{}': {}", url, e)))?;
+        let host = request.uri().host().unwrap_or("127.0.0.1").to_string();
+        let port = request.uri().port_u16().unwrap_or(80);
+        let stream = TcpStream::connect((host.as_str(), port))
+            .map_err(|e| AxonTransportError(format!("failed to connect '{}': {:?}", url, e)))?;
+        stream
+            .set_nonblocking(true)
+            .map_err(|e| AxonTransportError(format!("failed to set nonblocking: {:?}", e)))?;
+        let _ = stream.set_nodelay(true);
+        let mut transport = Self {
+            state: WebSocketClientState::Disconnected(String::new()),
+        };
+        transport.handshake(tungstenite::client(request, stream));
+        Ok(transport)
+    }
+
+    /// 断开原因
+    pub fn disconnect_reason(&self) -> Option<&str> {
+        match &self.state {
+            WebSocketClientState::Disconnected(reason) => Some(reason),
+            _ => None,
+        }
+    }
+
+    fn handshake(
+        &mut self,
+        result: Result<
+            (WebSocket<TcpStream>, Response),
+            HandshakeError<ClientHandshake<TcpStream>>,
+        >,
+    ) {
+        self.state = match result {
+            Ok((ws, _)) => WebSocketClientState::Connected(WebSocketPeer::new(ws)),
+            Err(HandshakeError::Interrupted(mid)) => WebSocketClientState::Connecting(mid),
+            Err(HandshakeError::Failure(e)) => WebSocketClientState::Disconnected(e.to_string()),
+        };
+    }
+}
+
+impl AxonClientTransport for AxonWebSocketClientTransport {
+    fn update(&mut self, _dt: Duration) -> Result<(), AxonTransportError> {
+        let state = std::mem::replace(
+            &mut self.state,
+            WebSocketClientState::Disconnected(String::new()),
+        );
+        match state {
+            WebSocketClientState::Connecting(mid) => self.handshake(mid.handshake()),
+            WebSocketClientState::Connected(mut peer) => {
+                peer.read();
+                self.state = match peer.closed.take() {
+                    Some(reason) => WebSocketClientState::Disconnected(reason),
+                    None => WebSocketClientState::Connected(peer),
+                };
+            }
+            WebSocketClientState::Disconnected(reason) => {
+                self.state = WebSocketClientState::Disconnected(reason);
+            }
+        }
+        match &self.state {
+            WebSocketClientState::Disconnected(reason) => Err(AxonTransportError(reason.clone())),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        matches!(self.state, WebSocketClientState::Connected(_))
+    }
+
+    fn is_disconnected(&self) -> bool {
+        matches!(self.state, WebSocketClientState::Disconnected(_))
+    }
+
+    fn receive(&mut self, channel: u8) -> Option<Vec<u8>> {
+        match &mut self.state {
+            WebSocketClientState::Connected(peer) if channel == AXON_CHANNEL_RELIABLE_ORDERED => {
+                peer.inbound.pop_front()
+            }
+            _ => None,
+        }
+    }
+
+    fn send(&mut self, _channel: u8, data: Vec<u8>) {
+        if let WebSocketClientState::Connected(peer) = &mut self.state {
+            peer.write(data);
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), AxonTransportError> {
+        if let WebSocketClientState::Connected(peer) = &mut self.state {
+            peer.flush();
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        if let WebSocketClientState::Connected(peer) = &mut self.state {
+            peer.close();
+        }
+        self.state = WebSocketClientState::Disconnected("disconnected by client".to_string());
+    }
+}
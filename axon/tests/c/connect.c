@@ -0,0 +1,101 @@
+/* 连接到 argv[1] 的服务器，收到 SYNC 后对快照中类型为 1 的对象发送一条 INVOKE，等待服务器断开连接 */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+
+#include "bevy_axon.h"
+
+static void sleep_ms(long ms) {
+    struct timespec ts = {0, ms * 1000000L};
+    nanosleep(&ts, NULL);
+}
+
+/* 按 sbin 格式编码 INVOKE 动作，返回长度 */
+static size_t encode_invoke(uint8_t *buf, uint64_t id, uint32_t type) {
+    size_t n = 0;
+    buf[n++] = 1;
+    buf[n++] = ACTION_TYPE_INVOKE;
+    buf[n++] = 4;
+    memcpy(buf + n, &id, 8);
+    n += 8;
+    buf[n++] = 3;
+    memcpy(buf + n, &type, 4);
+    n += 4;
+    buf[n++] = 13;
+    memset(buf + n, 0, 4);
+    n += 4;
+    return n;
+}
+
+int main(int argc, char **argv) {
+    if (argc < 3) {
+        fprintf(stderr, "usage: %s <addr> <client_id>\n", argv[0]);
+        return 2;
+    }
+    Game *game = NULL;
+    unsigned long long client_id = strtoull(argv[2], NULL, 10);
+    if (bevy_axon_ffi_create(argv[1], client_id, &game) != AXON_FFI_ERROR_OK) {
+        fprintf(stderr, "create: %s\n", bevy_axon_ffi_errmsg(NULL));
+        return 1;
+    }
+
+    uint64_t object = 0;
+    int synced = 0;
+    AxonFfiError err = AXON_FFI_ERROR_OK;
+    for (int i = 0; i < 2000 && !synced; i++) {
+        if (bevy_axon_ffi_update(game, 0.016f, NULL, NULL) != AXON_FFI_ERROR_OK) {
+            fprintf(stderr, "update: %s\n", bevy_axon_ffi_errmsg(game));
+            break;
+        }
+        if (bevy_axon_ffi_state(game) == AXON_FFI_STATE_DISCONNECTED) {
+            fprintf(stderr, "disconnected: %d\n", bevy_axon_ffi_disconnect_reason(game));
+            break;
+        }
+        uint8_t act;
+        uint64_t id;
+        uint32_t type;
+        const uint8_t *payload;
+        size_t len;
+        while ((err = bevy_axon_ffi_next_action(game, &act, &id, &type, &payload, &len)) ==
+               AXON_FFI_ERROR_OK) {
+            if (act == ACTION_TYPE_SPAWN && type == 1) {
+                object = id;
+            } else if (act == ACTION_TYPE_SYNC) {
+                synced = 1;
+            }
+        }
+        if (err != AXON_FFI_ERROR_END) {
+            fprintf(stderr, "next_action: %s\n", bevy_axon_ffi_errmsg(game));
+            break;
+        }
+        sleep_ms(1);
+    }
+    if (object == 0 || !synced) {
+        fprintf(stderr, "object=%llu synced=%d\n", (unsigned long long)object, synced);
+        bevy_axon_ffi_exit(game);
+        return 1;
+    }
+
+    uint8_t buf[32];
+    size_t n = encode_invoke(buf, object, 9);
+    if (bevy_axon_ffi_invoke(game, buf, n) != AXON_FFI_ERROR_OK) {
+        fprintf(stderr, "invoke: %s\n", bevy_axon_ffi_errmsg(game));
+        bevy_axon_ffi_exit(game);
+        return 1;
+    }
+    for (int i = 0; i < 2000; i++) {
+        bevy_axon_ffi_update(game, 0.016f, NULL, NULL);
+        if (bevy_axon_ffi_state(game) == AXON_FFI_STATE_DISCONNECTED) {
+            break;
+        }
+        sleep_ms(1);
+    }
+    AxonDisconnectReason reason = bevy_axon_ffi_disconnect_reason(game);
+    bevy_axon_ffi_exit(game);
+    if (reason != AXON_DISCONNECT_REASON_DISCONNECTED_BY_SERVER) {
+        fprintf(stderr, "reason: %d\n", reason);
+        return 1;
+    }
+    return 0;
+}
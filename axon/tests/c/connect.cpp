@@ -0,0 +1,71 @@
+// 与 connect.c 相同的流程，使用 bevy_axon.hpp
+#include <chrono>
+#include <cstdio>
+#include <cstdlib>
+#include <cstring>
+#include <thread>
+
+#include "bevy_axon.hpp"
+
+int main(int argc, char **argv) {
+    if (argc < 3) {
+        std::fprintf(stderr, "usage: %s <addr> <client_id>\n", argv[0]);
+        return 2;
+    }
+    bevy_axon::Client client;
+    if (client.connect(argv[1], std::strtoull(argv[2], nullptr, 10)) != AXON_FFI_ERROR_OK) {
+        std::fprintf(stderr, "create: %s\n", client.error());
+        return 1;
+    }
+
+    uint64_t object = 0;
+    bool synced = false, mirrored = false;
+    for (int i = 0; i < 2000 && !synced; i++) {
+        if (client.update(0.016f) != AXON_FFI_ERROR_OK) {
+            std::fprintf(stderr, "update: %s\n", client.error());
+            return 1;
+        }
+        client.for_each_change([&](const AxonChange &change) {
+            if (change.kind == AXON_CHANGE_KIND_SPAWN && change.t == 1) {
+                mirrored = true;
+            }
+        });
+        AxonFfiError err = client.for_each_action([&](const bevy_axon::Action &action) {
+            if (action.act == ACTION_TYPE_SPAWN && action.type == 1) {
+                object = action.id;
+            } else if (action.act == ACTION_TYPE_SYNC) {
+                synced = true;
+            }
+        });
+        if (err != AXON_FFI_ERROR_OK) {
+            std::fprintf(stderr, "next_action: %s\n", client.error());
+            return 1;
+        }
+        std::this_thread::sleep_for(std::chrono::milliseconds(1));
+    }
+    uint32_t type = 0;
+    if (object == 0 || !synced || !mirrored ||
+        client.object_type(object, type) != AXON_FFI_ERROR_OK || type != 1) {
+        std::fprintf(stderr, "object=%llu synced=%d mirrored=%d type=%u\n",
+                     static_cast<unsigned long long>(object), synced, mirrored, type);
+        return 1;
+    }
+
+    // act=INVOKE, id=object, type=9, 空数据
+    uint8_t invoke[] = {1, ACTION_TYPE_INVOKE, 4, 0, 0, 0, 0, 0, 0, 0, 0,
+                        3, 9, 0, 0, 0, 13, 0, 0, 0, 0};
+    std::memcpy(invoke + 3, &object, sizeof(object));
+    if (client.invoke(invoke, sizeof(invoke)) != AXON_FFI_ERROR_OK) {
+        std::fprintf(stderr, "invoke: %s\n", client.error());
+        return 1;
+    }
+    for (int i = 0; i < 2000 && client.state() != AXON_FFI_STATE_DISCONNECTED; i++) {
+        client.update(0.016f);
+        std::this_thread::sleep_for(std::chrono::milliseconds(1));
+    }
+    if (client.disconnect_reason() != AXON_DISCONNECT_REASON_DISCONNECTED_BY_SERVER) {
+        std::fprintf(stderr, "reason: %d\n", client.disconnect_reason());
+        return 1;
+    }
+    return 0;
+}
@@ -0,0 +1,155 @@
+#![cfg(all(feature = "ffi", feature = "server", target_os = "linux"))]
+
+//! 用 C/C++ 编译器对生成的头文件与 cdylib 编译测试程序，连接到本进程中运行 AxonServerPlugin 的服务器
+
+use bevy::prelude::*;
+use bevy_axon::core::*;
+use bevy_axon::loopback::*;
+use bevy_axon::netcode::*;
+use bevy_axon::server::*;
+use std::net::UdpSocket;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+const DT: Duration = Duration::from_millis(16);
+
+#[derive(Component, Default)]
+struct Player;
+
+impl AxonObject for Player {
+    fn axon_object_type() -> u32 {
+        1
+    }
+}
+
+/// 客户端对对象发送的事件，数据为空
+#[derive(Event, Clone)]
+struct Poke;
+
+impl AxonEvent for Poke {
+    fn axon_event_type() -> u32 {
+        9
+    }
+    fn axon_event_invoke(_: &[u8], commands: &mut Commands<'_, '_>) {
+        commands.trigger(Poke);
+    }
+    fn axon_event_invoke_entity(
+        _: &[u8],
+        entity: Entity,
+        client_id: u64,
+        commands: &mut Commands<'_, '_>,
+    ) {
+        commands.trigger(AxonEntityEvent {
+            entity,
+            client_id,
+            event: Poke,
+        });
+    }
+}
+
+#[derive(Resource, Default)]
+struct Poked(Vec<(Entity, u64)>);
+
+/// 收到 Poke 后断开发送者
+fn poke_disconnect(
+    event: On<AxonEntityEvent<Poke>>,
+    mut poked: ResMut<Poked>,
+    mut server: ResMut<AxonServer>,
+) {
+    poked.0.push((event.entity, event.client_id));
+    server.disconnect(event.client_id);
+}
+
+/// 测试可执行文件与 libbevy_axon.so 同在 target/<profile>/deps
+fn lib_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    for dir in [deps, deps.parent().unwrap()] {
+        if dir.join("libbevy_axon.so").exists() {
+            return dir.to_path_buf();
+        }
+    }
+    panic!("libbevy_axon.so not found next to {:?}", exe);
+}
+
+fn compile(compiler: &str, source: &str, name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib = lib_dir();
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let status = Command::new(compiler)
+        .arg(dir.join("tests/c").join(source))
+        .arg("-I")
+        .arg(dir.join("include"))
+        .arg("-L")
+        .arg(&lib)
+        .arg(format!("-Wl,-rpath,{}", lib.display()))
+        .arg("-lbevy_axon")
+        .args(["-Wall", "-Werror", "-o"])
+        .arg(&out)
+        .status()
+        .unwrap_or_else(|e| panic!("failed to run {}: {}", compiler, e));
+    assert!(status.success(), "failed to compile {}", source);
+    out
+}
+
+/// 运行客户端程序：从快照中取得 Player 的 id，收到 SYNC 后对它发送 Poke，服务器随后断开客户端
+fn run(program: &Path, client_id: u64) {
+    let addr = {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.local_addr().unwrap().to_string()
+    };
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_plugins((AxonPlugin, AxonServerPlugin));
+    app.add_axon_object::<Player>();
+    app.add_axon_event::<Poke>();
+    app.init_resource::<Poked>();
+    app.add_observer(poke_disconnect);
+    app.add_axon_transport(AxonNetcodeServerTransport::bind(&addr, 4).unwrap());
+    let entity = app.world_mut().spawn(Player).id();
+
+    // cargo test 的 LD_LIBRARY_PATH 优先于 rpath，其中的 target/<profile> 可能是未启用 ffi 的旧库
+    let mut child = Command::new(program)
+        .env("LD_LIBRARY_PATH", lib_dir())
+        .arg(&addr)
+        .arg(client_id.to_string())
+        .spawn()
+        .unwrap();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        app.step(DT);
+        std::thread::sleep(Duration::from_millis(1));
+    };
+    assert!(status.success(), "{:?} exited with {}", program, status);
+    assert_eq!(app.world().resource::<Poked>().0, vec![(entity, client_id)]);
+}
+
+#[test]
+fn test_header_up_to_date() {
+    let generated = std::fs::read_to_string(concat!(env!("OUT_DIR"), "/bevy_axon.h"))
+        .expect("Failed to read generated bevy_axon.h");
+    let committed =
+        std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/include/bevy_axon.h"))
+            .expect("Failed to read include/bevy_axon.h");
+    assert!(
+        generated == committed,
+        "include/bevy_axon.h is stale, run: cargo run --features ffi --bin gen-header"
+    );
+}
+
+#[test]
+fn test_c_client() {
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let program = compile(&compiler, "connect.c", "axon_connect_c");
+    run(&program, 11);
+}
+
+#[test]
+fn test_cpp_client() {
+    let compiler = std::env::var("CXX").unwrap_or_else(|_| "c++".to_string());
+    let program = compile(&compiler, "connect.cpp", "axon_connect_cpp");
+    run(&program, 12);
+}
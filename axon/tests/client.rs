@@ -0,0 +1,397 @@
+#![cfg(all(feature = "server", feature = "client"))]
+
+use bevy::prelude::*;
+use bevy_axon::client::*;
+use bevy_axon::core::*;
+use bevy_axon::loopback::*;
+use bevy_axon::netcode::*;
+use bevy_axon::protocol::*;
+use bevy_axon::server::*;
+use bevy_axon::transport::*;
+use renet::{ChannelConfig, ConnectionConfig, SendType};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const DT: Duration = Duration::from_millis(16);
+
+#[derive(Component, Default)]
+struct Player;
+
+impl AxonObject for Player {
+    fn axon_object_type() -> u32 {
+        1
+    }
+}
+
+#[derive(Component, Default)]
+struct Corpse;
+
+impl AxonObject for Corpse {
+    fn axon_object_type() -> u32 {
+        7
+    }
+}
+
+#[derive(Component, Serialize, Deserialize, Debug, PartialEq)]
+struct Position {
+    x: f32,
+    y: f32,
+}
+
+impl AxonVariant for Position {
+    fn axon_variant_type() -> u32 {
+        2
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Debug, PartialEq)]
+struct Phase(u32);
+
+impl AxonResource for Phase {
+    fn axon_resource_type() -> u32 {
+        4
+    }
+}
+
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Ping {
+    n: u32,
+}
+
+impl AxonEvent for Ping {
+    fn axon_event_type() -> u32 {
+        3
+    }
+    fn axon_event_invoke(bytes: &[u8], commands: &mut Commands<'_, '_>) {
+        match serde_sbin::from_bytes::<Ping>(bytes) {
+            Ok(event) => commands.trigger(event),
+            Err(e) => warn!("axon event decode error: {}", e),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct Received(Vec<Ping>);
+
+#[derive(Resource, Default)]
+struct Outbox(Vec<Ping>);
+
+#[derive(Resource, Default)]
+struct Synced(usize);
+
+fn record_ping(event: On<Ping>, mut received: ResMut<Received>) {
+    received.0.push(event.event().clone());
+}
+
+fn send_pings(mut outbox: ResMut<Outbox>, mut sender: AxonClientSender) {
+    for ping in outbox.0.drain(..) {
+        sender.send(0, &ping).unwrap();
+    }
+}
+
+fn server() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_plugins((AxonPlugin, AxonServerPlugin));
+    app.add_axon_object::<Player>();
+    app.add_axon_object::<Corpse>();
+    app.add_axon_variant::<Position>();
+    app.add_axon_event::<Ping>();
+    app.add_axon_resource::<Phase>();
+    app.init_resource::<Received>();
+    app.add_observer(record_ping);
+    app.start_loopback_server();
+    app
+}
+
+fn client() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_plugins((AxonPlugin, AxonClientPlugin));
+    app.add_axon_client_object::<Player>();
+    app.add_axon_client_object::<Corpse>();
+    app.add_axon_client_variant::<Position>();
+    app.add_axon_client_resource::<Phase>();
+    app.add_axon_event::<Ping>();
+    app.init_resource::<Received>();
+    app.init_resource::<Outbox>();
+    app.init_resource::<Synced>();
+    app.add_observer(record_ping);
+    app.add_observer(|_: On<AxonSyncEvent>, mut synced: ResMut<Synced>| synced.0 += 1);
+    app.add_systems(Update, send_pings);
+    app
+}
+
+fn step(server: &mut App, client: &mut App) {
+    server.step(DT);
+    client.step(DT);
+}
+
+#[test]
+fn test_client_mirror() {
+    let mut server = server();
+    let mut client = client();
+    server.connect_loopback_app(1, &mut client);
+
+    let entity = server
+        .world_mut()
+        .spawn((Player, Position { x: 1.0, y: 2.0 }))
+        .id();
+    let id = entity.to_bits();
+    step(&mut server, &mut client);
+
+    let local = client
+        .world()
+        .resource::<AxonClientEntities>()
+        .get(id)
+        .unwrap();
+    let replica = client.world().get::<AxonReplica>(local).unwrap();
+    assert_eq!((replica.id, replica.t), (id, 1));
+    assert_eq!(client.world().resource::<Synced>().0, 1);
+    assert!(client.world().get::<Player>(local).is_some());
+    assert_eq!(
+        client.world().get::<Position>(local),
+        Some(&Position { x: 1.0, y: 2.0 })
+    );
+
+    server.world_mut().get_mut::<Position>(entity).unwrap().y = 9.0;
+    step(&mut server, &mut client);
+    assert_eq!(client.world().get::<Position>(local).unwrap().y, 9.0);
+
+    server.world_mut().despawn(entity);
+    step(&mut server, &mut client);
+    assert!(client.world().get_entity(local).is_err());
+    assert!(client.world().resource::<AxonClientEntities>().is_empty());
+}
+
+#[test]
+fn test_client_retype() {
+    let mut server = server();
+    let mut client = client();
+    server.connect_loopback_app(1, &mut client);
+
+    let entity = server
+        .world_mut()
+        .spawn((Player, Position { x: 1.0, y: 2.0 }))
+        .id();
+    let id = entity.to_bits();
+    step(&mut server, &mut client);
+    let local = client
+        .world()
+        .resource::<AxonClientEntities>()
+        .get(id)
+        .unwrap();
+
+    server
+        .world_mut()
+        .entity_mut(entity)
+        .remove::<Player>()
+        .insert(Corpse);
+    step(&mut server, &mut client);
+    let world = client.world();
+    assert_eq!(world.get::<AxonReplica>(local).unwrap().t, 7);
+    assert!(world.get::<Player>(local).is_none());
+    assert!(world.get::<Corpse>(local).is_some());
+    assert!(world.get::<Position>(local).is_some());
+
+    server.world_mut().entity_mut(entity).remove::<Corpse>();
+    step(&mut server, &mut client);
+    assert!(client.world().get_entity(local).is_err());
+    assert!(server.world().get_entity(entity).is_ok());
+}
+
+#[test]
+fn test_client_events() {
+    let mut server = server();
+    let mut client = client();
+    server.connect_loopback_app(1, &mut client);
+    step(&mut server, &mut client);
+
+    client
+        .world_mut()
+        .resource_mut::<Outbox>()
+        .0
+        .push(Ping { n: 1 });
+    step(&mut server, &mut client);
+    step(&mut server, &mut client);
+    assert_eq!(server.world().resource::<Received>().0, vec![Ping { n: 1 }]);
+
+    server
+        .broadcast_axon_client_event(0, &Ping { n: 2 })
+        .unwrap();
+    step(&mut server, &mut client);
+    step(&mut server, &mut client);
+    assert_eq!(client.world().resource::<Received>().0, vec![Ping { n: 2 }]);
+}
+
+#[test]
+fn test_client_plugin_alone() {
+    let mut server = server();
+    let mut client = App::new();
+    client.add_plugins((MinimalPlugins, AxonClientPlugin));
+    client.add_axon_client_object::<Player>();
+    client.add_axon_event::<Ping>();
+    client.init_resource::<Received>();
+    client.add_observer(record_ping);
+    server.connect_loopback_app(1, &mut client);
+
+    let entity = server.world_mut().spawn(Player).id();
+    server
+        .broadcast_axon_client_event(0, &Ping { n: 3 })
+        .unwrap();
+    step(&mut server, &mut client);
+    step(&mut server, &mut client);
+    let entities = client.world().resource::<AxonClientEntities>();
+    assert!(entities.get(entity.to_bits()).is_some());
+    assert_eq!(client.world().resource::<Received>().0, vec![Ping { n: 3 }]);
+}
+
+#[test]
+fn test_client_ipv6() {
+    let addr = {
+        let socket = std::net::UdpSocket::bind("[::1]:0").unwrap();
+        socket.local_addr().unwrap().to_string()
+    };
+    let mut server = server();
+    server.add_axon_transport(AxonNetcodeServerTransport::bind(&addr, 4).unwrap());
+    let mut client = client();
+    client.connect_axon_server(&addr, 5);
+    for _ in 0..200 {
+        step(&mut server, &mut client);
+        let connected = client
+            .world()
+            .resource::<AxonClientConnection>()
+            .is_connected();
+        if connected && client.world().resource::<Synced>().0 > 0 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(client
+        .world()
+        .resource::<AxonClientConnection>()
+        .is_connected());
+    assert_eq!(client.world().resource::<Synced>().0, 1);
+}
+
+#[test]
+fn test_client_resource() {
+    let mut server = server();
+    server.insert_resource(Phase(1));
+    server.step(DT);
+
+    let mut client = client();
+    server.connect_loopback_app(1, &mut client);
+    step(&mut server, &mut client);
+    assert_eq!(client.world().get_resource::<Phase>(), Some(&Phase(1)));
+
+    server.insert_resource(Phase(2));
+    step(&mut server, &mut client);
+    assert_eq!(client.world().get_resource::<Phase>(), Some(&Phase(2)));
+}
+
+/// 只有可靠有序通道 2 的配置
+fn reliable_only() -> ConnectionConfig {
+    let channels = vec![ChannelConfig {
+        channel_id: AXON_CHANNEL_RELIABLE_ORDERED,
+        max_memory_usage_bytes: 5 * 1024 * 1024,
+        send_type: SendType::ReliableOrdered {
+            resend_time: Duration::from_millis(300),
+        },
+    }];
+    ConnectionConfig {
+        server_channels_config: channels.clone(),
+        client_channels_config: channels,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_netcode_reduced_channels() {
+    let addr = {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.local_addr().unwrap().to_string()
+    };
+    // 缺少通道 2 的配置被拒绝
+    let mut config = reliable_only();
+    config.client_channels_config[0].send_type = SendType::Unreliable;
+    assert!(AxonNetcodeServerTransport::bind_with(&addr, 4, 0, config).is_err());
+
+    // 只配置了通道 2 时，插件只读取该通道
+    let mut server = server();
+    server.add_axon_transport(
+        AxonNetcodeServerTransport::bind_with(&addr, 4, 0, reliable_only()).unwrap(),
+    );
+    let entity = server.world_mut().spawn(Player).id();
+    let mut client = client();
+    let transport = AxonNetcodeClientTransport::connect_with(
+        "127.0.0.1:0",
+        &addr,
+        5,
+        Some(axon_user_data(0)),
+        0,
+        reliable_only(),
+    )
+    .unwrap();
+    assert_eq!(transport.channels(), vec![AXON_CHANNEL_RELIABLE_ORDERED]);
+    client.insert_resource(AxonClientConnection::new(transport));
+    for _ in 0..200 {
+        step(&mut server, &mut client);
+        if client.world().resource::<Synced>().0 > 0 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    let entities = client.world().resource::<AxonClientEntities>();
+    assert!(entities.get(entity.to_bits()).is_some());
+
+    client
+        .world_mut()
+        .resource_mut::<Outbox>()
+        .0
+        .push(Ping { n: 4 });
+    for _ in 0..200 {
+        step(&mut server, &mut client);
+        if !server.world().resource::<Received>().0.is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(server.world().resource::<Received>().0, vec![Ping { n: 4 }]);
+}
+
+#[test]
+fn test_netcode_protocol_id() {
+    let addr = {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.local_addr().unwrap().to_string()
+    };
+    let mut server =
+        AxonNetcodeServerTransport::bind_with(&addr, 4, 42, ConnectionConfig::default()).unwrap();
+    // 协议 id 不一致的客户端无法连接
+    let mut other = AxonNetcodeClientTransport::connect("127.0.0.1:0", &addr, 1, None).unwrap();
+    let mut client = AxonNetcodeClientTransport::connect_with(
+        "127.0.0.1:0",
+        &addr,
+        2,
+        None,
+        42,
+        ConnectionConfig::default(),
+    )
+    .unwrap();
+    for _ in 0..200 {
+        server.update(DT).unwrap();
+        server.flush().unwrap();
+        for transport in [&mut other, &mut client] {
+            transport.update(DT).unwrap();
+            transport.flush().unwrap();
+        }
+        if client.is_connected() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(client.is_connected());
+    assert!(!other.is_connected());
+    assert_eq!(server.clients(), vec![2]);
+}
@@ -0,0 +1,990 @@
+#![cfg(feature = "ffi")]
+
+use bevy_axon::ffi::*;
+use bevy_axon::mirror::*;
+use bevy_axon::netcode::*;
+use bevy_axon::protocol::*;
+use bevy_axon::transport::*;
+use renet::{ChannelConfig, ConnectionConfig, SendType};
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::net::UdpSocket;
+use std::ptr::{null, null_mut};
+use std::time::Duration;
+
+const DT: Duration = Duration::from_millis(16);
+
+fn errmsg(game: *const Game) -> Option<String> {
+    let p = unsafe { bevy_axon_ffi_errmsg(game) };
+    if p.is_null() {
+        return None;
+    }
+    Some(unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned())
+}
+
+fn create(addr: &str, client_id: u64) -> *mut Game {
+    let addr = CString::new(addr).unwrap();
+    let mut game = null_mut();
+    let err = unsafe { bevy_axon_ffi_create(addr.as_ptr(), client_id, &mut game) };
+    assert_eq!(err, AxonFfiError::Ok);
+    assert!(!game.is_null());
+    game
+}
+
+/// 取一个当前空闲的本地端口（netcode 的公开地址必须与绑定地址一致）
+fn free_addr() -> String {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.local_addr().unwrap().to_string()
+}
+
+fn options(addr: &CString, client_id: u64) -> AxonClientOptions {
+    AxonClientOptions {
+        server_addr: addr.as_ptr(),
+        bind_addr: null(),
+        client_id,
+        protocol_id: 0,
+        user_data: null(),
+        user_data_len: 0,
+        available_bytes_per_tick: 0,
+        channels: null(),
+        channel_count: 0,
+        connect_timeout_ms: 0,
+    }
+}
+
+fn create_ex(options: &AxonClientOptions) -> Result<*mut Game, AxonFfiError> {
+    let mut game = null_mut();
+    match unsafe { bevy_axon_ffi_create_ex(options, &mut game) } {
+        AxonFfiError::Ok => Ok(game),
+        err => {
+            assert!(game.is_null());
+            Err(err)
+        }
+    }
+}
+
+fn update(game: *mut Game) -> (AxonFfiError, Vec<u8>) {
+    let mut data = null();
+    let mut len = 0;
+    let err = unsafe { bevy_axon_ffi_update(game, DT.as_secs_f32(), &mut data, &mut len) };
+    let bytes = if len > 0 {
+        unsafe { std::slice::from_raw_parts(data, len) }.to_vec()
+    } else {
+        Vec::new()
+    };
+    (err, bytes)
+}
+
+/// 推进双方直到 game 连接成功
+fn connect(server: &mut AxonNetcodeServerTransport, game: *mut Game) {
+    for _ in 0..200 {
+        server.update(DT).unwrap();
+        server.flush().unwrap();
+        assert_eq!(update(game).0, AxonFfiError::Ok);
+        if unsafe { bevy_axon_ffi_is_connected(game) } == 1 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(unsafe { bevy_axon_ffi_is_connected(game) }, 1);
+    while server.next_event().is_some() {}
+}
+
+/// 推进 game 直到收到数据
+fn receive(game: *mut Game) -> Vec<u8> {
+    for _ in 0..200 {
+        let (err, bytes) = update(game);
+        assert_eq!(err, AxonFfiError::Ok);
+        if !bytes.is_empty() {
+            return bytes;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    Vec::new()
+}
+
+#[test]
+fn test_ffi_create_errors() {
+    let mut game = null_mut();
+    let err = unsafe { bevy_axon_ffi_create(null(), 1, &mut game) };
+    assert_eq!(err, AxonFfiError::NullPointer);
+    assert!(game.is_null());
+    assert!(errmsg(null()).unwrap().contains("addr is null"));
+
+    let addr = CString::new("not an address").unwrap();
+    let err = unsafe { bevy_axon_ffi_create(addr.as_ptr(), 1, &mut game) };
+    assert_eq!(err, AxonFfiError::InvalidArgument);
+    assert!(errmsg(null()).unwrap().contains("not an address"));
+
+    let invalid = [0xffu8, 0];
+    let err = unsafe { bevy_axon_ffi_create(invalid.as_ptr() as *const c_char, 1, &mut game) };
+    assert_eq!(err, AxonFfiError::InvalidUtf8);
+
+    let err = unsafe { bevy_axon_ffi_create(addr.as_ptr(), 1, null_mut()) };
+    assert_eq!(err, AxonFfiError::NullPointer);
+
+    // 成功创建会清除线程上的错误
+    let game = create("127.0.0.1:9", 1);
+    assert_eq!(errmsg(null()), None);
+    unsafe { bevy_axon_ffi_exit(game) };
+}
+
+#[test]
+fn test_ffi_errors_per_game() {
+    let a = create("127.0.0.1:9", 1);
+    let b = create("127.0.0.1:9", 2);
+
+    let raw = [1u8, 2, 3];
+    let err = unsafe { bevy_axon_ffi_invoke(a, raw.as_ptr(), raw.len()) };
+    assert_eq!(err, AxonFfiError::NotConnected);
+    assert!(errmsg(a).unwrap().contains("not connected"));
+    assert_eq!(errmsg(b), None);
+
+    let err = unsafe { bevy_axon_ffi_invoke(b, null(), 3) };
+    assert_eq!(err, AxonFfiError::NullPointer);
+    assert!(errmsg(b).unwrap().contains("raw is null"));
+
+    let mut data = null();
+    let mut len = 0;
+    let err = unsafe { bevy_axon_ffi_update(a, -1.0, &mut data, &mut len) };
+    assert_eq!(err, AxonFfiError::InvalidArgument);
+    assert!(errmsg(a).unwrap().contains("invalid dt"));
+    assert_eq!(len, 0);
+
+    let err = unsafe { bevy_axon_ffi_update(null_mut(), 0.016, &mut data, &mut len) };
+    assert_eq!(err, AxonFfiError::NullPointer);
+
+    unsafe {
+        bevy_axon_ffi_exit(a);
+        bevy_axon_ffi_exit(b);
+    }
+}
+
+#[test]
+fn test_ffi_connect() {
+    let addr = free_addr();
+    let mut server = AxonNetcodeServerTransport::bind(&addr, 4).unwrap();
+    let game = create(&addr, 7);
+    connect(&mut server, game);
+
+    // 客户端声明了压缩，服务器消息带消息头
+    let payload = vec![9u8; 1024];
+    server.send(
+        7,
+        AXON_CHANNEL_RELIABLE_ORDERED,
+        axon_encode_message(&payload, AXON_COMPRESSION_THRESHOLD),
+    );
+    server.flush().unwrap();
+    assert_eq!(receive(game), payload);
+    assert_eq!(errmsg(game), None);
+
+    // 不支持压缩的服务器忽略该标志，发来不带消息头的动作流
+    let raw = action(ACTION_TYPE_SPAWN, 42, 1, &[]);
+    server.send(7, AXON_CHANNEL_RELIABLE_ORDERED, raw.clone());
+    server.flush().unwrap();
+    assert_eq!(receive(game), raw);
+    assert_eq!(errmsg(game), None);
+
+    unsafe { bevy_axon_ffi_exit(game) };
+}
+
+#[test]
+fn test_ffi_create_ex_validation() {
+    let addr = CString::new("127.0.0.1:9").unwrap();
+    let mut game = null_mut();
+    let err = unsafe { bevy_axon_ffi_create_ex(null(), &mut game) };
+    assert_eq!(err, AxonFfiError::NullPointer);
+
+    let mut o = options(&addr, 1);
+    o.server_addr = null();
+    assert_eq!(create_ex(&o).err(), Some(AxonFfiError::NullPointer));
+    assert!(errmsg(null()).unwrap().contains("server_addr is null"));
+
+    let v6 = CString::new("[::]:0").unwrap();
+    let mut o = options(&addr, 1);
+    o.bind_addr = v6.as_ptr();
+    assert_eq!(create_ex(&o).err(), Some(AxonFfiError::InvalidArgument));
+    assert!(errmsg(null()).unwrap().contains("address families"));
+
+    let data = [0u8; 300];
+    let mut o = options(&addr, 1);
+    o.user_data = data.as_ptr();
+    o.user_data_len = data.len();
+    assert_eq!(create_ex(&o).err(), Some(AxonFfiError::InvalidArgument));
+
+    let channel = |channel_id, send_type| AxonChannelOptions {
+        channel_id,
+        send_type,
+        max_memory_usage_bytes: 1024 * 1024,
+        resend_time_ms: 0,
+    };
+    let cases = [
+        // 通道 id 重复
+        vec![
+            channel(2, AXON_SEND_RELIABLE_ORDERED),
+            channel(2, AXON_SEND_UNRELIABLE),
+        ],
+        // 未知的发送方式
+        vec![channel(2, AXON_SEND_RELIABLE_ORDERED), channel(0, 9)],
+        // 缺少可靠有序的通道 2
+        vec![channel(2, AXON_SEND_UNRELIABLE)],
+    ];
+    for channels in cases.iter() {
+        let mut o = options(&addr, 1);
+        o.channels = channels.as_ptr();
+        o.channel_count = channels.len();
+        assert_eq!(create_ex(&o).err(), Some(AxonFfiError::InvalidArgument));
+    }
+
+    let mut o = options(&addr, 1);
+    o.channel_count = 1;
+    assert_eq!(create_ex(&o).err(), Some(AxonFfiError::NullPointer));
+}
+
+#[test]
+fn test_ffi_create_ex_connect() {
+    let addr = free_addr();
+    let channels = [AxonChannelOptions {
+        channel_id: AXON_CHANNEL_RELIABLE_ORDERED,
+        send_type: AXON_SEND_RELIABLE_ORDERED,
+        max_memory_usage_bytes: 1024 * 1024,
+        resend_time_ms: 100,
+    }];
+    let config = ConnectionConfig {
+        available_bytes_per_tick: 30_000,
+        server_channels_config: vec![ChannelConfig {
+            channel_id: AXON_CHANNEL_RELIABLE_ORDERED,
+            max_memory_usage_bytes: 1024 * 1024,
+            send_type: SendType::ReliableOrdered {
+                resend_time: Duration::from_millis(100),
+            },
+        }],
+        client_channels_config: vec![ChannelConfig {
+            channel_id: AXON_CHANNEL_RELIABLE_ORDERED,
+            max_memory_usage_bytes: 1024 * 1024,
+            send_type: SendType::ReliableOrdered {
+                resend_time: Duration::from_millis(100),
+            },
+        }],
+    };
+    let mut server = AxonNetcodeServerTransport::bind_with(&addr, 4, 42, config).unwrap();
+
+    let server_addr = CString::new(addr).unwrap();
+    let bind_addr = CString::new("127.0.0.1:0").unwrap();
+    // 不声明压缩，服务器消息不带消息头
+    let user_data = axon_user_data(0);
+    let mut o = options(&server_addr, 3);
+    o.bind_addr = bind_addr.as_ptr();
+    o.protocol_id = 42;
+    o.user_data = user_data.as_ptr();
+    o.user_data_len = user_data.len();
+    o.available_bytes_per_tick = 30_000;
+    o.channels = channels.as_ptr();
+    o.channel_count = channels.len();
+    o.connect_timeout_ms = 2000;
+    let game = create_ex(&o).unwrap();
+    connect(&mut server, game);
+
+    server.send(3, AXON_CHANNEL_RELIABLE_ORDERED, vec![1, 2, 3]);
+    server.flush().unwrap();
+    assert_eq!(receive(game), vec![1, 2, 3]);
+    unsafe { bevy_axon_ffi_exit(game) };
+
+    // 协议 id 不一致时无法连接，超时后断开
+    let mut o = options(&server_addr, 4);
+    o.connect_timeout_ms = 50;
+    let game = create_ex(&o).unwrap();
+    for _ in 0..20 {
+        server.update(DT).unwrap();
+        server.flush().unwrap();
+        assert_eq!(update(game).0, AxonFfiError::Ok);
+    }
+    assert_eq!(
+        unsafe { bevy_axon_ffi_state(game) },
+        AxonFfiState::Disconnected
+    );
+    assert_eq!(
+        unsafe { bevy_axon_ffi_disconnect_reason(game) },
+        AxonDisconnectReason::ConnectTimedOut
+    );
+    assert_eq!(errmsg(game), None);
+    unsafe { bevy_axon_ffi_exit(game) };
+}
+
+/// 推进双方直到 game 断开
+fn wait_disconnected(server: &mut AxonNetcodeServerTransport, game: *mut Game) {
+    for _ in 0..200 {
+        server.update(DT).unwrap();
+        server.flush().unwrap();
+        assert_eq!(update(game).0, AxonFfiError::Ok);
+        if unsafe { bevy_axon_ffi_state(game) } == AxonFfiState::Disconnected {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    panic!("game is still {:?}", unsafe { bevy_axon_ffi_state(game) });
+}
+
+#[test]
+fn test_ffi_state() {
+    let addr = free_addr();
+    let mut server = AxonNetcodeServerTransport::bind(&addr, 1).unwrap();
+
+    let a = create(&addr, 1);
+    assert_eq!(unsafe { bevy_axon_ffi_state(a) }, AxonFfiState::Connecting);
+    assert_eq!(
+        unsafe { bevy_axon_ffi_disconnect_reason(a) },
+        AxonDisconnectReason::None
+    );
+    connect(&mut server, a);
+    assert_eq!(unsafe { bevy_axon_ffi_state(a) }, AxonFfiState::Connected);
+
+    // 服务器已满
+    let b = create(&addr, 2);
+    wait_disconnected(&mut server, b);
+    assert_eq!(
+        unsafe { bevy_axon_ffi_disconnect_reason(b) },
+        AxonDisconnectReason::ConnectionDenied
+    );
+    unsafe { bevy_axon_ffi_exit(b) };
+
+    // 服务器踢出
+    server.disconnect(1);
+    wait_disconnected(&mut server, a);
+    assert_eq!(
+        unsafe { bevy_axon_ffi_disconnect_reason(a) },
+        AxonDisconnectReason::DisconnectedByServer
+    );
+    unsafe { bevy_axon_ffi_exit(a) };
+    while server.next_event().is_some() {}
+
+    // 客户端主动断开，服务器立即收到断开包
+    let c = create(&addr, 3);
+    connect(&mut server, c);
+    assert_eq!(unsafe { bevy_axon_ffi_disconnect(c) }, AxonFfiError::Ok);
+    assert_eq!(
+        unsafe { bevy_axon_ffi_state(c) },
+        AxonFfiState::Disconnected
+    );
+    assert_eq!(
+        unsafe { bevy_axon_ffi_disconnect_reason(c) },
+        AxonDisconnectReason::DisconnectedByClient
+    );
+    let mut disconnected = false;
+    for _ in 0..200 {
+        server.update(DT).unwrap();
+        if let Some(AxonServerEvent::Disconnected { client_id, .. }) = server.next_event() {
+            assert_eq!(client_id, 3);
+            disconnected = true;
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(disconnected);
+    assert_eq!(update(c).0, AxonFfiError::Ok);
+    unsafe { bevy_axon_ffi_exit(c) };
+}
+
+/// 按 sbin 格式手工编码一条动作
+fn action(act: u8, id: u64, t: u32, v: &[u8]) -> Vec<u8> {
+    let mut data = vec![1, act, 4];
+    data.extend(id.to_le_bytes());
+    data.push(3);
+    data.extend(t.to_le_bytes());
+    data.push(13);
+    data.extend((v.len() as u32).to_le_bytes());
+    data.extend_from_slice(v);
+    data
+}
+
+fn next_action(game: *mut Game) -> Result<(u8, u64, u32, Vec<u8>), AxonFfiError> {
+    let (mut act, mut id, mut t, mut payload, mut len) = (0, 0, 0, null(), 0);
+    let err = unsafe {
+        bevy_axon_ffi_next_action(game, &mut act, &mut id, &mut t, &mut payload, &mut len)
+    };
+    if err != AxonFfiError::Ok {
+        return Err(err);
+    }
+    let v = unsafe { std::slice::from_raw_parts(payload, len) }.to_vec();
+    Ok((act, id, t, v))
+}
+
+#[test]
+fn test_ffi_next_action() {
+    let addr = free_addr();
+    let mut server = AxonNetcodeServerTransport::bind(&addr, 4).unwrap();
+    let game = create(&addr, 5);
+    connect(&mut server, game);
+    assert_eq!(next_action(game), Err(AxonFfiError::End));
+
+    let mut first = action(1, 42, 1, &[]);
+    first.extend(action(3, 42, 2, &[7, 8, 9]));
+    let second = action(7, 0, 0, &[]);
+    for data in [first, second] {
+        server.send(
+            5,
+            AXON_CHANNEL_RELIABLE_ORDERED,
+            axon_encode_message(&data, AXON_COMPRESSION_THRESHOLD),
+        );
+    }
+    server.flush().unwrap();
+
+    let mut actions = Vec::new();
+    for _ in 0..200 {
+        assert_eq!(update(game).0, AxonFfiError::Ok);
+        loop {
+            match next_action(game) {
+                Ok(action) => actions.push(action),
+                Err(err) => {
+                    assert_eq!(err, AxonFfiError::End);
+                    break;
+                }
+            }
+        }
+        if actions.len() >= 3 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(
+        actions,
+        vec![
+            (1, 42, 1, vec![]),
+            (3, 42, 2, vec![7, 8, 9]),
+            (7, 0, 0, vec![])
+        ]
+    );
+
+    // 截断的动作返回 Decode，之后不再返回动作
+    let mut broken = action(3, 1, 2, &[1, 2, 3]);
+    broken.truncate(broken.len() - 1);
+    let mut data = action(2, 1, 1, &[]);
+    data.extend(broken);
+    server.send(
+        5,
+        AXON_CHANNEL_RELIABLE_ORDERED,
+        axon_encode_message(&data, AXON_COMPRESSION_THRESHOLD),
+    );
+    server.flush().unwrap();
+    receive(game);
+    assert_eq!(next_action(game), Ok((2, 1, 1, vec![])));
+    assert_eq!(next_action(game), Err(AxonFfiError::Decode));
+    assert!(errmsg(game).unwrap().contains("malformed action"));
+    assert_eq!(next_action(game), Err(AxonFfiError::End));
+
+    unsafe { bevy_axon_ffi_exit(game) };
+}
+
+#[derive(Default)]
+struct Recorder {
+    game: *mut Game,
+    states: Vec<(AxonFfiState, AxonDisconnectReason)>,
+    actions: Vec<(u8, u64, u32, Vec<u8>)>,
+    nested_update: Option<AxonFfiError>,
+}
+
+unsafe fn recorder<'a>(user_data: *mut c_void) -> &'a mut Recorder {
+    &mut *(user_data as *mut Recorder)
+}
+
+unsafe extern "C-unwind" fn on_spawn(
+    user_data: *mut c_void,
+    id: u64,
+    t: u32,
+    v: *const u8,
+    len: usize,
+) {
+    let r = recorder(user_data);
+    r.actions
+        .push((1, id, t, std::slice::from_raw_parts(v, len).to_vec()));
+    // 回调内重入 invoke 与 update
+    let reply = action(4, id, 9, &[]);
+    assert_eq!(
+        bevy_axon_ffi_invoke(r.game, reply.as_ptr(), reply.len()),
+        AxonFfiError::Ok
+    );
+    let (mut data, mut len) = (null(), 0);
+    r.nested_update = Some(bevy_axon_ffi_update(r.game, 0.0, &mut data, &mut len));
+}
+
+unsafe extern "C-unwind" fn on_change(
+    user_data: *mut c_void,
+    id: u64,
+    t: u32,
+    v: *const u8,
+    len: usize,
+) {
+    let r = recorder(user_data);
+    r.actions
+        .push((3, id, t, std::slice::from_raw_parts(v, len).to_vec()));
+}
+
+unsafe extern "C-unwind" fn on_other(
+    user_data: *mut c_void,
+    act: u8,
+    id: u64,
+    t: u32,
+    v: *const u8,
+    len: usize,
+) {
+    let r = recorder(user_data);
+    r.actions
+        .push((act, id, t, std::slice::from_raw_parts(v, len).to_vec()));
+}
+
+unsafe extern "C-unwind" fn on_state(
+    user_data: *mut c_void,
+    state: AxonFfiState,
+    reason: AxonDisconnectReason,
+) {
+    recorder(user_data).states.push((state, reason));
+}
+
+#[test]
+fn test_ffi_callbacks() {
+    let addr = free_addr();
+    let mut server = AxonNetcodeServerTransport::bind(&addr, 4).unwrap();
+    let game = create(&addr, 6);
+    let mut rec = Box::new(Recorder {
+        game,
+        ..Default::default()
+    });
+    let callbacks = AxonCallbacks {
+        user_data: &mut *rec as *mut Recorder as *mut c_void,
+        on_spawn: Some(on_spawn),
+        on_despawn: None,
+        on_change: Some(on_change),
+        on_invoke: None,
+        on_other: Some(on_other),
+        on_state: Some(on_state),
+    };
+    assert_eq!(
+        unsafe { bevy_axon_ffi_set_callbacks(game, &callbacks) },
+        AxonFfiError::Ok
+    );
+    connect(&mut server, game);
+    assert_eq!(
+        rec.states,
+        vec![(AxonFfiState::Connected, AxonDisconnectReason::None)]
+    );
+
+    let mut data = action(1, 42, 1, &[]);
+    data.extend(action(2, 43, 0, &[]));
+    data.extend(action(3, 42, 2, &[5]));
+    data.extend(action(7, 0, 0, &[]));
+    server.send(
+        6,
+        AXON_CHANNEL_RELIABLE_ORDERED,
+        axon_encode_message(&data, AXON_COMPRESSION_THRESHOLD),
+    );
+    server.flush().unwrap();
+    receive(game);
+    // 未注册 on_despawn，其余按顺序分发
+    assert_eq!(
+        rec.actions,
+        vec![(1, 42, 1, vec![]), (3, 42, 2, vec![5]), (7, 0, 0, vec![])]
+    );
+    assert_eq!(rec.nested_update, Some(AxonFfiError::InvalidArgument));
+    // 回调后 next_action 仍可遍历本帧数据
+    assert_eq!(next_action(game), Ok((1, 42, 1, vec![])));
+
+    // 回调中 invoke 的消息在同一次 update 中发出
+    let mut replies = Vec::new();
+    for _ in 0..200 {
+        server.update(DT).unwrap();
+        if let Some(message) = server.receive(6, AXON_CHANNEL_RELIABLE_ORDERED) {
+            replies.push(message);
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(replies, vec![action(4, 42, 9, &[])]);
+
+    server.disconnect(6);
+    wait_disconnected(&mut server, game);
+    assert_eq!(
+        rec.states.last(),
+        Some(&(
+            AxonFfiState::Disconnected,
+            AxonDisconnectReason::DisconnectedByServer
+        ))
+    );
+
+    unsafe { bevy_axon_ffi_exit(game) };
+}
+
+unsafe extern "C-unwind" fn on_spawn_panic(
+    _user_data: *mut c_void,
+    _id: u64,
+    _t: u32,
+    _v: *const u8,
+    _len: usize,
+) {
+    panic!("boom in callback");
+}
+
+#[test]
+fn test_ffi_callback_panic() {
+    let addr = free_addr();
+    let mut server = AxonNetcodeServerTransport::bind(&addr, 4).unwrap();
+    let game = create(&addr, 6);
+    let callbacks = AxonCallbacks {
+        user_data: null_mut(),
+        on_spawn: Some(on_spawn_panic),
+        on_despawn: None,
+        on_change: None,
+        on_invoke: None,
+        on_other: None,
+        on_state: None,
+    };
+    assert_eq!(
+        unsafe { bevy_axon_ffi_set_callbacks(game, &callbacks) },
+        AxonFfiError::Ok
+    );
+    connect(&mut server, game);
+
+    server.send(
+        6,
+        AXON_CHANNEL_RELIABLE_ORDERED,
+        axon_encode_message(&action(1, 42, 1, &[]), AXON_COMPRESSION_THRESHOLD),
+    );
+    server.flush().unwrap();
+    // 回调中的 panic 由 update 捕获，不会展开到调用方
+    let mut result = AxonFfiError::Ok;
+    for _ in 0..200 {
+        result = update(game).0;
+        if result != AxonFfiError::Ok {
+            break;
+        }
+        server.update(DT).unwrap();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(result, AxonFfiError::Panic);
+    assert!(errmsg(game)
+        .unwrap()
+        .contains("[bevy_axon_ffi_update] panic: boom in callback"));
+
+    // 中毒后的 game 只能释放
+    assert_eq!(
+        unsafe { bevy_axon_ffi_state(game) },
+        AxonFfiState::Disconnected
+    );
+    assert_eq!(update(game).0, AxonFfiError::Panic);
+    let data = action(4, 42, 9, &[]);
+    assert_eq!(
+        unsafe { bevy_axon_ffi_invoke(game, data.as_ptr(), data.len()) },
+        AxonFfiError::Panic
+    );
+    // 查询函数也不再读取回调 panic 时更新了一半的世界镜像
+    assert_eq!(
+        unsafe { bevy_axon_ffi_disconnect_reason(game) },
+        AxonDisconnectReason::Panicked
+    );
+    assert_eq!(unsafe { bevy_axon_ffi_is_connected(game) }, 0);
+    let mut ids = [0u64; 4];
+    assert_eq!(
+        unsafe { bevy_axon_ffi_objects(game, ids.as_mut_ptr(), ids.len()) },
+        0
+    );
+    let mut t = 0;
+    assert_eq!(
+        unsafe { bevy_axon_ffi_object_type(game, 42, &mut t) },
+        AxonFfiError::Panic
+    );
+    assert_eq!(variant(game, 42, 2), Err(AxonFfiError::Panic));
+    assert!(changes(game).is_empty());
+    assert_eq!(next_action(game), Err(AxonFfiError::Panic));
+    assert_eq!(
+        unsafe { bevy_axon_ffi_action_channel(game) },
+        AXON_CHANNEL_RELIABLE_ORDERED
+    );
+    let mut stats = AxonNetStats::default();
+    assert_eq!(
+        unsafe { bevy_axon_ffi_stats(game, &mut stats) },
+        AxonFfiError::Panic
+    );
+    assert!(errmsg(game).unwrap().contains("boom in callback"));
+
+    unsafe { bevy_axon_ffi_exit(game) };
+}
+
+#[test]
+fn test_ffi_channels() {
+    let addr = free_addr();
+    let mut server = AxonNetcodeServerTransport::bind(&addr, 4).unwrap();
+    let game = create(&addr, 6);
+    connect(&mut server, game);
+
+    for (channel, data) in [
+        (AXON_CHANNEL_UNRELIABLE, action(3, 42, 1, &[1])),
+        (AXON_CHANNEL_RELIABLE_UNORDERED, action(3, 42, 1, &[2])),
+        (AXON_CHANNEL_RELIABLE_ORDERED, action(1, 42, 1, &[])),
+    ] {
+        server.send(
+            6,
+            channel,
+            axon_encode_message(&data, AXON_COMPRESSION_THRESHOLD),
+        );
+    }
+    server.flush().unwrap();
+
+    // 按 AXON_CHANNELS 的顺序读取
+    let mut actions = Vec::new();
+    for _ in 0..200 {
+        assert_eq!(update(game).0, AxonFfiError::Ok);
+        while let Ok((act, _, _, v)) = next_action(game) {
+            actions.push((act, v, unsafe { bevy_axon_ffi_action_channel(game) }));
+        }
+        if actions.len() >= 3 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(
+        actions,
+        vec![
+            (1, vec![], AXON_CHANNEL_RELIABLE_ORDERED),
+            (3, vec![2], AXON_CHANNEL_RELIABLE_UNORDERED),
+            (3, vec![1], AXON_CHANNEL_UNRELIABLE),
+        ]
+    );
+
+    let input = action(4, 0, 5, &[3]);
+    let err = unsafe {
+        bevy_axon_ffi_invoke_on(game, AXON_CHANNEL_UNRELIABLE, input.as_ptr(), input.len())
+    };
+    assert_eq!(err, AxonFfiError::Ok);
+    let err = unsafe { bevy_axon_ffi_invoke_on(game, 9, input.as_ptr(), input.len()) };
+    assert_eq!(err, AxonFfiError::InvalidArgument);
+    assert!(errmsg(game).unwrap().contains("channel 9"));
+
+    update(game);
+    let mut received = None;
+    for _ in 0..200 {
+        server.update(DT).unwrap();
+        received = server.receive(6, AXON_CHANNEL_UNRELIABLE);
+        if received.is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(received, Some(input));
+
+    unsafe { bevy_axon_ffi_exit(game) };
+}
+
+#[test]
+fn test_ffi_stats() {
+    let addr = free_addr();
+    let mut server = AxonNetcodeServerTransport::bind(&addr, 4).unwrap();
+    let game = create(&addr, 8);
+    assert_eq!(
+        unsafe { bevy_axon_ffi_stats(game, null_mut()) },
+        AxonFfiError::NullPointer
+    );
+    assert!(errmsg(game).unwrap().contains("out_stats is null"));
+    connect(&mut server, game);
+
+    for _ in 0..2 {
+        server.send(
+            8,
+            AXON_CHANNEL_RELIABLE_ORDERED,
+            axon_encode_message(&action(7, 0, 0, &[]), AXON_COMPRESSION_THRESHOLD),
+        );
+    }
+    server.flush().unwrap();
+    let input = action(4, 0, 5, &[]);
+    let err = unsafe {
+        bevy_axon_ffi_invoke_on(game, AXON_CHANNEL_UNRELIABLE, input.as_ptr(), input.len())
+    };
+    assert_eq!(err, AxonFfiError::Ok);
+
+    let mut stats = AxonNetStats::default();
+    for _ in 0..200 {
+        assert_eq!(update(game).0, AxonFfiError::Ok);
+        assert_eq!(
+            unsafe { bevy_axon_ffi_stats(game, &mut stats) },
+            AxonFfiError::Ok
+        );
+        if stats.channels[0].messages_received >= 2 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(stats.channel_count, AXON_CHANNELS.len());
+    let channels: Vec<_> = stats.channels[..stats.channel_count]
+        .iter()
+        .map(|v| (v.channel_id, v.messages_sent, v.messages_received))
+        .collect();
+    assert_eq!(
+        channels,
+        vec![
+            (AXON_CHANNEL_RELIABLE_ORDERED, 0, 2),
+            (AXON_CHANNEL_RELIABLE_UNORDERED, 0, 0),
+            (AXON_CHANNEL_UNRELIABLE, 1, 0),
+        ]
+    );
+    assert!(stats.rtt_ms >= 0.0);
+    assert!((0.0..=1.0).contains(&stats.packet_loss));
+    assert!(stats.bytes_received_per_second > 0.0);
+
+    unsafe { bevy_axon_ffi_exit(game) };
+}
+
+#[test]
+fn test_ffi_json() {
+    let json = CString::new(r#"{"x": {"$f32": 1.5}, "name": "go"}"#).unwrap();
+    let (mut data, mut len) = (null(), 0);
+    let err = unsafe { bevy_axon_ffi_json_to_sbin(json.as_ptr(), &mut data, &mut len) };
+    assert_eq!(err, AxonFfiError::Ok);
+    let sbin = unsafe { std::slice::from_raw_parts(data, len) }.to_vec();
+
+    let mut out = null();
+    let err = unsafe { bevy_axon_ffi_sbin_to_json(sbin.as_ptr(), sbin.len(), 0, &mut out) };
+    assert_eq!(err, AxonFfiError::Ok);
+    let plain = unsafe { CStr::from_ptr(out) }.to_str().unwrap().to_string();
+    assert_eq!(plain, r#"{"x":1.5,"name":"go"}"#);
+    let err = unsafe { bevy_axon_ffi_sbin_to_json(sbin.as_ptr(), sbin.len(), 1, &mut out) };
+    assert_eq!(err, AxonFfiError::Ok);
+    let typed = unsafe { CStr::from_ptr(out) }.to_str().unwrap();
+    assert_eq!(typed, r#"{"x":{"$f32":1.5},"name":"go"}"#);
+
+    let bad = CString::new(r#"{"$u8": 256}"#).unwrap();
+    let err = unsafe { bevy_axon_ffi_json_to_sbin(bad.as_ptr(), &mut data, &mut len) };
+    assert_eq!(err, AxonFfiError::InvalidArgument);
+    assert!(data.is_null());
+    assert!(errmsg(null()).unwrap().contains("$u8 out of range"));
+    let err = unsafe { bevy_axon_ffi_sbin_to_json(sbin.as_ptr(), sbin.len() - 1, 0, &mut out) };
+    assert_eq!(err, AxonFfiError::Decode);
+    assert!(out.is_null());
+
+    // 以事件发给服务器
+    let addr = free_addr();
+    let mut server = AxonNetcodeServerTransport::bind(&addr, 4).unwrap();
+    let game = create(&addr, 9);
+    connect(&mut server, game);
+    let err = unsafe { bevy_axon_ffi_invoke_json(game, 42, 5, json.as_ptr()) };
+    assert_eq!(err, AxonFfiError::Ok);
+    let err = unsafe { bevy_axon_ffi_invoke_json(game, 42, 5, bad.as_ptr()) };
+    assert_eq!(err, AxonFfiError::InvalidArgument);
+    assert!(errmsg(game).unwrap().contains("$u8 out of range"));
+    update(game);
+    let mut received = None;
+    for _ in 0..200 {
+        server.update(DT).unwrap();
+        received = server.receive(9, AXON_CHANNEL_RELIABLE_ORDERED);
+        if received.is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(received, Some(action(4, 42, 5, &sbin)));
+
+    unsafe { bevy_axon_ffi_exit(game) };
+}
+
+fn changes(game: *mut Game) -> Vec<(AxonChangeKind, u64, u32)> {
+    let mut changes = Vec::new();
+    let mut change = AxonChange {
+        kind: AxonChangeKind::Spawn,
+        id: 0,
+        t: 0,
+    };
+    while unsafe { bevy_axon_ffi_next_change(game, &mut change) } == AxonFfiError::Ok {
+        changes.push((change.kind, change.id, change.t));
+    }
+    changes
+}
+
+fn variant(game: *mut Game, id: u64, t: u32) -> Result<Vec<u8>, AxonFfiError> {
+    let (mut payload, mut len) = (null(), 0);
+    match unsafe { bevy_axon_ffi_variant(game, id, t, &mut payload, &mut len) } {
+        AxonFfiError::Ok => Ok(unsafe { std::slice::from_raw_parts(payload, len) }.to_vec()),
+        err => Err(err),
+    }
+}
+
+#[test]
+fn test_ffi_mirror() {
+    let addr = free_addr();
+    let mut server = AxonNetcodeServerTransport::bind(&addr, 4).unwrap();
+    let game = create(&addr, 10);
+    connect(&mut server, game);
+
+    let mut data = Vec::new();
+    for (act, id, t, v) in [
+        (ACTION_TYPE_SPAWN, 1, 10, &[][..]),
+        (ACTION_TYPE_CHANGE, 1, 20, &[1]),
+        (ACTION_TYPE_SPAWN, 2, 11, &[]),
+        // 重复的生成与未知对象的变更被忽略
+        (ACTION_TYPE_SPAWN, 1, 99, &[]),
+        (ACTION_TYPE_CHANGE, 99, 20, &[]),
+        (ACTION_TYPE_CHANGE, 1, 20, &[2]),
+        (ACTION_TYPE_RESOURCE, 0, 30, &[9]),
+        (ACTION_TYPE_RETYPE, 1, 12, &[]),
+        (ACTION_TYPE_DESPAWN, 2, 0, &[]),
+        (ACTION_TYPE_SYNC, 0, 0, &[]),
+    ] {
+        data.extend(action(act, id, t, v));
+    }
+    server.send(
+        10,
+        AXON_CHANNEL_RELIABLE_ORDERED,
+        axon_encode_message(&data, AXON_COMPRESSION_THRESHOLD),
+    );
+    server.flush().unwrap();
+    receive(game);
+
+    assert_eq!(
+        changes(game),
+        vec![
+            (AxonChangeKind::Spawn, 1, 10),
+            (AxonChangeKind::Change, 1, 20),
+            (AxonChangeKind::Spawn, 2, 11),
+            (AxonChangeKind::Change, 1, 20),
+            (AxonChangeKind::Resource, 0, 30),
+            (AxonChangeKind::Retype, 1, 12),
+            (AxonChangeKind::Despawn, 2, 11),
+        ]
+    );
+    let mut ids = [0u64; 4];
+    assert_eq!(
+        unsafe { bevy_axon_ffi_objects(game, ids.as_mut_ptr(), ids.len()) },
+        1
+    );
+    assert_eq!(ids[0], 1);
+    assert_eq!(unsafe { bevy_axon_ffi_objects(game, null_mut(), 0) }, 1);
+    let mut t = 0;
+    assert_eq!(
+        unsafe { bevy_axon_ffi_object_type(game, 1, &mut t) },
+        AxonFfiError::Ok
+    );
+    assert_eq!(t, 12);
+    assert_eq!(
+        unsafe { bevy_axon_ffi_object_type(game, 2, &mut t) },
+        AxonFfiError::NotFound
+    );
+    // 换类型后变体保留
+    assert_eq!(variant(game, 1, 20), Ok(vec![2]));
+    assert_eq!(variant(game, 1, 21), Err(AxonFfiError::NotFound));
+    let (mut payload, mut len) = (null(), 0);
+    assert_eq!(
+        unsafe { bevy_axon_ffi_resource(game, 30, &mut payload, &mut len) },
+        AxonFfiError::Ok
+    );
+    assert_eq!(unsafe { std::slice::from_raw_parts(payload, len) }, &[9]);
+    assert_eq!(errmsg(game), None);
+
+    // 下一帧没有变化，状态保留
+    assert_eq!(update(game).0, AxonFfiError::Ok);
+    assert_eq!(changes(game), vec![]);
+    assert_eq!(variant(game, 1, 20), Ok(vec![2]));
+
+    // 断开时清空
+    server.disconnect(10);
+    wait_disconnected(&mut server, game);
+    assert_eq!(changes(game), vec![(AxonChangeKind::Despawn, 1, 12)]);
+    assert_eq!(unsafe { bevy_axon_ffi_objects(game, null_mut(), 0) }, 0);
+    assert_eq!(variant(game, 1, 20), Err(AxonFfiError::NotFound));
+
+    unsafe { bevy_axon_ffi_exit(game) };
+}
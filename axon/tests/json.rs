@@ -0,0 +1,206 @@
+#![cfg(feature = "ffi")]
+
+use bevy_axon::json::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Inner {
+    name: String,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Everything {
+    a: u8,
+    b: u16,
+    c: u32,
+    d: u64,
+    e: i8,
+    f: i16,
+    g: i32,
+    h: i64,
+    x: f32,
+    y: f64,
+    ok: bool,
+    text: String,
+    #[serde(with = "serde_bytes")]
+    raw: Vec<u8>,
+    list: Vec<i32>,
+    maybe: Option<u32>,
+    none: Option<u32>,
+    scores: BTreeMap<u32, String>,
+    named: BTreeMap<String, f32>,
+    inner: Inner,
+}
+
+fn everything() -> Everything {
+    Everything {
+        a: 255,
+        b: 65535,
+        c: 4_000_000_000,
+        d: u64::MAX,
+        e: -128,
+        f: -32768,
+        g: i32::MIN,
+        h: i64::MIN,
+        x: 1.1,
+        y: -2.5e300,
+        ok: true,
+        text: "你好, \"axon\"".to_string(),
+        raw: vec![0, 1, 255],
+        list: vec![-1, 0, 1],
+        maybe: Some(3),
+        none: None,
+        scores: BTreeMap::from([(1, "one".to_string()), (2, "two".to_string())]),
+        named: BTreeMap::from([("hp".to_string(), 0.5)]),
+        inner: Inner {
+            name: "inner".to_string(),
+            tags: vec!["a".to_string(), "b".to_string()],
+        },
+    }
+}
+
+#[test]
+fn test_typed_roundtrip() {
+    let value = everything();
+    let bytes = serde_sbin::to_bytes(&value).unwrap();
+    let typed = axon_sbin_to_json(&bytes, true).unwrap();
+    assert_eq!(typed["a"], json!({"$u8": 255}));
+    assert_eq!(typed["d"], json!({"$u64": u64::MAX}));
+    assert_eq!(typed["x"], json!({"$f32": 1.1}));
+    assert_eq!(typed["raw"], json!({"$bytes": [0, 1, 255]}));
+    assert_eq!(
+        typed["scores"],
+        json!({"$map": [[{"$u32": 1}, "one"], [{"$u32": 2}, "two"]]})
+    );
+    assert_eq!(typed["none"], json!(null));
+
+    // 经 JSON 文本转回 sbin 后字节不变
+    let text = typed.to_string();
+    let back = axon_json_str_to_sbin(&text).unwrap();
+    assert_eq!(back, bytes);
+    assert_eq!(serde_sbin::from_bytes::<Everything>(&back).unwrap(), value);
+}
+
+#[test]
+fn test_plain_json() {
+    let bytes = serde_sbin::to_bytes(&everything()).unwrap();
+    let plain = axon_sbin_to_json(&bytes, false).unwrap();
+    assert_eq!(plain["a"], json!(255));
+    assert_eq!(plain["x"], json!(1.1));
+    assert_eq!(plain["raw"], json!([0, 1, 255]));
+    assert_eq!(plain["list"], json!([-1, 0, 1]));
+    assert_eq!(plain["scores"], json!({"1": "one", "2": "two"}));
+    assert_eq!(plain["inner"], json!({"name": "inner", "tags": ["a", "b"]}));
+    // 字段顺序与 Rust 定义一致
+    let keys: Vec<_> = plain.as_object().unwrap().keys().cloned().collect();
+    assert_eq!(keys[..3], ["a", "b", "c"]);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Move {
+    id: i64,
+    speed: f64,
+    name: String,
+    path: Vec<i64>,
+    run: bool,
+    target: Option<i64>,
+    step: u32,
+}
+
+#[test]
+fn test_plain_json_to_rust() {
+    // 普通数字对应 i64/f64，其他数字类型需要包装
+    let bytes = axon_json_str_to_sbin(
+        r#"{"id": -3, "speed": 1.5, "name": "go", "path": [1, 2], "run": true, "target": null, "step": {"$u32": 9}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        serde_sbin::from_bytes::<Move>(&bytes).unwrap(),
+        Move {
+            id: -3,
+            speed: 1.5,
+            name: "go".to_string(),
+            path: vec![1, 2],
+            run: true,
+            target: None,
+            step: 9,
+        }
+    );
+
+    let bytes = axon_json_str_to_sbin(r#"{"$u32": 9}"#).unwrap();
+    assert_eq!(bytes, serde_sbin::to_bytes(&9u32).unwrap());
+    let bytes = axon_json_str_to_sbin("9").unwrap();
+    assert_eq!(bytes, serde_sbin::to_bytes(&9i64).unwrap());
+    let bytes = axon_json_str_to_sbin(&u64::MAX.to_string()).unwrap();
+    assert_eq!(bytes, serde_sbin::to_bytes(&u64::MAX).unwrap());
+}
+
+#[test]
+fn test_special_values() {
+    for v in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        let bytes = serde_sbin::to_bytes(&v).unwrap();
+        assert_eq!(axon_sbin_to_json(&bytes, false).unwrap(), json!(null));
+        let typed = axon_sbin_to_json(&bytes, true).unwrap();
+        assert_eq!(axon_json_to_sbin(&typed).unwrap(), bytes);
+    }
+
+    // 会被当作包装的单键对象用 $map 表示
+    let value = BTreeMap::from([("$u8".to_string(), 1u8)]);
+    let bytes = serde_sbin::to_bytes(&value).unwrap();
+    let typed = axon_sbin_to_json(&bytes, true).unwrap();
+    assert_eq!(typed, json!({"$map": [["$u8", {"$u8": 1}]]}));
+    assert_eq!(axon_json_to_sbin(&typed).unwrap(), bytes);
+}
+
+#[test]
+fn test_errors() {
+    assert!(matches!(
+        axon_json_str_to_sbin("{"),
+        Err(AxonJsonError::Json(_))
+    ));
+    for json in [
+        r#"{"$u8": 256}"#,
+        r#"{"$i8": -129}"#,
+        r#"{"$u32": 1.5}"#,
+        r#"{"$f32": "x"}"#,
+        r#"{"$bytes": [1, 300]}"#,
+        r#"{"$map": [[1]]}"#,
+    ] {
+        assert!(
+            matches!(
+                axon_json_str_to_sbin(json),
+                Err(AxonJsonError::InvalidValue(_))
+            ),
+            "{}",
+            json
+        );
+    }
+
+    let bytes = serde_sbin::to_bytes(&everything()).unwrap();
+    assert!(matches!(
+        axon_sbin_to_json(&bytes[..bytes.len() - 1], false),
+        Err(AxonJsonError::UnexpectedEof)
+    ));
+    let mut extra = bytes.clone();
+    extra.push(0);
+    assert!(matches!(
+        axon_sbin_to_json(&extra, false),
+        Err(AxonJsonError::TrailingBytes(_))
+    ));
+    assert!(matches!(
+        axon_sbin_to_json(&[99], false),
+        Err(AxonJsonError::InvalidType(99))
+    ));
+    assert!(matches!(
+        axon_sbin_to_json(&[], false),
+        Err(AxonJsonError::UnexpectedEof)
+    ));
+    let deep = vec![14u8; 1000];
+    assert!(matches!(
+        axon_sbin_to_json(&deep, false),
+        Err(AxonJsonError::InvalidValue(_))
+    ));
+}
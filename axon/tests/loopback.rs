@@ -0,0 +1,734 @@
+#![cfg(feature = "server")]
+
+use bevy::prelude::*;
+use bevy_axon::core::*;
+use bevy_axon::loopback::*;
+use bevy_axon::persist::*;
+use bevy_axon::protocol::*;
+use bevy_axon::server::*;
+use bevy_axon::transport::AxonServerTransport;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+const DT: Duration = Duration::from_millis(16);
+
+#[derive(Component, Default)]
+struct Player;
+
+impl AxonObject for Player {
+    fn axon_object_type() -> u32 {
+        1
+    }
+}
+
+#[derive(Component, Default)]
+struct Corpse;
+
+impl AxonObject for Corpse {
+    fn axon_object_type() -> u32 {
+        7
+    }
+}
+
+#[derive(Component, Serialize, Deserialize, Debug, PartialEq)]
+struct Position {
+    x: f32,
+    y: f32,
+}
+
+impl AxonVariant for Position {
+    fn axon_variant_type() -> u32 {
+        2
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Debug, PartialEq)]
+struct Score {
+    red: u32,
+    blue: u32,
+}
+
+impl AxonResource for Score {
+    fn axon_resource_type() -> u32 {
+        4
+    }
+}
+
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Move {
+    x: f32,
+}
+
+impl AxonEvent for Move {
+    fn axon_event_type() -> u32 {
+        3
+    }
+    fn axon_event_invoke(bytes: &[u8], commands: &mut Commands<'_, '_>) {
+        match serde_sbin::from_bytes::<Move>(bytes) {
+            Ok(event) => commands.trigger(event),
+            Err(e) => warn!("axon event decode error: {}", e),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct Received(Vec<Move>);
+
+fn server() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_plugins((AxonPlugin, AxonServerPlugin));
+    app.add_axon_persistent_object::<Player>();
+    app.add_axon_object::<Corpse>();
+    app.add_axon_persistent_variant::<Position>();
+    app.add_axon_event::<Move>();
+    app.add_axon_persistent_resource::<Score>();
+    app.init_resource::<Received>();
+    app.add_observer(|event: On<Move>, mut received: ResMut<Received>| {
+        received.0.push(event.event().clone());
+    });
+    app.start_loopback_server();
+    app
+}
+
+fn acts(actions: &[AxonAction]) -> Vec<(u8, u64, u32)> {
+    actions.iter().map(|v| (v.act, v.id, v.t)).collect()
+}
+
+/// 空世界的入场快照只有 SYNC
+fn assert_synced(app: &mut App, client_id: u64) {
+    let actions = app.loopback_client(client_id).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_SYNC, 0, 0)]);
+}
+
+#[test]
+fn test_client_entity() {
+    let mut app = server();
+    app.connect_loopback_client(1);
+    app.step(DT);
+
+    let mut query = app.world_mut().query::<&AxonClient>();
+    let ids: Vec<u64> = query.iter(app.world()).map(|v| v.id).collect();
+    assert_eq!(ids, vec![1]);
+
+    app.disconnect_loopback_client(1);
+    app.step(DT);
+    assert_eq!(query.iter(app.world()).count(), 0);
+}
+
+#[test]
+fn test_spawn_change_despawn() {
+    let mut app = server();
+    app.connect_loopback_client(1);
+    app.step(DT);
+    assert_synced(&mut app, 1);
+
+    let entity = app
+        .world_mut()
+        .spawn((Player, Position { x: 1.0, y: 2.0 }))
+        .id();
+    let id = entity.to_bits();
+    app.step(DT);
+
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(
+        acts(&actions),
+        vec![(ACTION_TYPE_SPAWN, id, 1), (ACTION_TYPE_CHANGE, id, 2)]
+    );
+    let pos: Position = serde_sbin::from_bytes(&actions[1].v).unwrap();
+    assert_eq!(pos, Position { x: 1.0, y: 2.0 });
+
+    app.world_mut().get_mut::<Position>(entity).unwrap().x = 5.0;
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_CHANGE, id, 2)]);
+    let pos: Position = serde_sbin::from_bytes(&actions[0].v).unwrap();
+    assert_eq!(pos.x, 5.0);
+
+    app.step(DT);
+    assert!(app.loopback_client(1).receive().is_empty());
+
+    app.world_mut().despawn(entity);
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_DESPAWN, id, 1)]);
+}
+
+#[test]
+fn test_late_join_snapshot() {
+    let mut app = server();
+    let entity = app
+        .world_mut()
+        .spawn((Player, Position { x: 3.0, y: 4.0 }))
+        .id();
+    let id = entity.to_bits();
+    app.step(DT);
+
+    app.connect_loopback_client(2);
+    app.step(DT);
+    let actions = app.loopback_client(2).receive();
+    assert_eq!(
+        acts(&actions),
+        vec![
+            (ACTION_TYPE_SPAWN, id, 1),
+            (ACTION_TYPE_CHANGE, id, 2),
+            (ACTION_TYPE_SYNC, 0, 0)
+        ]
+    );
+    let pos: Position = serde_sbin::from_bytes(&actions[1].v).unwrap();
+    assert_eq!(pos, Position { x: 3.0, y: 4.0 });
+}
+
+#[test]
+fn test_invoke() {
+    let mut app = server();
+    app.connect_loopback_client(1);
+    app.connect_loopback_client(2);
+    app.step(DT);
+    for client_id in [1, 2] {
+        assert_synced(&mut app, client_id);
+    }
+
+    app.loopback_client(1).invoke(0, &Move { x: 7.0 }).unwrap();
+    app.step(DT);
+    assert_eq!(app.world().resource::<Received>().0, vec![Move { x: 7.0 }]);
+
+    app.send_axon_client_event(9, &Move { x: 1.0 }, 2).unwrap();
+    app.step(DT);
+    assert!(app.loopback_client(1).receive().is_empty());
+    let actions = app.loopback_client(2).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_INVOKE, 9, 3)]);
+
+    app.broadcast_axon_client_event(0, &Move { x: 2.0 })
+        .unwrap();
+    app.step(DT);
+    for client_id in [1, 2] {
+        let actions = app.loopback_client(client_id).receive();
+        assert_eq!(acts(&actions), vec![(ACTION_TYPE_INVOKE, 0, 3)]);
+        let event: Move = serde_sbin::from_bytes(&actions[0].v).unwrap();
+        assert_eq!(event.x, 2.0);
+    }
+}
+
+#[test]
+fn test_invoke_garbage() {
+    let mut app = server();
+    app.connect_loopback_client(1);
+    app.step(DT);
+    assert_synced(&mut app, 1);
+
+    // 无法解码的事件被丢弃，服务器继续运行
+    app.loopback_client(1)
+        .send(encode_axon_action(ACTION_TYPE_INVOKE, 0, 3, &[0xff, 1]));
+    app.step(DT);
+    assert!(app.world().resource::<Received>().0.is_empty());
+
+    app.loopback_client(1).invoke(0, &Move { x: 7.0 }).unwrap();
+    app.step(DT);
+    assert_eq!(app.world().resource::<Received>().0, vec![Move { x: 7.0 }]);
+}
+
+#[test]
+fn test_spectator() {
+    let mut app = server();
+    app.connect_loopback_client(1);
+    app.connect_loopback_client_with_flags(2, AXON_FLAG_SPECTATOR);
+    app.step(DT);
+    for client_id in [1, 2] {
+        assert_synced(&mut app, client_id);
+    }
+
+    let mut query = app.world_mut().query::<&AxonSpectator>();
+    let ids: Vec<u64> = query.iter(app.world()).map(|v| v.id).collect();
+    assert_eq!(ids, vec![2]);
+    let mut query = app.world_mut().query::<&AxonClient>();
+    let ids: Vec<u64> = query.iter(app.world()).map(|v| v.id).collect();
+    assert_eq!(ids, vec![1]);
+
+    app.loopback_client(2).invoke(0, &Move { x: 7.0 }).unwrap();
+    app.step(DT);
+    assert!(app.world().resource::<Received>().0.is_empty());
+
+    let entity = app.world_mut().spawn(Player).id();
+    app.step(DT);
+    let actions = app.loopback_client(2).receive();
+    assert_eq!(
+        acts(&actions),
+        vec![(ACTION_TYPE_SPAWN, entity.to_bits(), 1)]
+    );
+}
+
+#[test]
+fn test_resource() {
+    let mut app = server();
+    app.connect_loopback_client(1);
+    app.step(DT);
+    assert_synced(&mut app, 1);
+
+    app.insert_resource(Score { red: 1, blue: 0 });
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_RESOURCE, 0, 4)]);
+    let score: Score = serde_sbin::from_bytes(&actions[0].v).unwrap();
+    assert_eq!(score, Score { red: 1, blue: 0 });
+
+    app.step(DT);
+    assert!(app.loopback_client(1).receive().is_empty());
+
+    app.world_mut().resource_mut::<Score>().blue = 2;
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_RESOURCE, 0, 4)]);
+
+    app.connect_loopback_client(2);
+    app.step(DT);
+    let actions = app.loopback_client(2).receive();
+    assert_eq!(
+        acts(&actions),
+        vec![(ACTION_TYPE_RESOURCE, 0, 4), (ACTION_TYPE_SYNC, 0, 0)]
+    );
+    let score: Score = serde_sbin::from_bytes(&actions[0].v).unwrap();
+    assert_eq!(score, Score { red: 1, blue: 2 });
+}
+
+#[test]
+fn test_sender() {
+    use bevy::ecs::system::RunSystemOnce;
+
+    let mut app = server();
+    for client_id in [1, 2, 3] {
+        app.connect_loopback_client(client_id);
+    }
+    app.step(DT);
+    for client_id in [1, 2, 3] {
+        assert_synced(&mut app, client_id);
+    }
+
+    let mut query = app.world_mut().query::<(Entity, &AxonClient)>();
+    let rooms: Vec<(Entity, AxonRoom)> = query
+        .iter(app.world())
+        .map(|(entity, client)| (entity, AxonRoom(if client.id == 3 { 2 } else { 1 })))
+        .collect();
+    for (entity, room) in rooms {
+        app.world_mut().entity_mut(entity).insert(room);
+    }
+    let owned = app.world_mut().spawn(AxonOwner { client_id: 3 }).id();
+    let orphan = app.world_mut().spawn_empty().id();
+
+    let n = app
+        .world_mut()
+        .run_system_once(|mut sender: AxonSender| {
+            sender
+                .send_to_room(AxonRoom(1), 5, &Move { x: 1.0 })
+                .unwrap()
+        })
+        .unwrap();
+    assert_eq!(n, 2);
+    app.step(DT);
+    for client_id in [1, 2] {
+        let actions = app.loopback_client(client_id).receive();
+        assert_eq!(acts(&actions), vec![(ACTION_TYPE_INVOKE, 5, 3)]);
+    }
+    assert!(app.loopback_client(3).receive().is_empty());
+
+    let result = app
+        .world_mut()
+        .run_system_once(move |mut sender: AxonSender| {
+            sender.send_to_owner(owned, 6, &Move { x: 2.0 }).unwrap();
+            sender.send_to_owner(orphan, 6, &Move { x: 3.0 })
+        })
+        .unwrap();
+    assert!(matches!(result, Err(AxonSendError::NoOwner(e)) if e == orphan));
+    app.step(DT);
+    assert!(app.loopback_client(1).receive().is_empty());
+    let actions = app.loopback_client(3).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_INVOKE, 6, 3)]);
+    let event: Move = serde_sbin::from_bytes(&actions[0].v).unwrap();
+    assert_eq!(event.x, 2.0);
+}
+
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Interact {
+    force: u32,
+}
+
+impl AxonEvent for Interact {
+    fn axon_event_type() -> u32 {
+        6
+    }
+    fn axon_event_invoke(bytes: &[u8], commands: &mut Commands<'_, '_>) {
+        match serde_sbin::from_bytes::<Interact>(bytes) {
+            Ok(event) => commands.trigger(event),
+            Err(e) => warn!("axon event decode error: {}", e),
+        }
+    }
+    fn axon_event_invoke_entity(
+        bytes: &[u8],
+        entity: Entity,
+        client_id: u64,
+        commands: &mut Commands<'_, '_>,
+    ) {
+        match serde_sbin::from_bytes::<Interact>(bytes) {
+            Ok(event) => commands.trigger(AxonEntityEvent {
+                entity,
+                client_id,
+                event,
+            }),
+            Err(e) => warn!("axon event decode error: {}", e),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct Interactions(Vec<(Entity, u64, u32)>);
+
+#[derive(Resource, Default)]
+struct Unknown(Vec<AxonUnknownTargetEvent>);
+
+#[test]
+fn test_entity_event() {
+    let mut app = server();
+    app.add_axon_event::<Interact>();
+    app.init_resource::<Interactions>();
+    app.init_resource::<Unknown>();
+    app.add_observer(
+        |event: On<AxonUnknownTargetEvent>, mut unknown: ResMut<Unknown>| {
+            unknown.0.push(event.event().clone());
+        },
+    );
+    app.connect_loopback_client(1);
+    let door = app.world_mut().spawn(Player).id();
+    let other = app.world_mut().spawn(Player).id();
+    app.world_mut().entity_mut(door).observe(
+        |event: On<AxonEntityEvent<Interact>>, mut interactions: ResMut<Interactions>| {
+            interactions
+                .0
+                .push((event.entity, event.client_id, event.event.force));
+        },
+    );
+    app.step(DT);
+
+    app.loopback_client(1)
+        .invoke(door.to_bits(), &Interact { force: 3 })
+        .unwrap();
+    app.step(DT);
+    assert_eq!(app.world().resource::<Interactions>().0, vec![(door, 1, 3)]);
+    assert!(app.world().resource::<Unknown>().0.is_empty());
+
+    app.world_mut().despawn(other);
+    app.step(DT);
+    for id in [other.to_bits(), 12345] {
+        app.loopback_client(1)
+            .invoke(id, &Interact { force: 1 })
+            .unwrap();
+    }
+    app.step(DT);
+    assert_eq!(app.world().resource::<Interactions>().0.len(), 1);
+    assert_eq!(
+        app.world().resource::<Unknown>().0,
+        vec![
+            AxonUnknownTargetEvent {
+                client_id: 1,
+                id: other.to_bits(),
+                t: 6
+            },
+            AxonUnknownTargetEvent {
+                client_id: 1,
+                id: 12345,
+                t: 6
+            },
+        ]
+    );
+}
+
+fn object_type(app: &App, id: u64) -> Option<u32> {
+    app.world().resource::<AxonServerSnapshot>().object_type(id)
+}
+
+#[test]
+fn test_object_lifecycle() {
+    let mut app = server();
+    app.connect_loopback_client(1);
+    app.step(DT);
+    assert_synced(&mut app, 1);
+
+    let entity = app
+        .world_mut()
+        .spawn((Player, Position { x: 1.0, y: 2.0 }))
+        .id();
+    let id = entity.to_bits();
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(
+        acts(&actions),
+        vec![(ACTION_TYPE_SPAWN, id, 1), (ACTION_TYPE_CHANGE, id, 2)]
+    );
+    assert_eq!(object_type(&app, id), Some(1));
+
+    // Player 换成 Corpse：类型变更，变体保留
+    app.world_mut()
+        .entity_mut(entity)
+        .remove::<Player>()
+        .insert(Corpse);
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_RETYPE, id, 7)]);
+    assert_eq!(object_type(&app, id), Some(7));
+    let snapshot = read_axon_actions(&app.world().resource::<AxonServerSnapshot>().snapshot());
+    assert_eq!(
+        acts(&snapshot),
+        vec![(ACTION_TYPE_SPAWN, id, 7), (ACTION_TYPE_CHANGE, id, 2)]
+    );
+
+    // 多个对象类型时最后加入的为对外类型
+    app.world_mut().entity_mut(entity).insert(Player);
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_RETYPE, id, 1)]);
+    app.world_mut().entity_mut(entity).remove::<Player>();
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_RETYPE, id, 7)]);
+    assert_eq!(object_type(&app, id), Some(7));
+
+    // 移除最后一个对象组件，实体仍存活
+    app.world_mut().entity_mut(entity).remove::<Corpse>();
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_DESPAWN, id, 7)]);
+    assert_eq!(object_type(&app, id), None);
+    assert!(app.world().get_entity(entity).is_ok());
+
+    app.world_mut().entity_mut(entity).insert(Player);
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_SPAWN, id, 1)]);
+    assert_eq!(object_type(&app, id), Some(1));
+
+    // 带多个对象类型的实体被销毁只发送一次 DESPAWN
+    app.world_mut().entity_mut(entity).insert(Corpse);
+    app.step(DT);
+    app.loopback_client(1).receive();
+    app.world_mut().despawn(entity);
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_DESPAWN, id, 7)]);
+    assert_eq!(object_type(&app, id), None);
+    assert_eq!(
+        app.world().resource::<AxonServerSnapshot>().ids().count(),
+        0
+    );
+}
+
+#[test]
+fn test_persist_restore() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!(
+        "bevy_axon_loopback_persist_{}.axsv",
+        std::process::id()
+    ));
+    {
+        let mut app = server();
+        app.world_mut().spawn((Player, Position { x: 1.0, y: 2.0 }));
+        app.world_mut().spawn((Corpse, Position { x: 3.0, y: 4.0 }));
+        app.insert_resource(Score { red: 2, blue: 1 });
+        app.step(DT);
+        app.save_axon_world(&path).unwrap();
+    }
+
+    // Corpse 未注册为可持久化，被跳过
+    let mut app = server();
+    assert_eq!(app.load_axon_world(&path).unwrap(), 1);
+    std::fs::remove_file(&path).unwrap();
+    app.step(DT);
+
+    let mut query = app.world_mut().query_filtered::<&Position, With<Player>>();
+    let positions: Vec<&Position> = query.iter(app.world()).collect();
+    assert_eq!(positions, vec![&Position { x: 1.0, y: 2.0 }]);
+    assert_eq!(app.world().resource::<Score>(), &Score { red: 2, blue: 1 });
+
+    app.connect_loopback_client(1);
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    let kinds: Vec<(u8, u32)> = actions.iter().map(|v| (v.act, v.t)).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (ACTION_TYPE_RESOURCE, 4),
+            (ACTION_TYPE_SPAWN, 1),
+            (ACTION_TYPE_CHANGE, 2),
+            (ACTION_TYPE_SYNC, 0)
+        ]
+    );
+}
+
+#[test]
+fn test_persist_multi_object() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!(
+        "bevy_axon_loopback_persist_multi_{}.axsv",
+        std::process::id()
+    ));
+    let server = || {
+        let mut app = server();
+        app.add_axon_persistent_object::<Corpse>();
+        app
+    };
+    {
+        let mut app = server();
+        let entity = app
+            .world_mut()
+            .spawn((Player, Position { x: 1.0, y: 2.0 }))
+            .id();
+        app.step(DT);
+        app.world_mut().entity_mut(entity).insert(Corpse);
+        app.step(DT);
+        app.save_axon_world(&path).unwrap();
+    }
+
+    // 实体上的所有对象类型都被恢复，对外类型不变
+    let mut app = server();
+    assert_eq!(app.load_axon_world(&path).unwrap(), 1);
+    std::fs::remove_file(&path).unwrap();
+    app.step(DT);
+
+    let mut query = app
+        .world_mut()
+        .query_filtered::<(Entity, &Position), (With<Player>, With<Corpse>)>();
+    let (entity, position) = query.single(app.world()).unwrap();
+    assert_eq!(position, &Position { x: 1.0, y: 2.0 });
+    let id = entity.to_bits();
+    assert_eq!(object_type(&app, id), Some(7));
+
+    app.connect_loopback_client(1);
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(
+        acts(&actions),
+        vec![
+            (ACTION_TYPE_SPAWN, id, 7),
+            (ACTION_TYPE_CHANGE, id, 2),
+            (ACTION_TYPE_SYNC, 0, 0)
+        ]
+    );
+
+    // 移除 Corpse 后退回 Player
+    app.world_mut().entity_mut(entity).remove::<Corpse>();
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(acts(&actions), vec![(ACTION_TYPE_RETYPE, id, 1)]);
+}
+
+#[test]
+fn test_join_chunks() {
+    let mut app = server();
+    let entities: Vec<Entity> = (0..4)
+        .map(|i| {
+            app.world_mut()
+                .spawn((
+                    Player,
+                    Position {
+                        x: i as f32,
+                        y: 0.0,
+                    },
+                ))
+                .id()
+        })
+        .collect();
+    app.step(DT);
+
+    // 每帧只发送一个对象
+    let mut config = app.world_mut().resource_mut::<AxonJoinConfig>();
+    config.chunk_size = 1;
+    config.frame_budget = 1;
+    app.connect_loopback_client(1);
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(actions.len(), 2);
+    assert_eq!(actions[0].act, ACTION_TYPE_SPAWN);
+    let sent = Entity::from_bits(actions[0].id);
+    let pending: Vec<Entity> = entities.into_iter().filter(|v| *v != sent).collect();
+
+    // 未发出的对象：销毁的不再发送，变更合并进快照；已发出的对象照常收到更新；
+    // 发往未发出对象的事件先带出该对象
+    app.world_mut().despawn(pending[0]);
+    app.world_mut().get_mut::<Position>(pending[1]).unwrap().y = 5.0;
+    app.world_mut().get_mut::<Position>(sent).unwrap().y = 7.0;
+    app.send_axon_client_event(pending[2].to_bits(), &Move { x: 1.0 }, 1)
+        .unwrap();
+    app.step(DT);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(
+        acts(&actions),
+        vec![
+            (ACTION_TYPE_SPAWN, pending[2].to_bits(), 1),
+            (ACTION_TYPE_CHANGE, pending[2].to_bits(), 2),
+            (ACTION_TYPE_INVOKE, pending[2].to_bits(), 3),
+            (ACTION_TYPE_CHANGE, sent.to_bits(), 2),
+            (ACTION_TYPE_SPAWN, pending[1].to_bits(), 1),
+            (ACTION_TYPE_CHANGE, pending[1].to_bits(), 2),
+            (ACTION_TYPE_SYNC, 0, 0)
+        ]
+    );
+    let pos: Position = serde_sbin::from_bytes(&actions[5].v).unwrap();
+    assert_eq!(pos.y, 5.0);
+
+    app.step(DT);
+    assert!(app.loopback_client(1).receive().is_empty());
+}
+
+#[test]
+fn test_compression() {
+    let mut app = server();
+    for i in 0..64 {
+        app.world_mut().spawn((
+            Player,
+            Position {
+                x: i as f32,
+                y: 0.0,
+            },
+        ));
+    }
+    app.step(DT);
+
+    app.connect_loopback_client(1);
+    app.connect_loopback_client_with_flags(2, AXON_FLAG_COMPRESSION);
+    app.step(DT);
+    let server = app.world().resource::<AxonServer>();
+    assert!(!server.is_compressed(1));
+    assert!(server.is_compressed(2));
+
+    let raw = app.loopback_client(1).receive();
+    let compressed = app.loopback_client(2).receive();
+    assert_eq!(raw.len(), 64 * 2 + 1);
+    assert_eq!(raw, compressed);
+
+    // 无法解码的消息被跳过
+    let mut transport = app.world().resource::<AxonLoopback>().server().clone();
+    transport.send(
+        2,
+        AXON_CHANNEL_RELIABLE_ORDERED,
+        vec![AXON_MESSAGE_LZ4, 0xff, 0xff, 0xff, 0xff],
+    );
+    assert!(app.loopback_client(2).receive().is_empty());
+
+    // 不带消息头的原始动作流（来自不支持压缩的服务器）按原样处理
+    transport.send(
+        2,
+        AXON_CHANNEL_RELIABLE_ORDERED,
+        encode_axon_action(ACTION_TYPE_SYNC, 0, 0, &[]),
+    );
+    assert_eq!(
+        acts(&app.loopback_client(2).receive()),
+        vec![(ACTION_TYPE_SYNC, 0, 0)]
+    );
+
+    let entity = app.world_mut().spawn(Player).id();
+    app.step(DT);
+    for client_id in [1, 2] {
+        let actions = app.loopback_client(client_id).receive();
+        assert_eq!(
+            acts(&actions),
+            vec![(ACTION_TYPE_SPAWN, entity.to_bits(), 1)]
+        );
+    }
+}
@@ -0,0 +1,187 @@
+#![cfg(all(feature = "server", feature = "websocket"))]
+
+use bevy::prelude::*;
+use bevy_axon::core::*;
+use bevy_axon::loopback::*;
+use bevy_axon::protocol::*;
+use bevy_axon::server::*;
+use bevy_axon::transport::*;
+use bevy_axon::websocket::*;
+use serde::{Deserialize, Serialize};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const DT: Duration = Duration::from_millis(16);
+
+#[derive(Component)]
+struct Player;
+
+impl AxonObject for Player {
+    fn axon_object_type() -> u32 {
+        1
+    }
+}
+
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Move {
+    x: f32,
+}
+
+impl AxonEvent for Move {
+    fn axon_event_type() -> u32 {
+        3
+    }
+    fn axon_event_invoke(bytes: &[u8], commands: &mut Commands<'_, '_>) {
+        match serde_sbin::from_bytes::<Move>(bytes) {
+            Ok(event) => commands.trigger(event),
+            Err(e) => warn!("axon event decode error: {}", e),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct Received(Vec<Move>);
+
+fn server() -> (App, String) {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_plugins((AxonPlugin, AxonServerPlugin));
+    app.add_axon_object::<Player>();
+    app.add_axon_event::<Move>();
+    app.init_resource::<Received>();
+    app.add_observer(|event: On<Move>, mut received: ResMut<Received>| {
+        received.0.push(event.event().clone());
+    });
+    app.start_loopback_server();
+    let mut transport = AxonWebSocketServerTransport::bind("127.0.0.1:0").unwrap();
+    transport.set_auth(|client_id, _| client_id < 100);
+    let addr = transport.local_addr().unwrap();
+    app.add_axon_transport(transport);
+    (app, format!("ws://{}", addr))
+}
+
+/// 运行服务器直到 f 返回 true
+fn run_until(
+    app: &mut App,
+    client: &mut AxonWebSocketClientTransport,
+    mut f: impl FnMut(&mut App, &mut AxonWebSocketClientTransport) -> bool,
+) {
+    for _ in 0..500 {
+        app.step(DT);
+        let _ = client.update(DT);
+        client.flush().unwrap();
+        if f(app, client) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(2));
+    }
+    panic!("timed out");
+}
+
+fn receive(client: &mut AxonWebSocketClientTransport) -> Vec<AxonAction> {
+    let mut actions = Vec::new();
+    while let Some(message) = client.receive(AXON_CHANNEL_RELIABLE_ORDERED) {
+        actions.extend(read_axon_actions(&message));
+    }
+    actions
+}
+
+#[test]
+fn test_websocket_alongside_loopback() {
+    let (mut app, url) = server();
+    app.connect_loopback_client(1);
+    let mut client =
+        AxonWebSocketClientTransport::connect(&format!("{}/?client_id=2", url)).unwrap();
+
+    run_until(&mut app, &mut client, |app, _| {
+        let mut query = app.world_mut().query::<&AxonClient>();
+        query.iter(app.world()).count() == 2
+    });
+    assert!(client.is_connected());
+    let mut actions = Vec::new();
+    run_until(&mut app, &mut client, |_, client| {
+        actions.extend(receive(client));
+        !actions.is_empty()
+    });
+    assert_eq!(actions[0].act, ACTION_TYPE_SYNC);
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(actions[0].act, ACTION_TYPE_SYNC);
+
+    let entity = app.world_mut().spawn(Player).id();
+    let mut actions = Vec::new();
+    run_until(&mut app, &mut client, |_, client| {
+        actions.extend(receive(client));
+        !actions.is_empty()
+    });
+    assert_eq!(actions[0].act, ACTION_TYPE_SPAWN);
+    assert_eq!(actions[0].id, entity.to_bits());
+    let actions = app.loopback_client(1).receive();
+    assert_eq!(actions[0].act, ACTION_TYPE_SPAWN);
+
+    let v = serde_sbin::to_bytes(&Move { x: 4.0 }).unwrap();
+    client.send(
+        AXON_CHANNEL_RELIABLE_ORDERED,
+        encode_axon_action(ACTION_TYPE_INVOKE, 0, 3, &v),
+    );
+    run_until(&mut app, &mut client, |app, _| {
+        !app.world().resource::<Received>().0.is_empty()
+    });
+    assert_eq!(app.world().resource::<Received>().0, vec![Move { x: 4.0 }]);
+
+    client.disconnect();
+    run_until(&mut app, &mut client, |app, _| {
+        let mut query = app.world_mut().query::<&AxonClient>();
+        let ids: Vec<u64> = query.iter(app.world()).map(|v| v.id).collect();
+        ids == vec![1]
+    });
+}
+
+#[test]
+fn test_websocket_spectator() {
+    let (mut app, url) = server();
+    let mut client = AxonWebSocketClientTransport::connect(&format!(
+        "{}/?client_id=5&flags={}",
+        url, AXON_FLAG_SPECTATOR
+    ))
+    .unwrap();
+    run_until(&mut app, &mut client, |app, _| {
+        let mut query = app.world_mut().query::<&AxonSpectator>();
+        query.iter(app.world()).map(|v| v.id).collect::<Vec<_>>() == vec![5]
+    });
+}
+
+#[test]
+fn test_websocket_client_id_auth() {
+    let (mut app, url) = server();
+    // 未通过校验的自选 id 在握手时被拒绝
+    let mut client =
+        AxonWebSocketClientTransport::connect(&format!("{}/?client_id=200", url)).unwrap();
+    run_until(&mut app, &mut client, |_, client| client.is_disconnected());
+
+    // 不带 client_id 时由服务器分配
+    let mut client = AxonWebSocketClientTransport::connect(&url).unwrap();
+    run_until(&mut app, &mut client, |app, _| {
+        let mut query = app.world_mut().query::<&AxonClient>();
+        query.iter(app.world()).count() == 1
+    });
+    let mut query = app.world_mut().query::<&AxonClient>();
+    let ids: Vec<u64> = query.iter(app.world()).map(|v| v.id).collect();
+    assert!(ids[0] > 1 << 63);
+}
+
+#[test]
+fn test_websocket_handshake_limits() {
+    let mut transport = AxonWebSocketServerTransport::bind("127.0.0.1:0").unwrap();
+    transport.set_handshake_limits(1, Duration::from_millis(50));
+    let addr = transport.local_addr().unwrap();
+    // 只建立 TCP 连接，不发送握手请求
+    let _a = TcpStream::connect(addr).unwrap();
+    let _b = TcpStream::connect(addr).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    transport.update(DT).unwrap();
+    assert_eq!(transport.pending_handshakes(), 1);
+
+    std::thread::sleep(Duration::from_millis(60));
+    transport.update(DT).unwrap();
+    assert_eq!(transport.pending_handshakes(), 0);
+}
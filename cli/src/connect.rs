@@ -1,3 +1,4 @@
+use bevy_axon::protocol::*;
 use renet::{ConnectionConfig, DefaultChannel, RenetClient};
 use renet_netcode::{ClientAuthentication, NetcodeClientTransport};
 use serde::Deserialize;
@@ -5,20 +6,27 @@ use serde_bytes::ByteBuf;
 use std::net::UdpSocket;
 use std::time::{Duration, Instant, SystemTime};
 
+fn on_message(message: &[u8]) {
+    match axon_decode_message(message) {
+        Some(bytes) => on_raw_data(&bytes),
+        None => println!("invalid message: {:?}", message.len()),
+    }
+}
+
 fn on_raw_data(bytes: &[u8]) {
     println!("raw data: {:?}", bytes.len());
     let mut deserializer = serde_sbin::SbinDeserializer::from_bytes(bytes);
     loop {
-        let act:Result<u8, _> = Deserialize::deserialize(&mut deserializer);
+        let act: Result<u8, _> = Deserialize::deserialize(&mut deserializer);
         if let Ok(act) = act {
             println!("act: {:?}", act);
-            let id:Result<u64, _> = Deserialize::deserialize(&mut deserializer);
+            let id: Result<u64, _> = Deserialize::deserialize(&mut deserializer);
             if let Ok(id) = id {
                 println!("id: {:?}", id);
-                let t:Result<u32, _> = Deserialize::deserialize(&mut deserializer);
+                let t: Result<u32, _> = Deserialize::deserialize(&mut deserializer);
                 if let Ok(t) = t {
                     println!("t: {:?}", t);
-                    let data:Result<ByteBuf, _> = Deserialize::deserialize(&mut deserializer);
+                    let data: Result<ByteBuf, _> = Deserialize::deserialize(&mut deserializer);
                     if let Ok(data) = data {
                         println!("data: {:?}", data.len());
                     }
@@ -30,7 +38,7 @@ fn on_raw_data(bytes: &[u8]) {
     }
 }
 
-pub fn run(addr: &str) {
+pub fn run(addr: &str, spectator: bool) {
     let server_addr = addr.parse().unwrap();
     let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
     socket.set_nonblocking(true).unwrap();
@@ -43,7 +51,11 @@ pub fn run(addr: &str) {
     let authentication = ClientAuthentication::Unsecure {
         server_addr,
         client_id: 0,
-        user_data: None,
+        user_data: Some(axon_user_data(if spectator {
+            AXON_FLAG_SPECTATOR | axon_compression_flag()
+        } else {
+            axon_compression_flag()
+        })),
         protocol_id: 0,
     };
 
@@ -59,15 +71,15 @@ pub fn run(addr: &str) {
 
         if client.is_connected() {
             while let Some(message) = client.receive_message(DefaultChannel::ReliableOrdered) {
-                on_raw_data(&message);
+                on_message(&message);
             }
 
             while let Some(message) = client.receive_message(DefaultChannel::ReliableUnordered) {
-                on_raw_data(&message);
+                on_message(&message);
             }
 
             while let Some(message) = client.receive_message(DefaultChannel::Unreliable) {
-                on_raw_data(&message);
+                on_message(&message);
             }
 
             transport.send_packets(&mut client).unwrap();
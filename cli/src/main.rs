@@ -1,5 +1,7 @@
 mod connect;
 mod metadata;
+#[cfg(feature = "replay")]
+mod replay;
 
 use clap::{Parser, Subcommand};
 
@@ -18,6 +20,9 @@ enum Commands {
         /// Server address (e.g., 127.0.0.1:7777)
         #[arg(default_value = "127.0.0.1:7777")]
         addr: String,
+        /// Connect as a read-only spectator
+        #[arg(long)]
+        spectator: bool,
     },
     /// Extract metadata from source directory
     Metadata {
@@ -28,17 +33,33 @@ enum Commands {
         #[arg(default_value = "metadata.json")]
         output: String,
     },
+    /// Serve a recorded replication stream to clients
+    #[cfg(feature = "replay")]
+    Replay {
+        /// Recording file created by AxonRecorder
+        file: String,
+        /// Server address (e.g., 127.0.0.1:7777)
+        #[arg(default_value = "127.0.0.1:7777")]
+        addr: String,
+        /// Playback speed
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Connect { addr } => {
-            connect::run(&addr);
+        Commands::Connect { addr, spectator } => {
+            connect::run(&addr, spectator);
         }
         Commands::Metadata { src, output } => {
             metadata::run(&src, &output);
         }
+        #[cfg(feature = "replay")]
+        Commands::Replay { file, addr, speed } => {
+            replay::run(&file, &addr, speed);
+        }
     }
 }
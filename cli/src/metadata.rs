@@ -8,6 +8,7 @@ pub struct Metadata {
     pub o: Vec<Info>,
     pub v: Vec<Info>,
     pub e: Vec<Info>,
+    pub r: Vec<Info>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -44,6 +45,7 @@ pub fn run(src: &str, dst: &str) {
         o: Vec::new(),
         v: Vec::new(),
         e: Vec::new(),
+        r: Vec::new(),
     };
 
     if let Err(e) = collect_files(src_path, &mut metadata, &crate_name, &project_root) {
@@ -61,6 +63,7 @@ pub fn run(src: &str, dst: &str) {
             println!("  - {} AxonObject found", metadata.o.len());
             println!("  - {} AxonVariant found", metadata.v.len());
             println!("  - {} AxonEvent found", metadata.e.len());
+            println!("  - {} AxonResource found", metadata.r.len());
         }
         Err(e) => {
             eprintln!("Error serializing metadata: {}", e);
@@ -174,8 +177,9 @@ fn parse_rust_file(content: &str, metadata: &mut Metadata, module_path: &str) {
             let has_axon_object = has_derive(&item_struct.attrs, "AxonObject");
             let has_axon_variant = has_derive(&item_struct.attrs, "AxonVariant");
             let has_axon_event = has_derive(&item_struct.attrs, "AxonEvent");
+            let has_axon_resource = has_derive(&item_struct.attrs, "AxonResource");
 
-            if !has_axon_object && !has_axon_variant && !has_axon_event {
+            if !has_axon_object && !has_axon_variant && !has_axon_event && !has_axon_resource {
                 continue;
             }
 
@@ -204,6 +208,9 @@ fn parse_rust_file(content: &str, metadata: &mut Metadata, module_path: &str) {
             if has_axon_event {
                 metadata.e.push(info.clone());
             }
+            if has_axon_resource {
+                metadata.r.push(info.clone());
+            }
         }
     }
 }
@@ -329,7 +336,7 @@ fn format_type(ty: &syn::Type, all_items: &[&Item]) -> (String, Vec<FieldInfo>)
             )
         }
         syn::Type::Tuple(type_tuple) => {
-            let types: Vec<String> = type_tuple.elems.iter().map(|t| get_type_name(t)).collect();
+            let types: Vec<String> = type_tuple.elems.iter().map(get_type_name).collect();
             (format!("({})", types.join(", ")), Vec::new())
         }
         syn::Type::Reference(type_ref) => {
@@ -380,7 +387,7 @@ fn get_type_name(ty: &syn::Type) -> String {
 fn find_struct_fields(type_name: &str, all_items: &[&Item]) -> Vec<FieldInfo> {
     for item in all_items {
         if let Item::Struct(item_struct) = item {
-            if item_struct.ident.to_string() == type_name {
+            if item_struct.ident == type_name {
                 return extract_fields(&item_struct.fields, all_items);
             }
         }
@@ -0,0 +1,103 @@
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::prelude::*;
+use bevy_axon::record::*;
+use bevy_axon::server::*;
+use std::io::BufRead;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Mutex;
+use std::time::Duration;
+
+enum ReplayCommand {
+    Pause,
+    Resume,
+    Seek(f64),
+    Speed(f64),
+    Status,
+    Quit,
+}
+
+#[derive(Resource)]
+struct ReplayInput(Mutex<Receiver<ReplayCommand>>);
+
+fn parse_command(line: &str) -> Option<ReplayCommand> {
+    let mut parts = line.split_whitespace();
+    let cmd = parts.next()?;
+    let arg = parts.next().and_then(|v| v.parse::<f64>().ok());
+    match (cmd, arg) {
+        ("p" | "pause", _) => Some(ReplayCommand::Pause),
+        ("r" | "resume", _) => Some(ReplayCommand::Resume),
+        ("seek", Some(v)) => Some(ReplayCommand::Seek(v)),
+        ("speed", Some(v)) => Some(ReplayCommand::Speed(v)),
+        ("s" | "status", _) => Some(ReplayCommand::Status),
+        ("q" | "quit", _) => Some(ReplayCommand::Quit),
+        _ => None,
+    }
+}
+
+fn replay_input_system(
+    input: Res<ReplayInput>,
+    mut replay: ResMut<AxonReplay>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    let rx = input.0.lock().unwrap();
+    while let Ok(cmd) = rx.try_recv() {
+        match cmd {
+            ReplayCommand::Pause => replay.pause(),
+            ReplayCommand::Resume => replay.resume(),
+            ReplayCommand::Seek(v) => replay.seek(v),
+            ReplayCommand::Speed(v) => replay.set_speed(v),
+            ReplayCommand::Status => {}
+            ReplayCommand::Quit => {
+                exit.write(AppExit::Success);
+            }
+        }
+        println!(
+            "{:.2}/{:.2}s speed: {} {}",
+            replay.position(),
+            replay.duration(),
+            replay.speed(),
+            if replay.is_paused() { "paused" } else { "playing" }
+        );
+    }
+}
+
+pub fn run(file: &str, addr: &str, speed: f64) {
+    let mut replay = match AxonReplay::open(file) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Error opening recording '{}': {}", file, e);
+            std::process::exit(1);
+        }
+    };
+    replay.set_speed(speed);
+    println!("Replaying '{}' ({:.2}s) on {}", file, replay.duration(), addr);
+    println!("Commands: pause | resume | seek <secs> | speed <x> | status | quit");
+
+    let (tx, rx) = channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            match parse_command(&line) {
+                Some(cmd) => {
+                    if tx.send(cmd).is_err() {
+                        break;
+                    }
+                }
+                None => eprintln!("unknown command: {}", line),
+            }
+        }
+    });
+
+    let mut app = App::new();
+    app.add_plugins(
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+            1.0 / 30.0,
+        ))),
+    );
+    app.add_plugins(AxonReplayPlugin);
+    app.insert_resource(replay);
+    app.insert_resource(ReplayInput(Mutex::new(rx)));
+    app.add_systems(Update, replay_input_system);
+    app.start_server(addr);
+    app.run();
+}
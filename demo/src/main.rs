@@ -3,8 +3,6 @@ use bevy::prelude::*;
 use bevy_axon::core::*;
 use bevy_axon::server::*;
 use bevy_axon_derive::*;
-use bevy_renet::netcode::NetcodeServerPlugin;
-use bevy_renet::RenetServerPlugin;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -83,9 +81,8 @@ fn main() {
         ))),
     );
 
-    app.add_plugins(AxonPlugin::default());
-    app.add_plugins(AxonServerPlugin::default());
-    app.add_plugins((RenetServerPlugin, NetcodeServerPlugin));
+    app.add_plugins(AxonPlugin);
+    app.add_plugins(AxonServerPlugin);
 
     app.add_axon_object::<Player>();
     app.add_axon_variant::<Position>();
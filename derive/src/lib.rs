@@ -7,6 +7,7 @@ pub fn axon_object_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as syn::DeriveInput);
     let name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = &input.generics.split_for_impl();
+    let schema = schema_fields(&input.data);
 
     let expanded = quote! {
         impl #impl_generics ::bevy_axon::core::AxonObject for #name #ty_generics #where_clause {
@@ -25,6 +26,9 @@ pub fn axon_object_derive(input: TokenStream) -> TokenStream {
                 const HASH: u32 = const_hash(FULL_NAME);
                 HASH
             }
+            fn axon_object_schema() -> &'static [::bevy_axon::core::AxonField] {
+                &[#(#schema),*]
+            }
         }
     };
 
@@ -36,6 +40,7 @@ pub fn axon_variant_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as syn::DeriveInput);
     let name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = &input.generics.split_for_impl();
+    let schema = schema_fields(&input.data);
 
     let expanded = quote! {
         impl #impl_generics ::bevy_axon::core::AxonVariant for #name #ty_generics #where_clause {
@@ -54,6 +59,9 @@ pub fn axon_variant_derive(input: TokenStream) -> TokenStream {
                 const HASH: u32 = const_hash(FULL_NAME);
                 HASH
             }
+            fn axon_variant_schema() -> &'static [::bevy_axon::core::AxonField] {
+                &[#(#schema),*]
+            }
         }
     };
 
@@ -65,6 +73,7 @@ pub fn axon_event_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as syn::DeriveInput);
     let name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = &input.generics.split_for_impl();
+    let schema = schema_fields(&input.data);
 
     let expanded = quote! {
         impl #impl_generics ::bevy_axon::core::AxonEvent for #name #ty_generics #where_clause {
@@ -83,12 +92,88 @@ pub fn axon_event_derive(input: TokenStream) -> TokenStream {
                 const HASH: u32 = const_hash(FULL_NAME);
                 HASH
             }
+            fn axon_event_schema() -> &'static [::bevy_axon::core::AxonField] {
+                &[#(#schema),*]
+            }
             fn axon_event_invoke(bytes: &[u8],  commands: &mut ::bevy::prelude::Commands<'_, '_>) {
-                let event = ::serde_sbin::from_bytes::<Self>(bytes).unwrap();
-                commands.trigger(event);
+                match ::serde_sbin::from_bytes::<Self>(bytes) {
+                    Ok(event) => commands.trigger(event),
+                    Err(e) => ::bevy::prelude::warn!("axon event decode error: {}", e),
+                }
+            }
+            fn axon_event_invoke_entity(
+                bytes: &[u8],
+                entity: ::bevy::prelude::Entity,
+                client_id: u64,
+                commands: &mut ::bevy::prelude::Commands<'_, '_>,
+            ) {
+                match ::serde_sbin::from_bytes::<Self>(bytes) {
+                    Ok(event) => commands.trigger(::bevy_axon::core::AxonEntityEvent {
+                        entity,
+                        client_id,
+                        event,
+                    }),
+                    Err(e) => ::bevy::prelude::warn!("axon event decode error: {}", e),
+                }
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+#[proc_macro_derive(AxonResource)]
+pub fn axon_resource_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = &input.generics.split_for_impl();
+    let schema = schema_fields(&input.data);
+
+    let expanded = quote! {
+        impl #impl_generics ::bevy_axon::core::AxonResource for #name #ty_generics #where_clause {
+            fn axon_resource_type() -> u32 {
+                const fn const_hash(s: &str) -> u32 {
+                    let bytes = s.as_bytes();
+                    let mut hash: u32 = 5381;
+                    let mut i = 0;
+                    while i < bytes.len() {
+                        hash = hash.wrapping_mul(33).wrapping_add(bytes[i] as u32);
+                        i += 1;
+                    }
+                    hash
+                }
+                const FULL_NAME: &str = concat!(module_path!(), "::", stringify!(#name));
+                const HASH: u32 = const_hash(FULL_NAME);
+                HASH
+            }
+            fn axon_resource_schema() -> &'static [::bevy_axon::core::AxonField] {
+                &[#(#schema),*]
             }
         }
     };
 
     TokenStream::from(expanded)
 }
+
+fn schema_fields(data: &syn::Data) -> Vec<proc_macro2::TokenStream> {
+    let fields = match data {
+        syn::Data::Struct(data) => &data.fields,
+        _ => return Vec::new(),
+    };
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let name = field
+                .ident
+                .as_ref()
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| format!("_{}", i));
+            let ty = &field.ty;
+            let ty = quote!(#ty).to_string().replace(' ', "");
+            quote! {
+                ::bevy_axon::core::AxonField { name: #name, ty: #ty }
+            }
+        })
+        .collect()
+}
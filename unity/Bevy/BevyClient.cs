@@ -10,6 +10,78 @@ using UnityEngine.Serialization;
 
 namespace Bevy
 {
+    /// <summary>
+    /// 与 Rust 端 AxonFfiError 一致的返回码
+    /// </summary>
+    public enum AxonFfiError
+    {
+        Ok = 0,
+        NullPointer = 1,
+        InvalidArgument = 2,
+        InvalidUtf8 = 3,
+        Transport = 4,
+        NotConnected = 5,
+        Decode = 6,
+        End = 7,
+        NotFound = 8,
+        Panic = 9,
+    }
+
+    /// <summary>
+    /// 与 Rust 端 AxonFfiState 一致的连接状态
+    /// </summary>
+    public enum AxonFfiState
+    {
+        Connecting = 0,
+        Connected = 1,
+        Disconnected = 2,
+    }
+
+    /// <summary>
+    /// 与 Rust 端 AxonDisconnectReason 一致的断开原因
+    /// </summary>
+    public enum AxonDisconnectReason
+    {
+        None = 0,
+        DisconnectedByClient = 1,
+        DisconnectedByServer = 2,
+        ConnectionDenied = 3,
+        ConnectTimedOut = 4,
+        ConnectionTimedOut = 5,
+        ConnectTokenExpired = 6,
+        Transport = 7,
+        Protocol = 8,
+        Channel = 9,
+        Panicked = 10,
+    }
+
+    /// <summary>
+    /// 与 Rust 端 AxonChannelStats 一致的通道消息计数
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct AxonChannelStats
+    {
+        public byte ChannelId;
+        public ulong MessagesSent;
+        public ulong MessagesReceived;
+    }
+
+    /// <summary>
+    /// 与 Rust 端 AxonNetStats 一致的网络统计
+    /// </summary>
+    [StructLayout(LayoutKind.Sequential)]
+    public struct AxonNetStats
+    {
+        public double RttMs;
+        public double PacketLoss;
+        public double BytesSentPerSecond;
+        public double BytesReceivedPerSecond;
+        public UIntPtr ChannelCount;
+
+        [MarshalAs(UnmanagedType.ByValArray, SizeConst = 8)]
+        public AxonChannelStats[] Channels;
+    }
+
     public class BevyClient : MonoBehaviour
     {
         private static readonly Dictionary<uint, Type> TypeSet = new();
@@ -28,6 +100,17 @@ namespace Bevy
         private const byte ActionTypeDespawn = 2;
         private const byte ActionTypeChange = 3;
         private const byte ActionTypeInvoke = 4;
+        private const byte ActionTypeRetype = 6;
+        private const byte ActionTypeSync = 7;
+
+        // 与 bevy_axon 的 AXON_CHANNEL_* 一致
+        private const byte ChannelUnreliable = 0;
+        private const byte ChannelReliableOrdered = 2;
+
+        /// <summary>
+        /// 入场快照已全部收到
+        /// </summary>
+        public event Action Synced;
 
         private readonly Dictionary<ulong, BevyObject> _objectSet = new();
 
@@ -40,7 +123,7 @@ namespace Bevy
 
         public string GetErrorMessage()
         {
-            var p = bevy_axon_ffi_errmsg();
+            var p = bevy_axon_ffi_errmsg(_client);
             return p == IntPtr.Zero ? null : Marshal.PtrToStringAnsi(p);
         }
 
@@ -54,8 +137,13 @@ namespace Bevy
 
             if (string.IsNullOrEmpty(addr)) return;
             var cAddr = Marshal.StringToHGlobalAnsi(addr);
-            _client = bevy_axon_ffi_create(cAddr, _clientId);
+            var err = bevy_axon_ffi_create(cAddr, _clientId, out _client);
             Marshal.FreeHGlobal(cAddr);
+            if (err != AxonFfiError.Ok)
+            {
+                Debug.LogError($"Connect {addr} failed: {err} {GetErrorMessage()}");
+                return;
+            }
 
             foreach (var v in _objectSet.Values)
             {
@@ -70,6 +158,7 @@ namespace Bevy
         public void Disconnect()
         {
             if (_client == IntPtr.Zero) return;
+            bevy_axon_ffi_disconnect(_client);
             bevy_axon_ffi_exit(_client);
             _client = IntPtr.Zero;
         }
@@ -90,6 +179,21 @@ namespace Bevy
 
         public bool IsConnected => _client != IntPtr.Zero && bevy_axon_ffi_is_connected(_client) != 0;
 
+        public AxonFfiState State => bevy_axon_ffi_state(_client);
+
+        public AxonDisconnectReason DisconnectReason => bevy_axon_ffi_disconnect_reason(_client);
+
+        // 每次 FixedUpdate 刷新，未连接时为默认值
+        public AxonNetStats Stats
+        {
+            get
+            {
+                if (_client == IntPtr.Zero || bevy_axon_ffi_stats(_client, out var stats) != AxonFfiError.Ok)
+                    return default;
+                return stats;
+            }
+        }
+
 
         private static int Find(ArraySegment<byte> data, int off, int c)
         {
@@ -102,112 +206,154 @@ namespace Bevy
         }
 
         // ReSharper disable Unity.PerformanceAnalysis
-        private void OnRawData(ArraySegment<byte> data)
+        private void OnAction(byte act, ulong id, uint t, byte[] d)
         {
-            var sb = new SbinReader(data);
-            try
+            switch (act)
             {
-                while (true)
+                case ActionTypeSpawn when _objectSet.ContainsKey(id):
+                    return;
+                case ActionTypeSpawn:
                 {
-                    var act = sb.ReadU8();
-                    var id = sb.ReadU64();
-                    var t = sb.ReadU32();
-                    var d = sb.ReadBytes();
+                    var item = prefabs.FirstOrDefault(v => v.typeId == t);
+                    if (!item) return;
+                    var v = Instantiate(item.gameObject, transform);
+                    var s = v.GetComponent<BevyObject>();
+                    if (!s)
+                    {
+                        Destroy(v);
+                        return;
+                    }
 
-                    switch (act)
+                    s.Id = id;
+                    var tr = v.transform;
+                    tr.localPosition = Vector3.zero;
+                    tr.localScale = Vector3.one;
+                    tr.localRotation = Quaternion.identity;
+                    _objectSet[s.Id] = s;
+                    Debug.Log($"spawn {id}");
+                    break;
+                }
+                case ActionTypeDespawn:
+                {
+                    if (!_objectSet.Remove(id, out var v)) return;
+                    Destroy(v.gameObject);
+                    Debug.Log($"despawn {id}");
+                    break;
+                }
+                case ActionTypeRetype:
+                {
+                    // 换成新类型的 prefab，保留已同步的变体
+                    if (!_objectSet.TryGetValue(id, out var old)) return;
+                    var item = prefabs.FirstOrDefault(v => v.typeId == t);
+                    if (!item) return;
+                    var v = Instantiate(item.gameObject, transform);
+                    var s = v.GetComponent<BevyObject>();
+                    if (!s)
                     {
-                        case ActionTypeSpawn when _objectSet.ContainsKey(id):
-                            continue;
-                        case ActionTypeSpawn:
-                        {
-                            var item = prefabs.FirstOrDefault(v => v.typeId == t);
-                            if (!item) continue;
-                            var v = Instantiate(item.gameObject, transform);
-                            var s = v.GetComponent<BevyObject>();
-                            if (!s)
-                            {
-                                Destroy(v);
-                                continue;
-                            }
-
-                            s.Id = id;
-                            var tr = v.transform;
-                            tr.localPosition = Vector3.zero;
-                            tr.localScale = Vector3.one;
-                            tr.localRotation = Quaternion.identity;
-                            _objectSet[s.Id] = s;
-                            Debug.Log($"spawn {id}");
-                            break;
-                        }
-                        case ActionTypeDespawn:
-                        {
-                            if (!_objectSet.Remove(id, out var v)) continue;
-                            Destroy(v.gameObject);
-                            Debug.Log($"despawn {id}");
-                            break;
-                        }
-                        case ActionTypeChange:
-                        {
-                            if (!_objectSet.TryGetValue(id, out var v)) continue;
-                            if (!TypeSet.TryGetValue(t, out var tt)) continue;
-                            try
-                            {
-                                v.SetValue(t, new SbinReader(d).ReadSerializable(tt));
-                            }
-                            catch (Exception e)
-                            {
-                                Debug.LogError(e);
-                            }
-
-                            break;
-                        }
-                        case ActionTypeInvoke:
-                            break;
+                        Destroy(v);
+                        return;
                     }
+
+                    s.Id = id;
+                    var tr = v.transform;
+                    tr.localPosition = Vector3.zero;
+                    tr.localScale = Vector3.one;
+                    tr.localRotation = Quaternion.identity;
+                    old.CopyValuesTo(s);
+                    Destroy(old.gameObject);
+                    _objectSet[id] = s;
+                    Debug.Log($"retype {id} -> {t}");
+                    break;
                 }
-            }
-            catch (EndOfStreamException)
-            {
-            }
-            catch (Exception e)
-            {
-                Debug.LogError(e);
+                case ActionTypeChange:
+                {
+                    if (!_objectSet.TryGetValue(id, out var v)) return;
+                    if (!TypeSet.TryGetValue(t, out var tt)) return;
+                    try
+                    {
+                        v.SetValue(t, new SbinReader(d).ReadSerializable(tt));
+                    }
+                    catch (Exception e)
+                    {
+                        Debug.LogError(e);
+                    }
+
+                    break;
+                }
+                case ActionTypeInvoke:
+                    break;
+                case ActionTypeSync:
+                    Synced?.Invoke();
+                    break;
             }
         }
 
         private void FixedUpdate()
         {
             if (_client == IntPtr.Zero) return;
-            if (_invoke.Length > 0)
+            Send(_invoke, ChannelReliableOrdered);
+            Send(_invokeUnreliable, ChannelUnreliable);
+
+            var updateErr = bevy_axon_ffi_update(_client, Time.deltaTime, out _, out _);
+            if (updateErr != AxonFfiError.Ok) Debug.LogWarning($"Update failed: {updateErr} {GetErrorMessage()}");
+
+            AxonFfiError actionErr;
+            while ((actionErr = bevy_axon_ffi_next_action(_client, out var act, out var id, out var t, out var payload,
+                       out var payloadLen)) == AxonFfiError.Ok)
             {
-                var handle = GCHandle.Alloc(_invoke.GetBuffer(), GCHandleType.Pinned);
+                var d = new byte[(int)payloadLen];
+                if (d.Length > 0) Marshal.Copy(payload, d, 0, d.Length);
                 try
                 {
-                    bevy_axon_ffi_invoke(_client, handle.AddrOfPinnedObject(), _invoke.Length);
+                    OnAction(act, id, t, d);
                 }
-                finally
+                catch (Exception e)
                 {
-                    handle.Free();
+                    Debug.LogError(e);
                 }
+            }
+
+            if (actionErr != AxonFfiError.End)
+                Debug.LogWarning($"Next action failed: {actionErr} {GetErrorMessage()}");
+        }
 
-                _invoke.SetLength(0);
-                _invoke.Position = 0;
+        private void Send(MemoryStream stream, byte channel)
+        {
+            if (stream.Length == 0) return;
+            var handle = GCHandle.Alloc(stream.GetBuffer(), GCHandleType.Pinned);
+            try
+            {
+                var err = bevy_axon_ffi_invoke_on(_client, channel, handle.AddrOfPinnedObject(),
+                    (UIntPtr)stream.Length);
+                if (err != AxonFfiError.Ok) Debug.LogWarning($"Invoke failed: {err} {GetErrorMessage()}");
+            }
+            finally
+            {
+                handle.Free();
             }
 
-            var raw = bevy_axon_ffi_update(_client, Time.deltaTime, out var len);
-            if (raw == IntPtr.Zero || len <= 0) return;
-            _raw.SetLength((int)len);
-            Marshal.Copy(raw, _raw.GetBuffer(), 0, (int)len);
-            OnRawData(new ArraySegment<byte>(_raw.GetBuffer(), 0, (int)len));
+            stream.SetLength(0);
+            stream.Position = 0;
         }
 
-        private readonly MemoryStream _raw = new(20480);
         private readonly MemoryStream _invoke = new(20480);
+        private readonly MemoryStream _invokeUnreliable = new(2048);
         private readonly MemoryStream _value = new(20480);
 
         public void Invoke(uint type, object data)
         {
-            var sb = new SbinWriter(_invoke);
+            Write(_invoke, type, data);
+        }
+
+        // 走不可靠通道，适合每帧发送、丢失无妨的输入
+        public void InvokeUnreliable(uint type, object data)
+        {
+            Write(_invokeUnreliable, type, data);
+        }
+
+        private void Write(MemoryStream stream, uint type, object data)
+        {
+            var sb = new SbinWriter(stream);
             sb.WriteU8(ActionTypeInvoke);
             sb.WriteU64(0);
             sb.WriteU32(type);
@@ -232,7 +378,7 @@ namespace Bevy
 #else
         [DllImport("bevy_axon", CallingConvention = CallingConvention.Cdecl)]
 #endif
-        private static extern IntPtr bevy_axon_ffi_create(IntPtr addr, ulong clientId);
+        private static extern AxonFfiError bevy_axon_ffi_create(IntPtr addr, ulong clientId, out IntPtr game);
 
 #if (UNITY_WEBGL || UNITY_IPHONE) && !UNITY_EDITOR
         [DllImport("__Internal")]
@@ -253,20 +399,58 @@ namespace Bevy
 #else
         [DllImport("bevy_axon", CallingConvention = CallingConvention.Cdecl)]
 #endif
-        private static extern IntPtr bevy_axon_ffi_update(IntPtr ptr, float dt, out long len);
+        private static extern AxonFfiError bevy_axon_ffi_disconnect(IntPtr ptr);
+
+#if (UNITY_WEBGL || UNITY_IPHONE) && !UNITY_EDITOR
+        [DllImport("__Internal")]
+#else
+        [DllImport("bevy_axon", CallingConvention = CallingConvention.Cdecl)]
+#endif
+        private static extern AxonFfiState bevy_axon_ffi_state(IntPtr ptr);
+
+#if (UNITY_WEBGL || UNITY_IPHONE) && !UNITY_EDITOR
+        [DllImport("__Internal")]
+#else
+        [DllImport("bevy_axon", CallingConvention = CallingConvention.Cdecl)]
+#endif
+        private static extern AxonDisconnectReason bevy_axon_ffi_disconnect_reason(IntPtr ptr);
+
+#if (UNITY_WEBGL || UNITY_IPHONE) && !UNITY_EDITOR
+        [DllImport("__Internal")]
+#else
+        [DllImport("bevy_axon", CallingConvention = CallingConvention.Cdecl)]
+#endif
+        private static extern AxonFfiError bevy_axon_ffi_stats(IntPtr ptr, out AxonNetStats stats);
+
+#if (UNITY_WEBGL || UNITY_IPHONE) && !UNITY_EDITOR
+        [DllImport("__Internal")]
+#else
+        [DllImport("bevy_axon", CallingConvention = CallingConvention.Cdecl)]
+#endif
+        private static extern AxonFfiError bevy_axon_ffi_update(IntPtr ptr, float dt, out IntPtr data,
+            out UIntPtr len);
+
+#if (UNITY_WEBGL || UNITY_IPHONE) && !UNITY_EDITOR
+        [DllImport("__Internal")]
+#else
+        [DllImport("bevy_axon", CallingConvention = CallingConvention.Cdecl)]
+#endif
+        private static extern AxonFfiError bevy_axon_ffi_next_action(IntPtr ptr, out byte act, out ulong id,
+            out uint type, out IntPtr payload, out UIntPtr len);
 
 #if (UNITY_WEBGL || UNITY_IPHONE) && !UNITY_EDITOR
         [DllImport("__Internal")]
 #else
         [DllImport("bevy_axon", CallingConvention = CallingConvention.Cdecl)]
 #endif
-        private static extern void bevy_axon_ffi_invoke(IntPtr ptr, IntPtr raw, long len);
+        private static extern AxonFfiError bevy_axon_ffi_invoke_on(IntPtr ptr, byte channel, IntPtr raw,
+            UIntPtr len);
 
 #if (UNITY_WEBGL || UNITY_IPHONE) && !UNITY_EDITOR
         [DllImport("__Internal")]
 #else
         [DllImport("bevy_axon", CallingConvention = CallingConvention.Cdecl)]
 #endif
-        private static extern IntPtr bevy_axon_ffi_errmsg();
+        private static extern IntPtr bevy_axon_ffi_errmsg(IntPtr game);
     }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+using System.Collections.Generic;
 using UnityEngine;
 using UnityEngine.Serialization;
 
@@ -10,14 +11,25 @@ namespace Bevy
 
         public ulong Id { get; set; }
 
+        private readonly Dictionary<uint, object> _values = new();
+
         public void SetValue(uint type, object data)
         {
+            _values[type] = data;
             foreach (var dst in GetComponentsInChildren<IBevyBehaviour>(true))
             {
                 dst.SetValue(type, data);
             }
         }
 
+        public void CopyValuesTo(BevyObject other)
+        {
+            foreach (var kv in _values)
+            {
+                other.SetValue(kv.Key, kv.Value);
+            }
+        }
+
         public void Invoke(uint type, object data)
         {
             if (data == null) return;